@@ -0,0 +1,322 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, State};
+
+use crate::error::LauncherError;
+use crate::offline::unix_now;
+use crate::persist::write_atomic;
+use crate::token_manager::TokenManager;
+use crate::token_store::{TokenState, UNASSIGNED};
+use crate::{decode_launcher_token, StoredToken, UserInfo};
+
+const ACCOUNTS_FILE: &str = "accounts.json";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AccountRole {
+    pub name: String,
+    pub color: String,
+}
+
+/// An account that has logged in on this machine. Its token lives in the
+/// token store under `account_id`. Serialized in the same shape as the
+/// `login-success` payload so the webview can treat them alike.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Account {
+    #[serde(rename = "accountId")]
+    pub account_id: String,
+    pub username: String,
+    pub email: String,
+    pub avatar_url: String,
+    #[serde(rename = "favoriteSkin")]
+    pub favorite_skin: String,
+    pub role: AccountRole,
+    pub added_at: u64,
+    /// When it last launched a game.
+    pub last_used_at: Option<u64>,
+}
+
+impl Account {
+    fn from_user(user: &UserInfo, now: u64) -> Self {
+        Account {
+            account_id: user.account_id.clone(),
+            username: user.username.clone(),
+            email: user.email.clone(),
+            avatar_url: user.avatar_url.clone(),
+            favorite_skin: user.favorite_skin.clone(),
+            role: AccountRole {
+                name: user.role.name.clone(),
+                color: user.role.color.clone(),
+            },
+            added_at: now,
+            last_used_at: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AccountRegistry {
+    /// The account launches and backend calls act as.
+    pub active: Option<String>,
+    pub accounts: Vec<Account>,
+}
+
+impl AccountRegistry {
+    pub fn get(&self, account_id: &str) -> Option<&Account> {
+        self.accounts.iter().find(|account| account.account_id == account_id)
+    }
+
+    /// Adds `account`, or refreshes what is known about it if it was already
+    /// here, keeping when it was added and last used.
+    fn upsert(&mut self, mut account: Account) -> Account {
+        match self
+            .accounts
+            .iter_mut()
+            .find(|known| known.account_id == account.account_id)
+        {
+            Some(known) => {
+                account.added_at = known.added_at;
+                account.last_used_at = known.last_used_at;
+                *known = account.clone();
+            }
+            None => self.accounts.push(account.clone()),
+        }
+        account
+    }
+
+    /// Removing the active account leaves nobody active; switching to
+    /// another one is up to the user.
+    fn remove(&mut self, account_id: &str) -> Option<Account> {
+        let index = self
+            .accounts
+            .iter()
+            .position(|account| account.account_id == account_id)?;
+        if self.active.as_deref() == Some(account_id) {
+            self.active = None;
+        }
+        Some(self.accounts.remove(index))
+    }
+}
+
+pub struct AccountState(pub Mutex<AccountRegistry>);
+
+impl AccountState {
+    pub fn load(app_handle: &AppHandle) -> Self {
+        let registry = fs::read_to_string(accounts_path(app_handle))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        AccountState(Mutex::new(registry))
+    }
+}
+
+fn accounts_path(app_handle: &AppHandle) -> PathBuf {
+    app_handle.path().app_data_dir().unwrap().join(ACCOUNTS_FILE)
+}
+
+fn save_accounts(registry: &AccountRegistry, app_handle: &AppHandle) -> Result<(), LauncherError> {
+    let content = serde_json::to_string_pretty(registry)?;
+    write_atomic(&accounts_path(app_handle), content).map_err(|e| LauncherError::io("Failed to save accounts", e))
+}
+
+/// The account launches and backend calls act as, if anyone is logged in.
+pub fn active_account(app_handle: &AppHandle) -> Option<String> {
+    app_handle.state::<AccountState>().0.lock().unwrap().active.clone()
+}
+
+/// Stores `stored` for the account `user` describes and switches to it. An
+/// account that was already known gets the new token; the others are left
+/// alone.
+pub fn add_account(app_handle: &AppHandle, user: &UserInfo, stored: &StoredToken) -> Result<Account, LauncherError> {
+    // Token first, so the registry never lists an account it can't act as.
+    app_handle
+        .state::<TokenState>()
+        .save(&user.account_id, stored)?;
+
+    let account = {
+        let state = app_handle.state::<AccountState>();
+        let mut registry = state.0.lock().unwrap();
+        let account = registry.upsert(Account::from_user(user, unix_now()));
+        registry.active = Some(account.account_id.clone());
+        save_accounts(&registry, app_handle)?;
+        account
+    };
+    app_handle.state::<TokenManager>().sync(app_handle);
+    Ok(account)
+}
+
+/// Forgets `account_id` and its token.
+pub fn forget_account(app_handle: &AppHandle, account_id: &str) -> Result<Account, LauncherError> {
+    let account = {
+        let state = app_handle.state::<AccountState>();
+        let mut registry = state.0.lock().unwrap();
+        let account = registry
+            .remove(account_id)
+            .ok_or_else(|| LauncherError::NotFound(format!("No account {}", account_id)))?;
+        save_accounts(&registry, app_handle)?;
+        account
+    };
+    let cleared = app_handle.state::<TokenState>().clear(account_id);
+    app_handle.state::<TokenManager>().sync(app_handle);
+    cleared?;
+    Ok(account)
+}
+
+/// Notes that `account_id` just launched a game.
+pub fn touch_account(app_handle: &AppHandle, account_id: &str) {
+    let state = app_handle.state::<AccountState>();
+    let mut registry = state.0.lock().unwrap();
+    let Some(account) = registry
+        .accounts
+        .iter_mut()
+        .find(|account| account.account_id == account_id)
+    else {
+        return;
+    };
+    account.last_used_at = Some(unix_now());
+    if let Err(e) = save_accounts(&registry, app_handle) {
+        println!("{}", e);
+    }
+}
+
+/// Gives a token stored before accounts existed to the account it belongs
+/// to. It stays unassigned, to try again next start, if the backend can't
+/// say whose it is.
+pub async fn adopt_unassigned_token(app_handle: AppHandle) {
+    let tokens = app_handle.state::<TokenState>();
+    let Some(stored) = tokens.get(UNASSIGNED) else {
+        return;
+    };
+    let user = match decode_launcher_token(&stored.token).await {
+        Ok(user) => user,
+        Err(e) => {
+            println!("Couldn't tell whose the stored token is yet: {}", e);
+            return;
+        }
+    };
+    if let Err(e) = tokens.save(&user.account_id, &stored) {
+        println!("Failed to move the stored token to its account: {}", e);
+        return;
+    }
+    {
+        let state = app_handle.state::<AccountState>();
+        let mut registry = state.0.lock().unwrap();
+        let account = registry.upsert(Account::from_user(&user, stored.stored_at));
+        registry.active.get_or_insert(account.account_id);
+        if let Err(e) = save_accounts(&registry, &app_handle) {
+            println!("{}", e);
+        }
+    }
+    if let Err(e) = tokens.clear(UNASSIGNED) {
+        println!("Failed to remove the unassigned token: {}", e);
+    }
+    println!("Moved the stored token to its account");
+    app_handle.state::<TokenManager>().sync(&app_handle);
+}
+
+#[tauri::command]
+pub async fn list_accounts(state: State<'_, AccountState>) -> Result<AccountRegistry, LauncherError> {
+    Ok(state.0.lock().unwrap().clone())
+}
+
+#[tauri::command]
+pub async fn switch_account(account_id: String, app_handle: AppHandle) -> Result<Account, LauncherError> {
+    let account = {
+        let state = app_handle.state::<AccountState>();
+        let mut registry = state.0.lock().unwrap();
+        let account = registry
+            .get(&account_id)
+            .cloned()
+            .ok_or_else(|| LauncherError::NotFound(format!("No account {}", account_id)))?;
+        registry.active = Some(account_id);
+        save_accounts(&registry, &app_handle)?;
+        account
+    };
+    app_handle.state::<TokenManager>().sync(&app_handle);
+    Ok(account)
+}
+
+#[tauri::command]
+pub async fn remove_account(account_id: String, app_handle: AppHandle) -> Result<(), LauncherError> {
+    forget_account(&app_handle, &account_id)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account(account_id: &str, username: &str, added_at: u64) -> Account {
+        Account {
+            account_id: account_id.to_string(),
+            username: username.to_string(),
+            added_at,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn upsert_adds_new_accounts() {
+        let mut registry = AccountRegistry::default();
+        registry.upsert(account("a", "first", 100));
+        let added = registry.upsert(account("b", "second", 200));
+
+        assert_eq!(added.added_at, 200);
+        let ids: Vec<&str> = registry.accounts.iter().map(|a| a.account_id.as_str()).collect();
+        assert_eq!(ids, ["a", "b"]);
+        assert_eq!(registry.active, None);
+    }
+
+    #[test]
+    fn upsert_refreshes_a_known_account_but_keeps_its_history() {
+        let mut registry = AccountRegistry::default();
+        registry.upsert(account("a", "old name", 100));
+        registry.accounts[0].last_used_at = Some(150);
+
+        let refreshed = registry.upsert(account("a", "new name", 300));
+        assert_eq!((refreshed.added_at, refreshed.last_used_at), (100, Some(150)));
+        assert_eq!(registry.accounts.len(), 1);
+        let stored = registry.get("a").unwrap();
+        assert_eq!(stored.username, "new name");
+        assert_eq!((stored.added_at, stored.last_used_at), (100, Some(150)));
+    }
+
+    #[test]
+    fn removing_the_active_account_leaves_nobody_active() {
+        let mut registry = AccountRegistry::default();
+        registry.upsert(account("a", "first", 100));
+        registry.upsert(account("b", "second", 200));
+        registry.active = Some("a".to_string());
+
+        assert_eq!(registry.remove("a").map(|a| a.username), Some("first".to_string()));
+        assert_eq!(registry.active, None);
+        assert!(registry.get("a").is_none());
+        assert!(registry.get("b").is_some());
+    }
+
+    #[test]
+    fn removing_another_account_keeps_the_active_one() {
+        let mut registry = AccountRegistry::default();
+        registry.upsert(account("a", "first", 100));
+        registry.upsert(account("b", "second", 200));
+        registry.active = Some("a".to_string());
+
+        assert!(registry.remove("b").is_some());
+        assert_eq!(registry.active.as_deref(), Some("a"));
+        assert!(registry.remove("missing").is_none());
+        assert_eq!(registry.accounts.len(), 1);
+    }
+
+    #[test]
+    fn loads_registries_saved_before_last_used_was_tracked() {
+        let registry: AccountRegistry = serde_json::from_str(
+            r#"{"active":"a","accounts":[{"accountId":"a","username":"first","added_at":100}]}"#,
+        )
+        .unwrap();
+        let account = registry.get("a").unwrap();
+        assert_eq!((account.added_at, account.last_used_at), (100, None));
+    }
+}
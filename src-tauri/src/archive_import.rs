@@ -0,0 +1,456 @@
+use serde::Serialize;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter};
+
+use crate::downloader::safe_join;
+use crate::error::LauncherError;
+use crate::library_scan::find_build_roots;
+use crate::preflight::available_space;
+use crate::register_version;
+
+// The biggest builds are around 100 GiB; anything past this is treated as a
+// bomb even when the disk could hold it.
+const MAX_TOTAL_SIZE: u64 = 200 * 1024 * 1024 * 1024;
+const MAX_ENTRIES: usize = 500_000;
+// No entry may expand more than this many times. Deflate tops out just
+// above 1000:1, so only crafted entries go past it.
+const MAX_COMPRESSION_RATIO: u64 = 1100;
+// The build folder may sit this many levels below the archive root.
+const MAX_BUILD_DEPTH: usize = 2;
+const SEVEN_ZIP_CANDIDATES: &[&str] = &[
+    "7z",
+    "7za",
+    "C:\\Program Files\\7-Zip\\7z.exe",
+    "C:\\Program Files (x86)\\7-Zip\\7z.exe",
+];
+
+#[derive(Debug, Clone, Serialize)]
+struct ImportProgress {
+    archive: String,
+    extracted_bytes: u64,
+    total_bytes: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportedBuild {
+    pub path: String,
+}
+
+struct ArchiveEntry {
+    path: String,
+    size: u64,
+    compressed_size: Option<u64>,
+}
+
+/// Applies the traversal and zip-bomb limits to an archive listing and
+/// returns the total uncompressed size. `free_space` is what the disk being
+/// extracted to has left, when that is known.
+fn check_entries(entries: &[ArchiveEntry], free_space: Option<u64>) -> Result<u64, LauncherError> {
+    if entries.len() > MAX_ENTRIES {
+        return Err(LauncherError::InvalidInput(format!(
+            "Archive has {} entries, refusing to extract",
+            entries.len()
+        )));
+    }
+
+    let mut total = 0u64;
+    for entry in entries {
+        safe_join(Path::new(""), &entry.path)?;
+
+        if let Some(compressed) = entry.compressed_size {
+            if entry.size / compressed.max(1) > MAX_COMPRESSION_RATIO {
+                return Err(LauncherError::InvalidInput(format!(
+                    "{} has a suspicious compression ratio",
+                    entry.path
+                )));
+            }
+        }
+
+        total = total.saturating_add(entry.size);
+        if total > MAX_TOTAL_SIZE {
+            return Err(LauncherError::InvalidInput("Archive expands past the size limit".to_string()));
+        }
+    }
+    if let Some(free) = free_space.filter(|&free| total > free) {
+        return Err(LauncherError::InvalidInput(format!(
+            "Archive expands to {} MB but only {} MB is free",
+            total / (1024 * 1024),
+            free / (1024 * 1024)
+        )));
+    }
+    Ok(total)
+}
+
+struct CountingReader<'a, R, F: FnMut(u64)> {
+    inner: R,
+    on_read: &'a mut F,
+}
+
+impl<R: Read, F: FnMut(u64)> Read for CountingReader<'_, R, F> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        (self.on_read)(read as u64);
+        Ok(read)
+    }
+}
+
+fn corrupt_zip(e: zip::result::ZipError) -> LauncherError {
+    LauncherError::CorruptBuild(format!("Failed to read zip: {}", e))
+}
+
+fn extract_zip<F: FnMut(u64, u64)>(
+    archive_path: &Path,
+    staging: &Path,
+    mut on_progress: F,
+) -> Result<(), LauncherError> {
+    let file = File::open(archive_path).map_err(|e| LauncherError::io("Failed to open archive", e))?;
+    let mut archive = zip::ZipArchive::new(file).map_err(corrupt_zip)?;
+
+    let mut entries = Vec::with_capacity(archive.len());
+    for index in 0..archive.len() {
+        let entry = archive.by_index_raw(index).map_err(corrupt_zip)?;
+        entries.push(ArchiveEntry {
+            path: entry.name().to_string(),
+            size: entry.size(),
+            compressed_size: Some(entry.compressed_size()),
+        });
+    }
+    let total = check_entries(&entries, available_space(staging))?;
+
+    let mut extracted = 0u64;
+    // Reads are a few KB each; report whole percents, like 7-Zip does.
+    let mut reported_percent = None;
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index).map_err(corrupt_zip)?;
+        let relative = entry
+            .enclosed_name()
+            .ok_or_else(|| LauncherError::InvalidInput(format!("Refusing unsafe path in archive: {}", entry.name())))?;
+        let target = staging.join(relative);
+
+        if entry.is_dir() {
+            fs::create_dir_all(&target)
+                .map_err(|e| LauncherError::io(format!("Failed to create {}", target.display()), e))?;
+            continue;
+        }
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| LauncherError::io(format!("Failed to create {}", parent.display()), e))?;
+        }
+
+        // Never write more than the header promised, whatever the stream says.
+        let declared = entry.size();
+        let mut out =
+            File::create(&target).map_err(|e| LauncherError::io(format!("Failed to create {}", target.display()), e))?;
+        let mut on_read = |read: u64| {
+            extracted += read;
+            let percent = extracted * 100 / total.max(1);
+            if reported_percent != Some(percent) {
+                reported_percent = Some(percent);
+                on_progress(extracted, total);
+            }
+        };
+        let mut reader = CountingReader {
+            inner: (&mut entry).take(declared + 1),
+            on_read: &mut on_read,
+        };
+        let written = io::copy(&mut reader, &mut out)
+            .map_err(|e| LauncherError::io(format!("Failed to extract {}", target.display()), e))?;
+        if written != declared {
+            return Err(LauncherError::CorruptBuild(format!(
+                "{} does not match its declared size",
+                target.display()
+            )));
+        }
+        out.flush()?;
+    }
+
+    if reported_percent != Some(100) {
+        on_progress(total, total);
+    }
+    Ok(())
+}
+
+fn find_seven_zip() -> Option<&'static str> {
+    SEVEN_ZIP_CANDIDATES.iter().copied().find(|candidate| {
+        Command::new(candidate)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .is_ok()
+    })
+}
+
+fn list_with_seven_zip(seven_zip: &str, archive_path: &Path) -> Result<Vec<ArchiveEntry>, LauncherError> {
+    let output = Command::new(seven_zip)
+        .args(["l", "-slt", "-ba"])
+        .arg(archive_path)
+        .output()
+        .map_err(|e| LauncherError::io("Failed to run 7-Zip", e))?;
+    if !output.status.success() {
+        return Err(LauncherError::CorruptBuild(format!(
+            "7-Zip could not list the archive: {}",
+            String::from_utf8_lossy(&output.stderr)
+        )));
+    }
+
+    let mut entries = Vec::new();
+    let mut current: Option<ArchiveEntry> = None;
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        if let Some(path) = line.strip_prefix("Path = ") {
+            entries.extend(current.take());
+            current = Some(ArchiveEntry {
+                path: path.replace('\\', "/"),
+                size: 0,
+                compressed_size: None,
+            });
+        } else if let (Some(entry), Some(size)) = (current.as_mut(), line.strip_prefix("Size = ")) {
+            entry.size = size.trim().parse().unwrap_or(0);
+        }
+    }
+    entries.extend(current);
+    Ok(entries)
+}
+
+fn extract_with_seven_zip<F: FnMut(u64, u64)>(
+    archive_path: &Path,
+    staging: &Path,
+    mut on_progress: F,
+) -> Result<(), LauncherError> {
+    let seven_zip = find_seven_zip()
+        .ok_or_else(|| LauncherError::NotFound("7-Zip is required to import .7z and .rar archives".to_string()))?;
+    let total = check_entries(&list_with_seven_zip(seven_zip, archive_path)?, available_space(staging))?;
+
+    let mut child = Command::new(seven_zip)
+        .args(["x", "-y", "-bso0", "-bsp1"])
+        .arg(format!("-o{}", staging.display()))
+        .arg(archive_path)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| LauncherError::io("Failed to run 7-Zip", e))?;
+
+    // Read alongside stdout, or 7-Zip blocks once the stderr pipe fills up.
+    let stderr = child.stderr.take().map(|mut stderr| {
+        thread::spawn(move || {
+            let mut errors = String::new();
+            let _ = stderr.read_to_string(&mut errors);
+            errors
+        })
+    });
+
+    // 7-Zip redraws its percentage with backspaces rather than newlines.
+    if let Some(mut stdout) = child.stdout.take() {
+        let mut buffer = [0u8; 4096];
+        let mut pending = String::new();
+        while let Ok(read) = stdout.read(&mut buffer) {
+            if read == 0 {
+                break;
+            }
+            pending.push_str(&String::from_utf8_lossy(&buffer[..read]));
+            let parts: Vec<&str> = pending.split(['\u{8}', '\r', '\n']).collect();
+            for part in &parts[..parts.len() - 1] {
+                if let Some(percent) = part.trim().split('%').next().and_then(|p| p.trim().parse::<u64>().ok()) {
+                    on_progress(total * percent.min(100) / 100, total);
+                }
+            }
+            pending = parts[parts.len() - 1].to_string();
+        }
+    }
+
+    let status = child.wait().map_err(|e| LauncherError::io("Failed to run 7-Zip", e))?;
+    let errors = stderr.and_then(|reader| reader.join().ok()).unwrap_or_default();
+    if !status.success() {
+        return Err(LauncherError::CorruptBuild(format!("7-Zip failed: {}", errors)));
+    }
+    on_progress(total, total);
+    Ok(())
+}
+
+fn is_seven_zip_format(path: &Path) -> bool {
+    matches!(
+        path.extension().map(|e| e.to_string_lossy().to_lowercase()).as_deref(),
+        Some("7z") | Some("rar")
+    )
+}
+
+/// Extracts `archive_path` into a staging folder under `dest_root`, finds the
+/// build inside it and moves that build to `dest_root/<name>`. Nothing is
+/// left behind in `dest_root` if any step fails.
+pub fn extract_build<F: FnMut(u64, u64)>(
+    archive_path: &Path,
+    dest_root: &Path,
+    on_progress: F,
+) -> Result<PathBuf, LauncherError> {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    let staging = dest_root.join(format!(".rewind-import-{:x}", nanos));
+    fs::create_dir_all(&staging).map_err(|e| LauncherError::io(format!("Failed to create {}", staging.display()), e))?;
+
+    let result = (|| {
+        if is_seven_zip_format(archive_path) {
+            extract_with_seven_zip(archive_path, &staging, on_progress)?;
+        } else {
+            extract_zip(archive_path, &staging, on_progress)?;
+        }
+
+        let roots = find_build_roots(&staging, MAX_BUILD_DEPTH);
+        let build_root = match roots.as_slice() {
+            [root] => root.clone(),
+            [] => return Err(LauncherError::NotFound("No Fortnite build found in the archive".to_string())),
+            _ => {
+                return Err(LauncherError::InvalidInput(
+                    "The archive contains more than one build".to_string(),
+                ))
+            }
+        };
+
+        let name = if build_root == staging {
+            archive_path.file_stem().map(|s| s.to_os_string())
+        } else {
+            build_root.file_name().map(|s| s.to_os_string())
+        }
+        .ok_or_else(|| LauncherError::InvalidInput("Could not name the imported build".to_string()))?;
+
+        let final_path = dest_root.join(name);
+        if final_path.exists() {
+            return Err(LauncherError::InvalidInput(format!("{} already exists", final_path.display())));
+        }
+        fs::rename(&build_root, &final_path).map_err(|e| LauncherError::io("Failed to move build into place", e))?;
+        Ok(final_path)
+    })();
+
+    if staging.exists() {
+        if let Err(e) = fs::remove_dir_all(&staging) {
+            println!("Failed to clean up {}: {}", staging.display(), e);
+        }
+    }
+
+    result
+}
+
+#[tauri::command]
+pub async fn import_archive(
+    archive_path: String,
+    dest_root: String,
+    app_handle: AppHandle,
+) -> Result<ImportedBuild, LauncherError> {
+    let archive = PathBuf::from(&archive_path);
+    if !archive.is_file() {
+        return Err(LauncherError::NotFound(format!("{} is not a file", archive_path)));
+    }
+    let dest = PathBuf::from(&dest_root);
+    if !dest.is_dir() {
+        return Err(LauncherError::NotFound(format!("{} is not a directory", dest_root)));
+    }
+
+    let progress_handle = app_handle.clone();
+    let final_path = tokio::task::spawn_blocking(move || {
+        extract_build(&archive, &dest, |extracted_bytes, total_bytes| {
+            let _ = progress_handle.emit("archive-import-progress", ImportProgress {
+                archive: archive_path.clone(),
+                extracted_bytes,
+                total_bytes,
+            });
+        })
+    })
+    .await??;
+
+    let path = final_path.to_string_lossy().to_string();
+    if let Err(e) = register_version(path.clone(), &app_handle).await {
+        if let Err(cleanup) = fs::remove_dir_all(&final_path) {
+            println!("Failed to roll back {}: {}", final_path.display(), cleanup);
+        }
+        return Err(e);
+    }
+
+    Ok(ImportedBuild { path })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fs::TempDir;
+    use zip::write::SimpleFileOptions;
+
+    fn write_zip(path: &Path, entries: &[(&str, Vec<u8>)]) {
+        let mut zip = zip::ZipWriter::new(File::create(path).unwrap());
+        let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored);
+        for (name, data) in entries {
+            zip.start_file(*name, options).unwrap();
+            zip.write_all(data).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    #[test]
+    fn zip_progress_is_reported_per_percent() {
+        let dir = TempDir::new("import", "progress");
+        let archive = dir.join("build.zip");
+        let entries: Vec<(&str, Vec<u8>)> = vec![
+            ("a.bin", vec![1u8; 3 * 1024 * 1024]),
+            ("b/c.bin", vec![2u8; 1024 * 1024 + 17]),
+            ("empty.txt", Vec::new()),
+        ];
+        write_zip(&archive, &entries);
+        let staging = dir.join("staging");
+        fs::create_dir_all(&staging).unwrap();
+
+        let mut reports = Vec::new();
+        extract_zip(&archive, &staging, |extracted, total| reports.push((extracted, total))).unwrap();
+
+        let total = 4 * 1024 * 1024 + 17;
+        assert!(reports.len() <= 101, "{} progress reports", reports.len());
+        assert!(reports.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        assert_eq!(reports.last(), Some(&(total, total)));
+        assert_eq!(fs::read(staging.join("b/c.bin")).unwrap().len(), 1024 * 1024 + 17);
+    }
+
+    #[test]
+    fn rejects_entries_outside_the_archive_root() {
+        let entry = |path: &str| ArchiveEntry {
+            path: path.to_string(),
+            size: 10,
+            compressed_size: Some(10),
+        };
+        assert_eq!(check_entries(&[entry("FortniteGame/a"), entry("b")], None).unwrap(), 20);
+        assert!(check_entries(&[entry("../evil")], None).is_err());
+    }
+
+    #[test]
+    fn rejects_bombs_of_any_size() {
+        let entry = |size: u64, compressed_size: u64| ArchiveEntry {
+            path: "bomb".to_string(),
+            size,
+            compressed_size: Some(compressed_size),
+        };
+        assert!(check_entries(&[entry(10 * 1024 * 1024 * 1024, 1024)], None).is_err());
+        // Entries under 1 MiB are held to the same ratio.
+        assert!(check_entries(&[entry(512 * 1024, 4)], None).is_err());
+        assert!(check_entries(&[entry(512 * 1024, 1024)], None).is_ok());
+        assert!(check_entries(&[entry(0, 0)], None).is_ok());
+
+        let huge: Vec<ArchiveEntry> = (0..3).map(|_| entry(100 * 1024 * 1024 * 1024, 1024 * 1024 * 1024)).collect();
+        assert!(check_entries(&huge, None).is_err());
+    }
+
+    #[test]
+    fn rejects_archives_bigger_than_the_free_space() {
+        let entries = [ArchiveEntry {
+            path: "FortniteGame/pak".to_string(),
+            size: 2048,
+            compressed_size: None,
+        }];
+        assert_eq!(check_entries(&entries, Some(4096)).unwrap(), 2048);
+        match check_entries(&entries, Some(1024)) {
+            Err(LauncherError::InvalidInput(message)) => assert!(message.contains("free")),
+            other => panic!("archive bigger than the disk was accepted: {:?}", other),
+        }
+    }
+}
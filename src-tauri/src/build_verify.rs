@@ -0,0 +1,444 @@
+use futures_util::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter};
+
+use crate::downloader::safe_join;
+use crate::error::LauncherError;
+
+pub const BUILDS_URL: &str = "your_builds_url";
+const HASH_WORKERS: usize = 4;
+const HASH_BUFFER_SIZE: usize = 1024 * 1024;
+// Folders the game writes to at runtime; never part of a manifest.
+const EXCLUDED_DIRS: &[&str] = &["FortniteGame/Saved"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub path: String,
+    pub size: u64,
+    pub sha256: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BuildManifest {
+    #[serde(default)]
+    pub build: Option<String>,
+    pub files: Vec<ManifestEntry>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ManifestSource {
+    File { path: String },
+    Remote { build: String },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FileMismatch {
+    pub path: String,
+    pub expected_size: u64,
+    pub actual_size: u64,
+    pub expected_sha256: String,
+    pub actual_sha256: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct VerifyReport {
+    pub ok: bool,
+    pub verified: usize,
+    pub missing: Vec<String>,
+    pub extra: Vec<String>,
+    pub mismatched: Vec<FileMismatch>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct HashProgress {
+    build_path: String,
+    file: String,
+    done: usize,
+    total: usize,
+}
+
+pub fn hash_file(path: &Path) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; HASH_BUFFER_SIZE];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Lists every file under `root` as a `/`-separated relative path, skipping
+/// `EXCLUDED_DIRS`. Symlinks are not followed.
+pub fn list_files(root: &Path) -> io::Result<Vec<String>> {
+    let mut files = Vec::new();
+    let mut stack = vec![root.to_path_buf()];
+
+    while let Some(dir) = stack.pop() {
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            let relative = relative_path(root, &path);
+            let file_type = entry.file_type()?;
+
+            if file_type.is_dir() {
+                if !EXCLUDED_DIRS.iter().any(|excluded| relative.eq_ignore_ascii_case(excluded)) {
+                    stack.push(path);
+                }
+            } else if file_type.is_file() {
+                files.push(relative);
+            }
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+fn relative_path(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn parent_dir(relative: &str) -> &str {
+    relative.rsplit_once('/').map(|(dir, _)| dir).unwrap_or("")
+}
+
+/// `relative` in the form paths are compared in: as is, except on Windows,
+/// where `Paks/A.pak` and `paks/a.pak` are the same file.
+fn path_key(relative: &str) -> String {
+    if cfg!(windows) {
+        relative.to_lowercase()
+    } else {
+        relative.to_string()
+    }
+}
+
+/// Compares `root` against `manifest`. Files of the wrong size are reported
+/// without being hashed. Files not in the manifest only count as extra when
+/// they sit in a folder the manifest covers, so user content elsewhere in the
+/// build is left alone. A manifest with a path that leads out of the build is
+/// refused as a whole.
+pub async fn verify_against_manifest<F>(
+    root: PathBuf,
+    manifest: &BuildManifest,
+    on_progress: F,
+) -> Result<VerifyReport, LauncherError>
+where
+    F: Fn(&str, usize, usize),
+{
+    let mut full_paths = HashMap::new();
+    for entry in &manifest.files {
+        let full_path = safe_join(&root, &entry.path).map_err(|_| {
+            LauncherError::CorruptBuild(format!("The manifest lists a path outside the build: {}", entry.path))
+        })?;
+        full_paths.insert(entry.path.as_str(), full_path);
+    }
+    let expected: HashSet<String> = manifest.files.iter().map(|entry| path_key(&entry.path)).collect();
+
+    let list_root = root.clone();
+    let on_disk = tokio::task::spawn_blocking(move || list_files(&list_root))
+        .await?
+        .map_err(|e| LauncherError::io("Failed to list build files", e))?;
+    let on_disk_set: HashSet<String> = on_disk.iter().map(|path| path_key(path)).collect();
+
+    let covered_dirs: HashSet<String> = manifest.files.iter().map(|e| path_key(parent_dir(&e.path))).collect();
+    let extra: Vec<String> = on_disk
+        .iter()
+        .filter(|path| !expected.contains(&path_key(path)) && covered_dirs.contains(&path_key(parent_dir(path))))
+        .cloned()
+        .collect();
+
+    let mut missing = Vec::new();
+    let mut mismatched = Vec::new();
+    let mut to_hash = Vec::new();
+
+    for entry in &manifest.files {
+        if !on_disk_set.contains(&path_key(&entry.path)) {
+            missing.push(entry.path.clone());
+            continue;
+        }
+        let full_path = full_paths[entry.path.as_str()].clone();
+        let actual_size = fs::metadata(&full_path).map(|m| m.len()).unwrap_or(0);
+        if actual_size != entry.size {
+            mismatched.push(FileMismatch {
+                path: entry.path.clone(),
+                expected_size: entry.size,
+                actual_size,
+                expected_sha256: entry.sha256.clone(),
+                actual_sha256: None,
+            });
+            continue;
+        }
+        to_hash.push((entry.clone(), full_path));
+    }
+
+    let total = to_hash.len();
+    let mut results = stream::iter(to_hash.into_iter().map(|(entry, full_path)| {
+        async move {
+            let hash = tokio::task::spawn_blocking(move || hash_file(&full_path)).await;
+            (entry, hash)
+        }
+    }))
+    .buffer_unordered(HASH_WORKERS);
+
+    let mut done = 0;
+    let mut verified = 0;
+    while let Some((entry, hash)) = results.next().await {
+        done += 1;
+        on_progress(&entry.path, done, total);
+
+        let actual = hash?.map_err(|e| LauncherError::io(format!("Failed to hash {}", entry.path), e))?;
+        if actual.eq_ignore_ascii_case(&entry.sha256) {
+            verified += 1;
+        } else {
+            mismatched.push(FileMismatch {
+                path: entry.path.clone(),
+                expected_size: entry.size,
+                actual_size: entry.size,
+                expected_sha256: entry.sha256,
+                actual_sha256: Some(actual),
+            });
+        }
+    }
+
+    mismatched.sort_by(|a, b| a.path.cmp(&b.path));
+
+    Ok(VerifyReport {
+        ok: missing.is_empty() && extra.is_empty() && mismatched.is_empty(),
+        verified,
+        missing,
+        extra,
+        mismatched,
+    })
+}
+
+/// Hashes every file under `root` into a manifest.
+pub async fn build_manifest<F>(root: PathBuf, on_progress: F) -> Result<BuildManifest, LauncherError>
+where
+    F: Fn(&str, usize, usize),
+{
+    let list_root = root.clone();
+    let files = tokio::task::spawn_blocking(move || list_files(&list_root))
+        .await?
+        .map_err(|e| LauncherError::io("Failed to list build files", e))?;
+
+    let total = files.len();
+    let mut results = stream::iter(files.into_iter().map(|relative| {
+        let full_path = root.join(&relative);
+        async move {
+            let entry = tokio::task::spawn_blocking(move || -> io::Result<(u64, String)> {
+                let size = fs::metadata(&full_path)?.len();
+                Ok((size, hash_file(&full_path)?))
+            })
+            .await;
+            (relative, entry)
+        }
+    }))
+    .buffer_unordered(HASH_WORKERS);
+
+    let mut entries = Vec::with_capacity(total);
+    let mut done = 0;
+    while let Some((relative, entry)) = results.next().await {
+        done += 1;
+        on_progress(&relative, done, total);
+
+        let (size, sha256) = entry?.map_err(|e| LauncherError::io(format!("Failed to hash {}", relative), e))?;
+        entries.push(ManifestEntry {
+            path: relative,
+            size,
+            sha256,
+        });
+    }
+
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(BuildManifest {
+        build: None,
+        files: entries,
+    })
+}
+
+async fn load_manifest(source: ManifestSource) -> Result<BuildManifest, LauncherError> {
+    match source {
+        ManifestSource::File { path } => {
+            let content = tokio::fs::read_to_string(&path)
+                .await
+                .map_err(|e| LauncherError::io(format!("Failed to read manifest {}", path), e))?;
+            Ok(serde_json::from_str(&content)?)
+        }
+        ManifestSource::Remote { build } => {
+            let url = format!("{}/{}/manifest", BUILDS_URL, urlencoding::encode(&build));
+            let response = reqwest::get(&url).await?;
+            if !response.status().is_success() {
+                return Err(LauncherError::api(response.status()));
+            }
+            Ok(response.json::<BuildManifest>().await?)
+        }
+    }
+}
+
+fn emit_progress(app_handle: &AppHandle, event: &str, build_path: &str, file: &str, done: usize, total: usize) {
+    let _ = app_handle.emit(event, HashProgress {
+        build_path: build_path.to_string(),
+        file: file.to_string(),
+        done,
+        total,
+    });
+}
+
+#[tauri::command]
+pub async fn verify_version(
+    path: String,
+    source: ManifestSource,
+    app_handle: AppHandle,
+) -> Result<VerifyReport, LauncherError> {
+    let root = PathBuf::from(&path);
+    if !root.is_dir() {
+        return Err(LauncherError::NotFound(format!("{} is not a directory", path)));
+    }
+
+    let manifest = load_manifest(source).await?;
+    verify_against_manifest(root, &manifest, |file, done, total| {
+        emit_progress(&app_handle, "verify-progress", &path, file, done, total)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn create_manifest(
+    path: String,
+    output_path: String,
+    app_handle: AppHandle,
+) -> Result<BuildManifest, LauncherError> {
+    let root = PathBuf::from(&path);
+    if !root.is_dir() {
+        return Err(LauncherError::NotFound(format!("{} is not a directory", path)));
+    }
+
+    let mut manifest = build_manifest(root, |file, done, total| {
+        emit_progress(&app_handle, "manifest-progress", &path, file, done, total)
+    })
+    .await?;
+    manifest.build = Path::new(&path)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string());
+
+    let content = serde_json::to_string_pretty(&manifest)?;
+    tokio::fs::write(&output_path, content)
+        .await
+        .map_err(|e| LauncherError::io("Failed to write manifest", e))?;
+
+    Ok(manifest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fs::{fake_build, TempDir};
+
+    fn entry(path: &str, data: &[u8]) -> ManifestEntry {
+        ManifestEntry {
+            path: path.to_string(),
+            size: data.len() as u64,
+            sha256: hex::encode(Sha256::digest(data)),
+        }
+    }
+
+    async fn verify(root: &Path, files: Vec<ManifestEntry>) -> Result<VerifyReport, LauncherError> {
+        let manifest = BuildManifest { build: None, files };
+        verify_against_manifest(root.to_path_buf(), &manifest, |_, _, _| {}).await
+    }
+
+    /// The fake build's files as a manifest would list them.
+    fn build_entries() -> Vec<ManifestEntry> {
+        vec![
+            entry("Engine/Build.version", b"{}"),
+            entry("FortniteGame/Binaries/Win64/FortniteClient-Win64-Shipping.exe", &[0u8; 300]),
+            entry("FortniteGame/Content/Paks/pakchunk0.pak", &[0u8; 1000]),
+        ]
+    }
+
+    #[tokio::test]
+    async fn a_matching_build_verifies() {
+        let dir = TempDir::new("verify", "ok");
+        let root = fake_build(&dir, "build", false);
+        fs::create_dir_all(root.join("FortniteGame/Saved")).unwrap();
+        fs::write(root.join("FortniteGame/Saved/log.txt"), "runtime").unwrap();
+
+        let report = verify(&root, build_entries()).await.unwrap();
+        assert!(report.ok);
+        assert_eq!(report.verified, 3);
+    }
+
+    #[tokio::test]
+    async fn reports_missing_and_extra_files() {
+        let dir = TempDir::new("verify", "missing-extra");
+        let root = fake_build(&dir, "build", false);
+        fs::remove_file(root.join("Engine/Build.version")).unwrap();
+        fs::write(root.join("FortniteGame/Content/Paks/pakchunk1.pak"), "extra").unwrap();
+        // Not in a folder the manifest covers, so not extra.
+        fs::write(root.join("notes.txt"), "mine").unwrap();
+
+        let report = verify(&root, build_entries()).await.unwrap();
+        assert!(!report.ok);
+        assert_eq!(report.missing, ["Engine/Build.version"]);
+        assert_eq!(report.extra, ["FortniteGame/Content/Paks/pakchunk1.pak"]);
+        assert_eq!(report.verified, 2);
+    }
+
+    #[tokio::test]
+    async fn reports_size_and_hash_mismatches() {
+        let dir = TempDir::new("verify", "mismatch");
+        let root = fake_build(&dir, "build", false);
+        fs::write(root.join("Engine/Build.version"), "{\"changed\":1}").unwrap();
+        fs::write(root.join("FortniteGame/Content/Paks/pakchunk0.pak"), vec![1u8; 1000]).unwrap();
+
+        let report = verify(&root, build_entries()).await.unwrap();
+        let sizes: Vec<(&str, u64, Option<&String>)> = report
+            .mismatched
+            .iter()
+            .map(|m| (m.path.as_str(), m.actual_size, m.actual_sha256.as_ref()))
+            .collect();
+        assert_eq!(sizes.len(), 2);
+        assert_eq!(sizes[0], ("Engine/Build.version", 13, None));
+        assert_eq!(sizes[1].0, "FortniteGame/Content/Paks/pakchunk0.pak");
+        assert_eq!(sizes[1].2, Some(&hex::encode(Sha256::digest([1u8; 1000]))));
+        assert_eq!(report.verified, 1);
+    }
+
+    #[tokio::test]
+    async fn refuses_paths_outside_the_build() {
+        let dir = TempDir::new("verify", "traversal");
+        let root = fake_build(&dir, "build", false);
+        fs::write(dir.join("secret.txt"), "not the build's").unwrap();
+
+        for path in ["../secret.txt", "/etc/passwd", ""] {
+            let mut files = build_entries();
+            files.push(entry(path, b"not the build's"));
+            assert!(
+                matches!(verify(&root, files).await, Err(LauncherError::CorruptBuild(_))),
+                "{} was accepted",
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn paths_compare_like_the_file_system_does() {
+        assert_eq!(path_key("Paks/A.pak") == path_key("paks/a.pak"), cfg!(windows));
+        assert_eq!(path_key("Paks/A.pak"), path_key("Paks/A.pak"));
+    }
+}
@@ -0,0 +1,276 @@
+use serde::Serialize;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tauri::{AppHandle, Manager};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::error::LauncherError;
+use crate::session_log::{format_line, LogLine};
+use crate::StoredVersion;
+
+const CRASHES_DIR: &str = "FortniteGame/Saved/Crashes";
+pub const LOG_TAIL_LINES: usize = 500;
+// Minidumps can be large; anything past this is left out of the bundle.
+const MAX_BUNDLED_FILE_SIZE: u64 = 256 * 1024 * 1024;
+
+/// What was known about the session when it crashed. Written to the bundle
+/// as `context.json`.
+#[derive(Debug, Clone, Serialize)]
+pub struct CrashContext {
+    pub session_id: String,
+    pub build: String,
+    /// The account the session was launched as.
+    pub account_id: Option<String>,
+    pub version: Option<StoredVersion>,
+    pub args: Vec<String>,
+    /// The DLLs the launcher injected into the client.
+    pub injected_payloads: Vec<String>,
+    /// Every module loaded in the client as of the last snapshot, taken when
+    /// it started running and every half minute after. Anything loaded
+    /// after that snapshot is missing.
+    pub loaded_modules: Vec<String>,
+    pub exit_code: Option<i32>,
+    pub launcher_version: String,
+}
+
+/// Crash folders the engine wrote under `build_root` since `since`.
+pub fn new_crash_folders(build_root: &Path, since: SystemTime) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(build_root.join(CRASHES_DIR)) else {
+        return Vec::new();
+    };
+    let mut folders: Vec<PathBuf> = entries
+        .flatten()
+        .filter(|entry| {
+            let Ok(metadata) = entry.metadata() else {
+                return false;
+            };
+            metadata.is_dir() && metadata.modified().is_ok_and(|modified| modified >= since)
+        })
+        .map(|entry| entry.path())
+        .collect();
+    folders.sort();
+    folders
+}
+
+fn zip_failed(e: zip::result::ZipError) -> LauncherError {
+    match e {
+        zip::result::ZipError::Io(e) => LauncherError::io("Failed to write crash report", e),
+        e => LauncherError::Internal(format!("Failed to write crash report: {}", e)),
+    }
+}
+
+fn write_failed(e: io::Error) -> LauncherError {
+    LauncherError::io("Failed to write crash report", e)
+}
+
+fn add_folder<W: Write + io::Seek>(
+    zip: &mut ZipWriter<W>,
+    options: SimpleFileOptions,
+    folder: &Path,
+    prefix: &str,
+) -> Result<(), LauncherError> {
+    let mut stack = vec![folder.to_path_buf()];
+    while let Some(dir) = stack.pop() {
+        let listing = fs::read_dir(&dir).map_err(|e| LauncherError::io(format!("Failed to read {}", dir.display()), e))?;
+        for entry in listing.flatten() {
+            let path = entry.path();
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            if file_type.is_dir() {
+                stack.push(path);
+                continue;
+            }
+            if !file_type.is_file() {
+                continue;
+            }
+
+            let relative = path
+                .strip_prefix(folder)
+                .unwrap_or(&path)
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            let name = format!("{}/{}", prefix, relative);
+            if entry.metadata().map(|m| m.len()).unwrap_or(0) > MAX_BUNDLED_FILE_SIZE {
+                println!("Leaving {} out of the crash report, it is too big", path.display());
+                continue;
+            }
+
+            zip.start_file(name, options).map_err(zip_failed)?;
+            let mut source =
+                File::open(&path).map_err(|e| LauncherError::io(format!("Failed to read {}", path.display()), e))?;
+            io::copy(&mut source, zip).map_err(write_failed)?;
+        }
+    }
+    Ok(())
+}
+
+/// Zips the context, the log tail and every crash folder into `out`. The zip
+/// is written next to `out` first so a half-written report never appears.
+pub fn write_crash_bundle(
+    out: &Path,
+    context: &CrashContext,
+    crash_folders: &[PathBuf],
+    log_lines: &[LogLine],
+) -> Result<(), LauncherError> {
+    if let Some(parent) = out.parent() {
+        fs::create_dir_all(parent).map_err(write_failed)?;
+    }
+    let temp_path = out.with_extension("zip.tmp");
+
+    let result = (|| {
+        let file = File::create(&temp_path).map_err(|e| LauncherError::io("Failed to create crash report", e))?;
+        let mut zip = ZipWriter::new(file);
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+        zip.start_file("context.json", options).map_err(zip_failed)?;
+        let json = serde_json::to_string_pretty(context)?;
+        zip.write_all(json.as_bytes()).map_err(write_failed)?;
+
+        zip.start_file("session.log", options).map_err(zip_failed)?;
+        for line in log_lines {
+            zip.write_all(format_line(line).as_bytes()).map_err(write_failed)?;
+        }
+
+        for folder in crash_folders {
+            let name = folder
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| "crash".to_string());
+            add_folder(&mut zip, options, folder, &format!("crashes/{}", name))?;
+        }
+
+        let file = zip.finish().map_err(zip_failed)?;
+        file.sync_all().map_err(write_failed)
+    })();
+
+    match result {
+        Ok(()) => fs::rename(&temp_path, out).map_err(write_failed),
+        Err(e) => {
+            let _ = fs::remove_file(&temp_path);
+            Err(e)
+        }
+    }
+}
+
+pub fn crash_report_path(app_handle: &AppHandle, session_id: &str) -> Result<PathBuf, LauncherError> {
+    if session_id.is_empty() || !session_id.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(LauncherError::InvalidInput(format!("Invalid session id: {}", session_id)));
+    }
+    Ok(app_handle
+        .path()
+        .app_data_dir()
+        .unwrap()
+        .join("crashes")
+        .join(format!("{}.zip", session_id)))
+}
+
+/// Copies the session's crash report to `destination`, or shows it in the
+/// file manager when no destination is given. Returns where the report is.
+#[tauri::command]
+pub async fn export_crash_report(
+    session_id: String,
+    destination: Option<String>,
+    app_handle: AppHandle,
+) -> Result<String, LauncherError> {
+    let report = crash_report_path(&app_handle, &session_id)?;
+    if !report.is_file() {
+        return Err(LauncherError::NotFound(format!("No crash report for session {}", session_id)));
+    }
+
+    match destination {
+        Some(destination) => {
+            tokio::fs::copy(&report, &destination)
+                .await
+                .map_err(|e| LauncherError::io("Failed to export crash report", e))?;
+            Ok(destination)
+        }
+        None => {
+            tauri_plugin_opener::reveal_item_in_dir(&report)
+                .map_err(|e| LauncherError::Internal(format!("Failed to show crash report: {}", e)))?;
+            Ok(report.to_string_lossy().to_string())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fs::TempDir;
+    use crate::session_log::LogSource;
+    use std::io::Read;
+    use std::time::Duration;
+
+    fn context() -> CrashContext {
+        CrashContext {
+            session_id: "18f3a".to_string(),
+            build: "C:/Builds/8.51".to_string(),
+            account_id: Some("account".to_string()),
+            version: None,
+            args: vec!["-AUTH_PASSWORD=<redacted>".to_string()],
+            injected_payloads: vec!["C:/Rewind/payloads/client.dll".to_string()],
+            loaded_modules: vec![
+                "C:/Builds/8.51/FortniteGame/Binaries/Win64/FortniteClient-Win64-Shipping.exe".to_string(),
+                "C:/Rewind/payloads/client.dll".to_string(),
+            ],
+            exit_code: Some(-1073741819),
+            launcher_version: "1.0.0".to_string(),
+        }
+    }
+
+    #[test]
+    fn finds_only_crash_folders_written_since_launch() {
+        let build = TempDir::new("crash", "folders");
+        let crashes = build.join(CRASHES_DIR);
+        fs::create_dir_all(crashes.join("UE4CC-Windows-OLD")).unwrap();
+        let old = fs::metadata(crashes.join("UE4CC-Windows-OLD")).unwrap();
+        let since = old.modified().unwrap() + Duration::from_millis(1);
+        std::thread::sleep(Duration::from_millis(20));
+        fs::create_dir_all(crashes.join("UE4CC-Windows-B")).unwrap();
+        fs::create_dir_all(crashes.join("UE4CC-Windows-A")).unwrap();
+        fs::write(crashes.join("stray.txt"), "not a folder").unwrap();
+
+        assert_eq!(
+            new_crash_folders(&build, since),
+            [crashes.join("UE4CC-Windows-A"), crashes.join("UE4CC-Windows-B")]
+        );
+        assert!(new_crash_folders(&build.join("missing"), since).is_empty());
+    }
+
+    #[test]
+    fn bundles_context_log_and_crash_folders() {
+        let dir = TempDir::new("crash", "bundle");
+        let crash = dir.join("build").join(CRASHES_DIR).join("UE4CC-Windows-A");
+        fs::create_dir_all(crash.join("extra")).unwrap();
+        fs::write(crash.join("UE4Minidump.dmp"), "dump").unwrap();
+        fs::write(crash.join("extra/CrashContext.runtime-xml"), "<xml/>").unwrap();
+        let log = [LogLine {
+            timestamp_ms: 1,
+            source: LogSource::Stderr,
+            line: "Fatal error".to_string(),
+        }];
+
+        let out = dir.join("crashes/18f3a.zip");
+        write_crash_bundle(&out, &context(), &[crash], &log).unwrap();
+        assert!(!out.with_extension("zip.tmp").exists());
+
+        let mut zip = zip::ZipArchive::new(File::open(&out).unwrap()).unwrap();
+        let mut read = |name: &str| {
+            let mut content = String::new();
+            zip.by_name(name).unwrap().read_to_string(&mut content).unwrap();
+            content
+        };
+        let context: serde_json::Value = serde_json::from_str(&read("context.json")).unwrap();
+        assert_eq!(context["injected_payloads"][0], "C:/Rewind/payloads/client.dll");
+        assert_eq!(context["loaded_modules"][1], "C:/Rewind/payloads/client.dll");
+        assert_eq!(context["exit_code"], -1073741819);
+        assert_eq!(read("session.log"), "1 stderr Fatal error\n");
+        assert_eq!(read("crashes/UE4CC-Windows-A/UE4Minidump.dmp"), "dump");
+        assert_eq!(read("crashes/UE4CC-Windows-A/extra/CrashContext.runtime-xml"), "<xml/>");
+    }
+}
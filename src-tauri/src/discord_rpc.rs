@@ -3,6 +3,8 @@ use serde::{Deserialize, Serialize};
 use std::sync::{Arc, Mutex};
 use tauri::State;
 
+use crate::error::LauncherError;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct DiscordActivity {
     pub details: Option<String>,
@@ -33,16 +35,21 @@ impl DiscordRpcState {
     }
 }
 
+/// Discord not running is the usual cause, so it reads as unreachable.
+fn discord_error(e: Box<dyn std::error::Error>) -> LauncherError {
+    LauncherError::NetworkDown(format!("Discord is not available: {}", e))
+}
+
 #[tauri::command]
 pub async fn discord_rpc_init(
     client_id: String,
     state: State<'_, Arc<DiscordRpcState>>,
-) -> Result<(), String> {
+) -> Result<(), LauncherError> {
     println!("Initializing Discord RPC with client ID: {}", client_id);
 
-    let mut client_guard = state.client.lock().map_err(|e| e.to_string())?;
-    let mut client = DiscordIpcClient::new(&client_id).map_err(|e| e.to_string())?;
-    client.connect().map_err(|e| e.to_string())?;
+    let mut client_guard = state.client.lock().map_err(|e| LauncherError::Internal(e.to_string()))?;
+    let mut client = DiscordIpcClient::new(&client_id).map_err(discord_error)?;
+    client.connect().map_err(discord_error)?;
 
     *client_guard = Some(client);
 
@@ -54,9 +61,9 @@ pub async fn discord_rpc_init(
 pub async fn discord_rpc_set_activity(
     activity: DiscordActivity,
     state: State<'_, Arc<DiscordRpcState>>,
-) -> Result<(), String> {
-    let mut guard = state.client.lock().map_err(|e| e.to_string())?;
-    let client = guard.as_mut().ok_or("Discord RPC client not initialized")?;
+) -> Result<(), LauncherError> {
+    let mut guard = state.client.lock().map_err(|e| LauncherError::Internal(e.to_string()))?;
+    let client = guard.as_mut().ok_or_else(|| LauncherError::Internal("Discord RPC client not initialized".to_string()))?;
 
     let mut discord_activity = activity::Activity::new();
 
@@ -100,29 +107,29 @@ pub async fn discord_rpc_set_activity(
         discord_activity = discord_activity.buttons(discord_buttons);
     }
 
-    client.set_activity(discord_activity).map_err(|e| e.to_string())?;
+    client.set_activity(discord_activity).map_err(discord_error)?;
     Ok(())
 }
 
 #[tauri::command]
 pub async fn discord_rpc_clear_activity(
     state: State<'_, Arc<DiscordRpcState>>,
-) -> Result<(), String> {
-    let mut guard = state.client.lock().map_err(|e| e.to_string())?;
-    let client = guard.as_mut().ok_or("Discord RPC client not initialized")?;
+) -> Result<(), LauncherError> {
+    let mut guard = state.client.lock().map_err(|e| LauncherError::Internal(e.to_string()))?;
+    let client = guard.as_mut().ok_or_else(|| LauncherError::Internal("Discord RPC client not initialized".to_string()))?;
 
-    client.clear_activity().map_err(|e| e.to_string())?;
+    client.clear_activity().map_err(discord_error)?;
     Ok(())
 }
 
 #[tauri::command]
 pub async fn discord_rpc_disconnect(
     state: State<'_, Arc<DiscordRpcState>>,
-) -> Result<(), String> {
-    let mut guard = state.client.lock().map_err(|e| e.to_string())?;
+) -> Result<(), LauncherError> {
+    let mut guard = state.client.lock().map_err(|e| LauncherError::Internal(e.to_string()))?;
 
     if let Some(client) = guard.as_mut() {
-        client.close().map_err(|e| e.to_string())?;
+        client.close().map_err(discord_error)?;
     }
 
     *guard = None;
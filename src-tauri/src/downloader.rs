@@ -0,0 +1,817 @@
+use futures_util::stream::{self, StreamExt};
+use reqwest::header::RANGE;
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, OpenOptions};
+use std::io::{Seek, SeekFrom, Write};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, State};
+use tokio::sync::watch;
+
+use crate::build_verify::{hash_file, BUILDS_URL};
+use crate::error::LauncherError;
+use crate::library_scan::is_build_root;
+use crate::persist::write_atomic;
+use crate::register_version;
+
+const JOURNAL_NAME: &str = ".rewind-download.json";
+const PART_EXTENSION: &str = "part";
+const DEFAULT_WORKERS: usize = 4;
+const CHUNK_ATTEMPTS: u32 = 3;
+// Chunks are held in memory until they are hash-checked, one per worker, so
+// a manifest can't ask for more than this at once.
+const MAX_CHUNK_SIZE: u64 = 64 * 1024 * 1024;
+pub const CANCELLED: &str = "Download cancelled";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChunkEntry {
+    pub offset: u64,
+    pub size: u64,
+    pub sha256: String,
+    #[serde(default)]
+    pub url: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChunkedFile {
+    pub path: String,
+    pub size: u64,
+    #[serde(default)]
+    pub sha256: Option<String>,
+    pub chunks: Vec<ChunkEntry>,
+}
+
+/// Download manifest for one build. A chunk with its own `url` is the whole
+/// body served there; one without is fetched from `<base_url>/<file path>`
+/// with an HTTP range covering the chunk.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChunkManifest {
+    pub build: String,
+    pub base_url: String,
+    pub files: Vec<ChunkedFile>,
+}
+
+impl ChunkManifest {
+    pub fn total_size(&self) -> u64 {
+        self.files.iter().map(|f| f.size).sum()
+    }
+
+    /// Checks that each file's chunks cover it exactly, with no gaps or
+    /// overlaps and nothing past its size, and that no chunk is larger than
+    /// `MAX_CHUNK_SIZE`.
+    pub fn validate(&self) -> Result<(), LauncherError> {
+        for file in &self.files {
+            let invalid = |message: String| LauncherError::CorruptBuild(format!("Bad manifest for {}: {}", file.path, message));
+
+            let mut chunks: Vec<&ChunkEntry> = file.chunks.iter().collect();
+            chunks.sort_by_key(|chunk| chunk.offset);
+            let mut covered = 0u64;
+            for chunk in chunks {
+                if chunk.size > MAX_CHUNK_SIZE {
+                    return Err(invalid(format!("chunk at {} is {} bytes", chunk.offset, chunk.size)));
+                }
+                if chunk.offset != covered {
+                    return Err(invalid(format!("expected a chunk at {}, got one at {}", covered, chunk.offset)));
+                }
+                covered = chunk
+                    .offset
+                    .checked_add(chunk.size)
+                    .filter(|end| *end <= file.size)
+                    .ok_or_else(|| invalid(format!("chunk at {} runs past the end of the file", chunk.offset)))?;
+            }
+            if covered != file.size {
+                return Err(invalid(format!("chunks cover {} of {} bytes", covered, file.size)));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DownloadState {
+    Running,
+    Paused,
+    Cancelled,
+}
+
+/// Shared pause/resume/cancel switch. Workers call `checkpoint` between
+/// reads and park there while paused.
+pub struct DownloadControl {
+    state: watch::Sender<DownloadState>,
+}
+
+impl DownloadControl {
+    pub fn new() -> Self {
+        Self {
+            state: watch::channel(DownloadState::Running).0,
+        }
+    }
+
+    pub fn state(&self) -> DownloadState {
+        *self.state.borrow()
+    }
+
+    pub fn set(&self, state: DownloadState) {
+        if self.state() != DownloadState::Cancelled {
+            self.state.send_replace(state);
+        }
+    }
+
+    pub async fn checkpoint(&self) -> Result<(), LauncherError> {
+        let mut receiver = self.state.subscribe();
+        loop {
+            match *receiver.borrow_and_update() {
+                DownloadState::Running => return Ok(()),
+                DownloadState::Cancelled => return Err(LauncherError::Cancelled(CANCELLED.to_string())),
+                DownloadState::Paused => {}
+            }
+            receiver
+                .changed()
+                .await
+                .map_err(|e| LauncherError::Internal(format!("Download control dropped: {}", e)))?;
+        }
+    }
+}
+
+impl Default for DownloadControl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Token bucket shared by every worker of every download. A limit of zero
+/// means unlimited.
+pub struct RateLimiter {
+    bytes_per_sec: AtomicU64,
+    bucket: tokio::sync::Mutex<(Instant, f64)>,
+}
+
+impl RateLimiter {
+    pub fn new(bytes_per_sec: u64) -> Self {
+        Self {
+            bytes_per_sec: AtomicU64::new(bytes_per_sec),
+            bucket: tokio::sync::Mutex::new((Instant::now(), 0.0)),
+        }
+    }
+
+    pub fn set_limit(&self, bytes_per_sec: u64) {
+        self.bytes_per_sec.store(bytes_per_sec, Ordering::SeqCst);
+    }
+
+    pub async fn acquire(&self, amount: usize) {
+        let limit = self.bytes_per_sec.load(Ordering::SeqCst);
+        if limit == 0 {
+            return;
+        }
+
+        let mut bucket = self.bucket.lock().await;
+        let (last, tokens) = &mut *bucket;
+        let now = Instant::now();
+        *tokens = (*tokens + now.duration_since(*last).as_secs_f64() * limit as f64).min(limit as f64);
+        *last = now;
+        *tokens -= amount as f64;
+
+        if *tokens < 0.0 {
+            tokio::time::sleep(Duration::from_secs_f64(-*tokens / limit as f64)).await;
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DownloadProgress {
+    pub id: String,
+    pub downloaded_bytes: u64,
+    pub total_bytes: u64,
+    pub state: DownloadState,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Journal {
+    completed: HashSet<String>,
+}
+
+fn chunk_id(file: &ChunkedFile, chunk: &ChunkEntry) -> String {
+    format!("{}#{}", file.path, chunk.offset)
+}
+
+/// Rejects relative paths that would land outside `root`.
+pub fn safe_join(root: &Path, relative: &str) -> Result<PathBuf, LauncherError> {
+    let relative_path = Path::new(relative);
+    let is_safe = relative_path
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)));
+    if relative.is_empty() || !is_safe {
+        return Err(LauncherError::InvalidInput(format!("Refusing unsafe path: {}", relative)));
+    }
+    Ok(root.join(relative_path))
+}
+
+fn part_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".");
+    name.push(PART_EXTENSION);
+    path.with_file_name(name)
+}
+
+fn chunk_url(manifest: &ChunkManifest, file: &ChunkedFile, chunk: &ChunkEntry) -> String {
+    chunk.url.clone().unwrap_or_else(|| {
+        let encoded: Vec<String> = file
+            .path
+            .split('/')
+            .map(|segment| urlencoding::encode(segment).into_owned())
+            .collect();
+        format!("{}/{}", manifest.base_url.trim_end_matches('/'), encoded.join("/"))
+    })
+}
+
+fn load_journal(path: &Path) -> Journal {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_journal(path: &Path, journal: &Journal) -> Result<(), LauncherError> {
+    let content = serde_json::to_string(journal)?;
+    write_atomic(path, content).map_err(|e| LauncherError::io("Failed to save the download journal", e))
+}
+
+/// Fetches `chunk` from `url`. `ranged` asks for just the chunk's bytes of
+/// the whole file at `url`; otherwise the body is expected to be the chunk.
+async fn fetch_chunk(
+    client: &reqwest::Client,
+    url: &str,
+    ranged: bool,
+    chunk: &ChunkEntry,
+    control: &DownloadControl,
+    limiter: &RateLimiter,
+) -> Result<Vec<u8>, LauncherError> {
+    if chunk.size == 0 {
+        return Ok(Vec::new());
+    }
+
+    let mut request = client.get(url);
+    if ranged {
+        request = request.header(RANGE, format!("bytes={}-{}", chunk.offset, chunk.offset + chunk.size - 1));
+    }
+    let response = request.send().await?;
+
+    match response.status() {
+        StatusCode::PARTIAL_CONTENT if ranged => {}
+        StatusCode::OK if !ranged || chunk.offset == 0 => {}
+        StatusCode::OK => {
+            return Err(LauncherError::Api {
+                status: StatusCode::OK.as_u16(),
+                message: "Server ignored the range request".to_string(),
+            })
+        }
+        status => return Err(LauncherError::api(status)),
+    }
+
+    let mut data = Vec::with_capacity(chunk.size.min(MAX_CHUNK_SIZE) as usize);
+    let mut body = response.bytes_stream();
+    while let Some(piece) = body.next().await {
+        control.checkpoint().await?;
+        let piece = piece?;
+        limiter.acquire(piece.len()).await;
+
+        let remaining = chunk.size as usize - data.len();
+        data.extend_from_slice(&piece[..piece.len().min(remaining)]);
+        if data.len() as u64 == chunk.size {
+            break;
+        }
+    }
+
+    if data.len() as u64 != chunk.size {
+        return Err(LauncherError::CorruptBuild(format!(
+            "Chunk ended after {} of {} bytes",
+            data.len(),
+            chunk.size
+        )));
+    }
+
+    let actual = hex::encode(Sha256::digest(&data));
+    if !actual.eq_ignore_ascii_case(&chunk.sha256) {
+        return Err(LauncherError::CorruptBuild(format!(
+            "Chunk hash mismatch: expected {}, got {}",
+            chunk.sha256, actual
+        )));
+    }
+
+    Ok(data)
+}
+
+fn write_chunk(path: &Path, offset: u64, data: &[u8]) -> Result<(), LauncherError> {
+    let mut file = OpenOptions::new()
+        .write(true)
+        .open(path)
+        .map_err(|e| LauncherError::io(format!("Failed to open {}", path.display()), e))?;
+    file.seek(SeekFrom::Start(offset))?;
+    file.write_all(data)
+        .map_err(|e| LauncherError::io(format!("Failed to write {}", path.display()), e))
+}
+
+/// Downloads every file of `manifest` into `dest`. Chunks are fetched in
+/// parallel, hash-checked and written into `<file>.part` at their offset; a
+/// journal in `dest` records finished chunks so a later call resumes where
+/// this one stopped. Each file is renamed into place once all its chunks are
+/// in and its whole-file hash, if given, matches.
+pub async fn download_build<F>(
+    manifest: &ChunkManifest,
+    dest: &Path,
+    client: &reqwest::Client,
+    control: &DownloadControl,
+    limiter: &RateLimiter,
+    workers: usize,
+    on_progress: F,
+) -> Result<(), LauncherError>
+where
+    F: Fn(u64, u64),
+{
+    manifest.validate()?;
+    fs::create_dir_all(dest).map_err(|e| LauncherError::io(format!("Failed to create {}", dest.display()), e))?;
+
+    let journal_path = dest.join(JOURNAL_NAME);
+    let journal = Mutex::new(load_journal(&journal_path));
+    let total = manifest.total_size();
+    let mut downloaded = 0u64;
+    let mut pending = Vec::new();
+
+    for file in &manifest.files {
+        let target = safe_join(dest, &file.path)?;
+        let part = part_path(&target);
+
+        let finalized = !part.exists()
+            && fs::metadata(&target).map(|m| m.len() == file.size).unwrap_or(false);
+        if finalized {
+            downloaded += file.size;
+            continue;
+        }
+
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| LauncherError::io(format!("Failed to create {}", parent.display()), e))?;
+        }
+        let part_file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(false)
+            .open(&part)
+            .map_err(|e| LauncherError::io(format!("Failed to create {}", part.display()), e))?;
+        part_file.set_len(file.size)?;
+
+        for chunk in &file.chunks {
+            if journal.lock().unwrap().completed.contains(&chunk_id(file, chunk)) {
+                downloaded += chunk.size;
+            } else {
+                pending.push((file, chunk, part.clone()));
+            }
+        }
+    }
+    on_progress(downloaded, total);
+
+    // Built up front rather than mapped lazily inside the stream: a stream
+    // holding the mapping closure isn't provably Send, so the download
+    // couldn't be spawned.
+    let fetches: Vec<_> = pending
+        .into_iter()
+        .map(|(file, chunk, part)| {
+            let url = chunk_url(manifest, file, chunk);
+            let ranged = chunk.url.is_none();
+            async move {
+                let mut attempt = 0;
+                let data = loop {
+                    control.checkpoint().await?;
+                    attempt += 1;
+                    match fetch_chunk(client, &url, ranged, chunk, control, limiter).await {
+                        Ok(data) => break data,
+                        Err(e) if control.state() == DownloadState::Cancelled => return Err(e),
+                        Err(e) if attempt >= CHUNK_ATTEMPTS => {
+                            println!("Giving up on {} @ {}: {}", file.path, chunk.offset, e);
+                            return Err(e);
+                        }
+                        Err(e) => {
+                            println!("Retrying {} @ {}: {}", file.path, chunk.offset, e);
+                            tokio::time::sleep(Duration::from_millis(500 * u64::from(attempt))).await;
+                        }
+                    }
+                };
+                let offset = chunk.offset;
+                tokio::task::spawn_blocking(move || write_chunk(&part, offset, &data)).await??;
+                Ok::<_, LauncherError>((chunk_id(file, chunk), chunk.size))
+            }
+        })
+        .collect();
+    let mut results = stream::iter(fetches).buffer_unordered(workers.max(1));
+
+    while let Some(result) = results.next().await {
+        let (id, size) = result?;
+        {
+            let mut journal = journal.lock().unwrap();
+            journal.completed.insert(id);
+            save_journal(&journal_path, &journal)?;
+        }
+        downloaded += size;
+        on_progress(downloaded, total);
+    }
+
+    for file in &manifest.files {
+        let target = safe_join(dest, &file.path)?;
+        let part = part_path(&target);
+        if !part.exists() {
+            continue;
+        }
+        if let Some(expected) = &file.sha256 {
+            let actual =
+                hash_file(&part).map_err(|e| LauncherError::io(format!("Failed to read {}", part.display()), e))?;
+            if !actual.eq_ignore_ascii_case(expected) {
+                // Forget the file's chunks so the next attempt fetches it again.
+                let mut journal = journal.lock().unwrap();
+                for chunk in &file.chunks {
+                    journal.completed.remove(&chunk_id(file, chunk));
+                }
+                save_journal(&journal_path, &journal)?;
+                return Err(LauncherError::CorruptBuild(format!(
+                    "{} failed verification after download",
+                    file.path
+                )));
+            }
+        }
+        fs::rename(&part, &target).map_err(|e| LauncherError::io(format!("Failed to finalize {}", file.path), e))?;
+    }
+
+    let _ = fs::remove_file(&journal_path);
+    Ok(())
+}
+
+/// Removes the journal and every `.part` file a cancelled download left.
+pub fn discard_partial_download(manifest: &ChunkManifest, dest: &Path) {
+    for file in &manifest.files {
+        if let Ok(target) = safe_join(dest, &file.path) {
+            let _ = fs::remove_file(part_path(&target));
+        }
+    }
+    let _ = fs::remove_file(dest.join(JOURNAL_NAME));
+}
+
+pub struct DownloadManager {
+    downloads: Mutex<HashMap<String, Arc<DownloadControl>>>,
+    limiter: Arc<RateLimiter>,
+}
+
+impl DownloadManager {
+    pub fn new() -> Self {
+        Self {
+            downloads: Mutex::new(HashMap::new()),
+            limiter: Arc::new(RateLimiter::new(0)),
+        }
+    }
+
+    fn control(&self, id: &str) -> Result<Arc<DownloadControl>, LauncherError> {
+        self.downloads
+            .lock()
+            .unwrap()
+            .get(id)
+            .cloned()
+            .ok_or_else(|| LauncherError::NotFound(format!("No download with id {}", id)))
+    }
+}
+
+impl Default for DownloadManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct DownloadFinished {
+    id: String,
+    path: String,
+    ok: bool,
+    error: Option<String>,
+}
+
+async fn fetch_chunk_manifest(url: &str) -> Result<ChunkManifest, LauncherError> {
+    let response = reqwest::get(url).await?;
+    if !response.status().is_success() {
+        return Err(LauncherError::api(response.status()));
+    }
+    Ok(response.json::<ChunkManifest>().await?)
+}
+
+fn emit_progress(app_handle: &AppHandle, id: &str, downloaded_bytes: u64, total_bytes: u64, state: DownloadState) {
+    let _ = app_handle.emit("download-progress", DownloadProgress {
+        id: id.to_string(),
+        downloaded_bytes,
+        total_bytes,
+        state,
+    });
+}
+
+/// Starts downloading `build` into `dest_path` in the background and returns
+/// the download id. Progress arrives as `download-progress` events and the
+/// outcome as `download-finished`; a finished build is added to the library.
+#[tauri::command]
+pub async fn start_download(
+    build: String,
+    dest_path: String,
+    manifest_url: Option<String>,
+    manager: State<'_, Arc<DownloadManager>>,
+    app_handle: AppHandle,
+) -> Result<String, LauncherError> {
+    let url = manifest_url.unwrap_or_else(|| {
+        format!("{}/{}/download-manifest", BUILDS_URL, urlencoding::encode(&build))
+    });
+    let manifest = fetch_chunk_manifest(&url).await?;
+
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    let id = format!("{:x}", nanos);
+
+    let control = Arc::new(DownloadControl::new());
+    manager.downloads.lock().unwrap().insert(id.clone(), control.clone());
+    let limiter = manager.limiter.clone();
+    let manager = manager.inner().clone();
+
+    let task_id = id.clone();
+    tauri::async_runtime::spawn(async move {
+        let dest = PathBuf::from(&dest_path);
+        let client = reqwest::Client::new();
+        let result = download_build(&manifest, &dest, &client, &control, &limiter, DEFAULT_WORKERS, |done, total| {
+            emit_progress(&app_handle, &task_id, done, total, control.state())
+        })
+        .await;
+
+        let result = match result {
+            Ok(()) if is_build_root(&dest) => register_version(dest_path.clone(), &app_handle).await,
+            Ok(()) => Err(LauncherError::CorruptBuild(
+                "Download finished but no build was found in the destination".to_string(),
+            )),
+            Err(e) => {
+                if control.state() == DownloadState::Cancelled {
+                    discard_partial_download(&manifest, &dest);
+                }
+                Err(e)
+            }
+        };
+
+        manager.downloads.lock().unwrap().remove(&task_id);
+        let _ = app_handle.emit("download-finished", DownloadFinished {
+            id: task_id,
+            path: dest_path,
+            ok: result.is_ok(),
+            error: result.err().map(|e| e.to_string()),
+        });
+    });
+
+    Ok(id)
+}
+
+#[tauri::command]
+pub async fn pause_download(id: String, manager: State<'_, Arc<DownloadManager>>) -> Result<(), LauncherError> {
+    manager.control(&id)?.set(DownloadState::Paused);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn resume_download(id: String, manager: State<'_, Arc<DownloadManager>>) -> Result<(), LauncherError> {
+    manager.control(&id)?.set(DownloadState::Running);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn cancel_download(id: String, manager: State<'_, Arc<DownloadManager>>) -> Result<(), LauncherError> {
+    manager.control(&id)?.set(DownloadState::Cancelled);
+    Ok(())
+}
+
+/// Caps the combined speed of all downloads. Zero removes the cap.
+#[tauri::command]
+pub async fn set_download_limit(
+    bytes_per_sec: u64,
+    manager: State<'_, Arc<DownloadManager>>,
+) -> Result<(), LauncherError> {
+    manager.limiter.set_limit(bytes_per_sec);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fs::TempDir;
+    use crate::test_http::{Request, Response, TestServer};
+    use std::sync::atomic::AtomicBool;
+
+    const FILE_PATH: &str = "FortniteGame/Content/Paks/pakchunk0.pak";
+
+    fn content() -> Vec<u8> {
+        (0..300u32).map(|i| (i * 7 % 251) as u8).collect()
+    }
+
+    fn sha256(data: &[u8]) -> String {
+        hex::encode(Sha256::digest(data))
+    }
+
+    /// `(start, end)` of a `bytes=start-end` header.
+    fn range(request: &Request) -> Option<(usize, usize)> {
+        let (start, end) = request.header("range")?.strip_prefix("bytes=")?.split_once('-')?;
+        Some((start.parse().ok()?, end.parse().ok()?))
+    }
+
+    fn manifest(base_url: &str, chunk_urls: &[Option<String>]) -> ChunkManifest {
+        let data = content();
+        let chunks = chunk_urls
+            .iter()
+            .enumerate()
+            .map(|(index, url)| ChunkEntry {
+                offset: index as u64 * 100,
+                size: 100,
+                sha256: sha256(&data[index * 100..index * 100 + 100]),
+                url: url.clone(),
+            })
+            .collect();
+        ChunkManifest {
+            build: "test".to_string(),
+            base_url: format!("{}/builds", base_url),
+            files: vec![ChunkedFile {
+                path: FILE_PATH.to_string(),
+                size: data.len() as u64,
+                sha256: Some(sha256(&data)),
+                chunks,
+            }],
+        }
+    }
+
+    async fn download(manifest: &ChunkManifest, dest: &Path) -> Result<(), LauncherError> {
+        download_build(
+            manifest,
+            dest,
+            &reqwest::Client::new(),
+            &DownloadControl::new(),
+            &RateLimiter::new(0),
+            2,
+            |_, _| {},
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn resumes_with_only_the_missing_chunks() {
+        let failing = Arc::new(AtomicBool::new(true));
+        let fail = failing.clone();
+        let server = TestServer::start(move |request| match range(request) {
+            Some((100, _)) if fail.load(Ordering::SeqCst) => Response::new(500, "down"),
+            Some((start, end)) => Response::new(206, &content()[start..=end]),
+            None => Response::new(200, content()),
+        })
+        .await;
+        let manifest = manifest(&server.url, &[None, None, None]);
+        let dest = TempDir::new("download", "resume");
+        let target = dest.join(FILE_PATH);
+
+        assert!(download(&manifest, &dest).await.is_err());
+        assert!(part_path(&target).exists());
+        assert_eq!(load_journal(&dest.join(JOURNAL_NAME)).completed.len(), 2);
+
+        failing.store(false, Ordering::SeqCst);
+        server.clear_requests();
+        download(&manifest, &dest).await.unwrap();
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].path, format!("/builds/{}", FILE_PATH));
+        assert_eq!(range(&requests[0]), Some((100, 199)));
+        assert_eq!(fs::read(&target).unwrap(), content());
+        assert!(!part_path(&target).exists());
+        assert!(!dest.join(JOURNAL_NAME).exists());
+    }
+
+    #[tokio::test]
+    async fn chunks_with_their_own_url_are_fetched_whole() {
+        let server = TestServer::start(|request| match (request.path.as_str(), range(request)) {
+            ("/chunks/1", None) => Response::new(200, &content()[100..200]),
+            ("/chunks/1", Some(_)) => Response::new(200, "whole file that ignores the range"),
+            (_, Some((start, end))) => Response::new(206, &content()[start..=end]),
+            (_, None) => Response::new(200, content()),
+        })
+        .await;
+        let manifest = manifest(&server.url, &[None, Some(format!("{}/chunks/1", server.url)), None]);
+        let dest = TempDir::new("download", "chunk-url");
+
+        download(&manifest, &dest).await.unwrap();
+
+        assert_eq!(fs::read(dest.join(FILE_PATH)).unwrap(), content());
+        let chunk_request = server.requests().into_iter().find(|r| r.path == "/chunks/1").unwrap();
+        assert_eq!(chunk_request.header("range"), None);
+    }
+
+    #[tokio::test]
+    async fn rejects_servers_that_ignore_ranges() {
+        let server = TestServer::start(|_| Response::new(200, content())).await;
+        let manifest = manifest(&server.url, &[None, None, None]);
+        let chunk = &manifest.files[0].chunks[1];
+        let url = chunk_url(&manifest, &manifest.files[0], chunk);
+
+        let error = fetch_chunk(
+            &reqwest::Client::new(),
+            &url,
+            true,
+            chunk,
+            &DownloadControl::new(),
+            &RateLimiter::new(0),
+        )
+        .await
+        .unwrap_err()
+        .to_string();
+        assert!(error.contains("ignored the range"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn rejects_chunks_with_the_wrong_hash() {
+        let server = TestServer::start(|_| Response::new(200, vec![0u8; 100])).await;
+        let manifest = manifest(&server.url, &[None]);
+        let chunk = &manifest.files[0].chunks[0];
+
+        let error = fetch_chunk(
+            &reqwest::Client::new(),
+            &chunk_url(&manifest, &manifest.files[0], chunk),
+            true,
+            chunk,
+            &DownloadControl::new(),
+            &RateLimiter::new(0),
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(error, LauncherError::CorruptBuild(_)), "{}", error);
+        assert!(error.to_string().contains("hash mismatch"), "{}", error);
+    }
+
+    #[test]
+    fn accepts_chunks_that_cover_the_file_in_any_order() {
+        let mut manifest = manifest("http://localhost", &[None, None, None]);
+        assert!(manifest.validate().is_ok());
+        manifest.files[0].chunks.reverse();
+        assert!(manifest.validate().is_ok());
+    }
+
+    #[test]
+    fn rejects_chunks_that_do_not_cover_the_file_exactly() {
+        let cases: [(&str, fn(&mut ChunkedFile)); 6] = [
+            ("gap", |file| {
+                file.chunks.remove(1);
+            }),
+            ("overlap", |file| file.chunks[1].offset = 50),
+            ("short", |file| file.size += 1),
+            ("past the end", |file| file.chunks[2].size = 200),
+            ("oversized", |file| {
+                file.size = MAX_CHUNK_SIZE + 1;
+                file.chunks = vec![ChunkEntry {
+                    offset: 0,
+                    size: MAX_CHUNK_SIZE + 1,
+                    sha256: String::new(),
+                    url: None,
+                }];
+            }),
+            ("overflow", |file| {
+                file.chunks[2].offset = u64::MAX - 10;
+                file.chunks[2].size = 100;
+            }),
+        ];
+        for (name, break_manifest) in cases {
+            let mut manifest = manifest("http://localhost", &[None, None, None]);
+            break_manifest(&mut manifest.files[0]);
+            let error = manifest.validate().unwrap_err();
+            assert!(matches!(error, LauncherError::CorruptBuild(_)), "{}: {}", name, error);
+        }
+    }
+
+    #[tokio::test]
+    async fn a_bad_manifest_fetches_and_writes_nothing() {
+        let server = TestServer::start(|_| Response::new(200, content())).await;
+        let mut manifest = manifest(&server.url, &[None, None, None]);
+        manifest.files[0].chunks[2].size = 200;
+        let dir = TempDir::new("download", "bad-manifest");
+        let dest = dir.join("build");
+
+        assert!(matches!(download(&manifest, &dest).await, Err(LauncherError::CorruptBuild(_))));
+        assert!(server.requests().is_empty());
+        assert!(!dest.exists());
+    }
+
+    #[test]
+    fn refuses_paths_outside_the_destination() {
+        let root = Path::new("builds");
+        assert!(safe_join(root, "FortniteGame/a.pak").is_ok());
+        for path in ["", "../a.pak", "FortniteGame/../../a.pak", "/etc/passwd"] {
+            assert!(safe_join(root, path).is_err(), "{}", path);
+        }
+    }
+}
@@ -0,0 +1,212 @@
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use std::fmt;
+use std::io;
+
+/// The error every command returns. It reaches the frontend as
+/// `{code, message, details}`; `code` is stable and meant to be branched on,
+/// `message` is for people and may change.
+#[derive(Debug)]
+pub enum LauncherError {
+    /// No stored login, or it has expired.
+    AuthExpired(String),
+    /// The backend or the internet can't be reached.
+    NetworkDown(String),
+    /// The backend answered with an error status.
+    Api { status: u16, message: String },
+    NotFound(String),
+    /// Build or payload files are missing, damaged or fail verification.
+    CorruptBuild(String),
+    AlreadyRunning(String),
+    InvalidInput(String),
+    /// The game or one of its helpers couldn't be started or controlled.
+    Launch(String),
+    Io { message: String, source: io::Error },
+    Http(reqwest::Error),
+    Json(serde_json::Error),
+    Regex(regex::Error),
+    /// The user called it off, e.g. cancelled a download.
+    Cancelled(String),
+    /// Waited too long for something outside the launcher, e.g. the browser.
+    Timeout(String),
+    /// The OS credential store refused or isn't there.
+    Keyring(String),
+    /// A bug or a broken invariant in the launcher itself.
+    Internal(String),
+}
+
+impl LauncherError {
+    /// An I/O failure with a message saying what was being done.
+    pub fn io(message: impl Into<String>, source: io::Error) -> Self {
+        LauncherError::Io {
+            message: message.into(),
+            source,
+        }
+    }
+
+    /// The backend answered with `status` instead of what was asked for.
+    pub fn api(status: reqwest::StatusCode) -> Self {
+        LauncherError::Api {
+            status: status.as_u16(),
+            message: format!("API returned error status: {}", status),
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            LauncherError::AuthExpired(_) => "auth_expired",
+            LauncherError::NetworkDown(_) => "network_down",
+            LauncherError::Api { .. } => "api_error",
+            LauncherError::NotFound(_) => "not_found",
+            LauncherError::CorruptBuild(_) => "corrupt_build",
+            LauncherError::AlreadyRunning(_) => "already_running",
+            LauncherError::InvalidInput(_) => "invalid_input",
+            LauncherError::Launch(_) => "launch_failed",
+            LauncherError::Io { source, .. } if source.kind() == io::ErrorKind::NotFound => "not_found",
+            LauncherError::Io { source, .. } if source.kind() == io::ErrorKind::PermissionDenied => {
+                "permission_denied"
+            }
+            LauncherError::Io { .. } => "io",
+            LauncherError::Http(_) => "http",
+            LauncherError::Json(_) => "parse",
+            LauncherError::Regex(_) => "regex",
+            LauncherError::Cancelled(_) => "cancelled",
+            LauncherError::Timeout(_) => "timeout",
+            LauncherError::Keyring(_) => "keyring",
+            LauncherError::Internal(_) => "internal",
+        }
+    }
+
+    /// Whatever the underlying error adds to the message, if anything.
+    pub fn details(&self) -> Option<String> {
+        match self {
+            LauncherError::Api { status, .. } => Some(format!("HTTP {}", status)),
+            LauncherError::Io { source, .. } => Some(source.to_string()),
+            LauncherError::Http(e) => e.url().map(|url| url.to_string()),
+            LauncherError::Json(e) => Some(format!("line {}, column {}", e.line(), e.column())),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for LauncherError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LauncherError::AuthExpired(message)
+            | LauncherError::NetworkDown(message)
+            | LauncherError::Api { message, .. }
+            | LauncherError::NotFound(message)
+            | LauncherError::CorruptBuild(message)
+            | LauncherError::AlreadyRunning(message)
+            | LauncherError::InvalidInput(message)
+            | LauncherError::Launch(message)
+            | LauncherError::Io { message, .. }
+            | LauncherError::Cancelled(message)
+            | LauncherError::Timeout(message)
+            | LauncherError::Keyring(message)
+            | LauncherError::Internal(message) => f.write_str(message),
+            LauncherError::Http(e) => write!(f, "Request failed: {}", e),
+            LauncherError::Json(e) => write!(f, "Failed to parse JSON: {}", e),
+            LauncherError::Regex(e) => write!(f, "Invalid pattern: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for LauncherError {}
+
+impl Serialize for LauncherError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("LauncherError", 3)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.serialize_field("details", &self.details())?;
+        state.end()
+    }
+}
+
+impl From<io::Error> for LauncherError {
+    fn from(e: io::Error) -> Self {
+        LauncherError::io(e.to_string(), e)
+    }
+}
+
+impl From<reqwest::Error> for LauncherError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_connect() || e.is_timeout() {
+            LauncherError::NetworkDown(format!("Couldn't reach the server: {}", e))
+        } else if let Some(status) = e.status() {
+            LauncherError::api(status)
+        } else {
+            LauncherError::Http(e)
+        }
+    }
+}
+
+impl From<serde_json::Error> for LauncherError {
+    fn from(e: serde_json::Error) -> Self {
+        LauncherError::Json(e)
+    }
+}
+
+impl From<tokio::task::JoinError> for LauncherError {
+    fn from(e: tokio::task::JoinError) -> Self {
+        LauncherError::Internal(format!("Background task failed: {}", e))
+    }
+}
+
+impl From<regex::Error> for LauncherError {
+    fn from(e: regex::Error) -> Self {
+        LauncherError::Regex(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn serializes_to_what_the_frontend_reads() {
+        let not_found = LauncherError::io("No build here", io::Error::new(io::ErrorKind::NotFound, "gone"));
+        assert_eq!(
+            serde_json::to_value(not_found).unwrap(),
+            json!({ "code": "not_found", "message": "No build here", "details": "gone" })
+        );
+        assert_eq!(
+            serde_json::to_value(LauncherError::Cancelled("Download cancelled".to_string())).unwrap(),
+            json!({ "code": "cancelled", "message": "Download cancelled", "details": null })
+        );
+        assert_eq!(
+            serde_json::to_value(LauncherError::api(reqwest::StatusCode::BAD_GATEWAY)).unwrap(),
+            json!({
+                "code": "api_error",
+                "message": "API returned error status: 502 Bad Gateway",
+                "details": "HTTP 502",
+            })
+        );
+    }
+
+    #[test]
+    fn codes_match_the_frontend_union() {
+        let codes: Vec<&str> = [
+            LauncherError::AuthExpired(String::new()),
+            LauncherError::NetworkDown(String::new()),
+            LauncherError::CorruptBuild(String::new()),
+            LauncherError::Cancelled(String::new()),
+            LauncherError::Timeout(String::new()),
+            LauncherError::Keyring(String::new()),
+            LauncherError::Internal(String::new()),
+        ]
+        .iter()
+        .map(LauncherError::code)
+        .collect();
+        assert_eq!(
+            codes,
+            ["auth_expired", "network_down", "corrupt_build", "cancelled", "timeout", "keyring", "internal"]
+        );
+
+        let union = include_str!("../../src/utils/launcherError.ts");
+        for code in codes {
+            assert!(union.contains(&format!("'{}'", code)), "{} is missing from launcherError.ts", code);
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use reqwest::{Client, StatusCode};
+use serde::Deserialize;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use crate::error::LauncherError;
+use crate::token_manager::TokenManager;
+
+pub const EXCHANGE_URL: &str = "your_exchange_url";
+// Codes only live for a few minutes, so a slow answer isn't worth waiting on.
+const EXCHANGE_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Deserialize)]
+struct ExchangeCodeResponse {
+    code: String,
+}
+
+/// Asks the backend at `url` for a one-time code the game can log in with,
+/// on behalf of whoever `token` belongs to.
+pub async fn request_exchange_code(client: &Client, url: &str, token: &str) -> Result<String, LauncherError> {
+    let response = client
+        .post(url)
+        .bearer_auth(token)
+        .timeout(EXCHANGE_TIMEOUT)
+        .send()
+        .await?;
+    let status = response.status();
+    match status {
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+            return Err(LauncherError::AuthExpired(
+                "Your login has expired. Log in again to play.".to_string(),
+            ))
+        }
+        status if !status.is_success() => return Err(LauncherError::api(status)),
+        _ => {}
+    }
+
+    let exchange: ExchangeCodeResponse = response.json().await?;
+    if exchange.code.is_empty() {
+        return Err(LauncherError::Api {
+            status: status.as_u16(),
+            message: "The backend returned an empty exchange code".to_string(),
+        });
+    }
+    Ok(exchange.code)
+}
+
+/// A fresh exchange code for the logged-in account.
+pub async fn exchange_code(app_handle: &AppHandle) -> Result<String, LauncherError> {
+    let token = app_handle.state::<TokenManager>().bearer(app_handle).await?;
+    request_exchange_code(&Client::new(), EXCHANGE_URL, &token).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_http::{Response, TestServer};
+    use serde_json::json;
+
+    async fn exchange_at(server: &TestServer, path: &str) -> Result<String, LauncherError> {
+        request_exchange_code(&Client::new(), &format!("{}{}", server.url, path), "launcher-token").await
+    }
+
+    #[tokio::test]
+    async fn asks_for_a_code_with_the_bearer_token() {
+        let server = TestServer::start(|_| Response::json(200, json!({ "code": "one-time" }))).await;
+
+        assert_eq!(exchange_at(&server, "/exchange").await.unwrap(), "one-time");
+        let requests = server.requests();
+        assert_eq!(requests[0].method, "POST");
+        assert_eq!(requests[0].path, "/exchange");
+        assert_eq!(requests[0].header("authorization"), Some("Bearer launcher-token"));
+    }
+
+    #[tokio::test]
+    async fn maps_failures_to_what_the_user_can_do_about_them() {
+        let server = TestServer::start(|request| match request.path.as_str() {
+            "/401" => Response::new(401, "unauthorized"),
+            "/403" => Response::new(403, "forbidden"),
+            "/502" => Response::new(502, "bad gateway"),
+            _ => Response::json(200, json!({ "code": "" })),
+        })
+        .await;
+
+        assert!(matches!(exchange_at(&server, "/401").await, Err(LauncherError::AuthExpired(_))));
+        assert!(matches!(exchange_at(&server, "/403").await, Err(LauncherError::AuthExpired(_))));
+        assert!(matches!(
+            exchange_at(&server, "/502").await,
+            Err(LauncherError::Api { status: 502, .. })
+        ));
+        assert!(matches!(exchange_at(&server, "/empty").await, Err(LauncherError::Api { status: 200, .. })));
+    }
+}
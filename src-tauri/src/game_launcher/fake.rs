@@ -0,0 +1,175 @@
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use super::{GameLauncher, GameProcess, LaunchSpec, ProcessExit, ProcessOutput, ProcessRole};
+
+const FIRST_FAKE_PID: u32 = 10_000;
+
+/// What the fake launcher has done with one spawned process.
+// Only read back by tests; the fake-launcher app build just runs the fake.
+#[cfg_attr(not(test), allow(dead_code))]
+#[derive(Debug, Clone)]
+pub struct FakeRecord {
+    pub pid: u32,
+    pub spec: LaunchSpec,
+    pub suspended: bool,
+    pub close_requested: bool,
+    pub killed: bool,
+    pub exit: Option<ProcessExit>,
+    pub injected: Vec<PathBuf>,
+}
+
+#[derive(Default)]
+struct FakeState {
+    records: Vec<FakeRecord>,
+    fail_roles: Vec<ProcessRole>,
+    /// Exit code processes report when asked to close, if they listen.
+    close_exit: Option<i32>,
+}
+
+/// Starts nothing. Records every spawn so launch flows can be exercised
+/// without a build, and lets the caller decide when processes exit.
+#[derive(Clone, Default)]
+pub struct FakeLauncher {
+    state: Arc<Mutex<FakeState>>,
+}
+
+#[cfg(test)]
+impl FakeLauncher {
+    pub fn records(&self) -> Vec<FakeRecord> {
+        self.state.lock().unwrap().records.clone()
+    }
+
+    /// Makes the next spawn of `role` fail.
+    pub fn fail_next(&self, role: ProcessRole) {
+        self.state.lock().unwrap().fail_roles.push(role);
+    }
+
+    /// Makes processes exit with `code` when asked to close. By default they
+    /// ignore the request and have to be killed.
+    pub fn exit_on_close(&self, code: i32) {
+        self.state.lock().unwrap().close_exit = Some(code);
+    }
+
+    pub fn exit(&self, pid: u32, code: Option<i32>) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(record) = state.records.iter_mut().find(|r| r.pid == pid) {
+            record.exit.get_or_insert(ProcessExit { code });
+        }
+    }
+}
+
+impl GameLauncher for FakeLauncher {
+    fn name(&self) -> &'static str {
+        "fake"
+    }
+
+    fn spawn(&self, spec: &LaunchSpec) -> Result<Box<dyn GameProcess>, String> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(index) = state.fail_roles.iter().position(|role| *role == spec.role) {
+            state.fail_roles.remove(index);
+            return Err(format!("Failed to start {}", spec.executable.display()));
+        }
+
+        let pid = FIRST_FAKE_PID + state.records.len() as u32;
+        state.records.push(FakeRecord {
+            pid,
+            spec: spec.clone(),
+            suspended: spec.suspended,
+            close_requested: false,
+            killed: false,
+            exit: None,
+            injected: Vec::new(),
+        });
+        Ok(Box::new(FakeProcess {
+            pid,
+            role: spec.role,
+            state: self.state.clone(),
+        }))
+    }
+}
+
+struct FakeProcess {
+    pid: u32,
+    role: ProcessRole,
+    state: Arc<Mutex<FakeState>>,
+}
+
+impl FakeProcess {
+    fn with_record<T>(&self, f: impl FnOnce(&mut FakeRecord) -> T) -> T {
+        let mut state = self.state.lock().unwrap();
+        let record = state
+            .records
+            .iter_mut()
+            .find(|r| r.pid == self.pid)
+            .expect("fake process without a record");
+        f(record)
+    }
+}
+
+impl GameProcess for FakeProcess {
+    fn pid(&self) -> u32 {
+        self.pid
+    }
+
+    fn role(&self) -> ProcessRole {
+        self.role
+    }
+
+    fn suspend(&mut self) -> Result<(), String> {
+        self.with_record(|record| record.suspended = true);
+        Ok(())
+    }
+
+    fn resume(&mut self) -> Result<(), String> {
+        self.with_record(|record| record.suspended = false);
+        Ok(())
+    }
+
+    fn try_wait(&mut self) -> Result<Option<ProcessExit>, String> {
+        Ok(self.with_record(|record| record.exit))
+    }
+
+    fn request_close(&mut self) -> Result<(), String> {
+        let close_exit = self.state.lock().unwrap().close_exit;
+        self.with_record(|record| {
+            record.close_requested = true;
+            if let (Some(code), None) = (close_exit, record.exit) {
+                record.exit = Some(ProcessExit { code: Some(code) });
+            }
+        });
+        Ok(())
+    }
+
+    fn kill(&mut self) -> Result<(), String> {
+        self.with_record(|record| {
+            if record.exit.is_none() {
+                record.killed = true;
+                record.exit = Some(ProcessExit { code: Some(1) });
+            }
+        });
+        Ok(())
+    }
+
+    fn take_output(&mut self) -> ProcessOutput {
+        Vec::new()
+    }
+
+    fn inject(&mut self, dll: &Path) -> Result<(), String> {
+        self.with_record(|record| record.injected.push(dll.to_path_buf()));
+        Ok(())
+    }
+
+    /// The executable and whatever was injected, as if nothing else loaded.
+    fn modules(&mut self) -> Result<Vec<String>, String> {
+        self.with_record(|record| {
+            if record.exit.is_some() {
+                return Err(format!("{} has exited", record.pid));
+            }
+            Ok(std::iter::once(&record.spec.executable)
+                .chain(&record.injected)
+                .map(|path| path.to_string_lossy().to_string())
+                .collect())
+        })
+    }
+}
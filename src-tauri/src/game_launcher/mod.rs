@@ -0,0 +1,479 @@
+#[cfg(any(test, feature = "fake-launcher"))]
+mod fake;
+#[cfg(all(windows, not(feature = "fake-launcher")))]
+mod windows;
+#[cfg(all(unix, not(feature = "fake-launcher")))]
+mod wine;
+
+#[cfg(any(test, feature = "fake-launcher"))]
+pub use fake::FakeLauncher;
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Manager, State};
+
+use crate::error::LauncherError;
+use crate::persist::write_atomic;
+
+const CONFIG_FILE: &str = "launcher.json";
+pub const BINARIES_DIR: &str = "FortniteGame/Binaries/Win64";
+const CLIENT_EXE: &str = "FortniteClient-Win64-Shipping.exe";
+const LAUNCHER_STUB_EXE: &str = "FortniteLauncher.exe";
+const EAC_STUB_EXE: &str = "FortniteClient-Win64-Shipping_EAC.exe";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProcessRole {
+    Client,
+    Launcher,
+    AntiCheat,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+pub type ProcessOutput = Vec<(OutputStream, Box<dyn Read + Send>)>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ProcessExit {
+    /// `None` when the process was ended by a signal rather than exiting.
+    pub code: Option<i32>,
+}
+
+#[derive(Debug, Clone)]
+pub struct LaunchSpec {
+    pub role: ProcessRole,
+    pub executable: PathBuf,
+    pub args: Vec<String>,
+    pub working_dir: PathBuf,
+    pub env: Vec<(String, String)>,
+    pub suspended: bool,
+    /// Pipe stdout and stderr back to us instead of discarding them.
+    pub capture_output: bool,
+}
+
+impl LaunchSpec {
+    /// Runs `executable` from its own folder with no arguments.
+    pub fn new(role: ProcessRole, executable: impl Into<PathBuf>) -> Self {
+        let executable = executable.into();
+        let working_dir = executable
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+        LaunchSpec {
+            role,
+            executable,
+            args: Vec::new(),
+            working_dir,
+            env: Vec::new(),
+            suspended: false,
+            capture_output: false,
+        }
+    }
+
+    pub fn args(mut self, args: &[String]) -> Self {
+        self.args.extend_from_slice(args);
+        self
+    }
+
+    pub fn suspended(mut self) -> Self {
+        self.suspended = true;
+        self
+    }
+
+    pub fn captured(mut self) -> Self {
+        self.capture_output = true;
+        self
+    }
+}
+
+/// A process started by a `GameLauncher`. Dropping it releases the handle
+/// without ending the process.
+pub trait GameProcess: Send {
+    fn pid(&self) -> u32;
+    fn role(&self) -> ProcessRole;
+    fn suspend(&mut self) -> Result<(), String>;
+    fn resume(&mut self) -> Result<(), String>;
+    /// Returns the exit once the process has ended, without blocking.
+    fn try_wait(&mut self) -> Result<Option<ProcessExit>, String>;
+    /// Asks the process to exit on its own, the way closing its window would.
+    /// Only sends the request; the process may take a while to act on it.
+    fn request_close(&mut self) -> Result<(), String>;
+    fn kill(&mut self) -> Result<(), String>;
+    /// Hands over the captured stdout and stderr pipes, once. Empty when the
+    /// spec didn't ask for capture.
+    fn take_output(&mut self) -> ProcessOutput;
+    /// Loads `dll` into the process. Meant for a client that is still
+    /// suspended.
+    fn inject(&mut self, dll: &Path) -> Result<(), String>;
+    /// The paths of the modules currently loaded in the process, the
+    /// executable first.
+    fn modules(&mut self) -> Result<Vec<String>, String>;
+}
+
+/// Starts game processes on a particular platform.
+pub trait GameLauncher: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn spawn(&self, spec: &LaunchSpec) -> Result<Box<dyn GameProcess>, String>;
+}
+
+/// Quotes one argument the way `CommandLineToArgvW` reads it back.
+fn quote_arg(arg: &str, command_line: &mut String) {
+    if !arg.is_empty() && !arg.contains([' ', '\t', '"']) {
+        command_line.push_str(arg);
+        return;
+    }
+
+    command_line.push('"');
+    let mut backslashes = 0;
+    for c in arg.chars() {
+        match c {
+            '\\' => backslashes += 1,
+            '"' => {
+                command_line.extend(std::iter::repeat('\\').take(backslashes * 2 + 1));
+                command_line.push('"');
+                backslashes = 0;
+            }
+            _ => {
+                command_line.extend(std::iter::repeat('\\').take(backslashes));
+                command_line.push(c);
+                backslashes = 0;
+            }
+        }
+    }
+    command_line.extend(std::iter::repeat('\\').take(backslashes * 2));
+    command_line.push('"');
+}
+
+/// The Windows command line for `executable` and `args`. Also used to show
+/// users exactly what a launch will run.
+pub fn command_line(executable: &str, args: &[String]) -> String {
+    let mut command_line = String::new();
+    quote_arg(executable, &mut command_line);
+    for arg in args {
+        command_line.push(' ');
+        quote_arg(arg, &mut command_line);
+    }
+    command_line
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WineRunner {
+    #[default]
+    Wine,
+    Proton,
+}
+
+/// How builds are run on Linux. Ignored on Windows.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WineConfig {
+    pub runner: WineRunner,
+    /// The `wine` binary or the `proton` script. Defaults to `wine` on PATH.
+    pub binary: Option<String>,
+    /// WINEPREFIX for Wine, STEAM_COMPAT_DATA_PATH for Proton.
+    pub prefix: Option<String>,
+    pub env: HashMap<String, String>,
+}
+
+#[cfg(feature = "fake-launcher")]
+pub fn platform_launcher(_config: &WineConfig) -> Arc<dyn GameLauncher> {
+    Arc::new(FakeLauncher::default())
+}
+
+#[cfg(all(windows, not(feature = "fake-launcher")))]
+pub fn platform_launcher(_config: &WineConfig) -> Arc<dyn GameLauncher> {
+    Arc::new(windows::WindowsLauncher)
+}
+
+#[cfg(all(unix, not(feature = "fake-launcher")))]
+pub fn platform_launcher(config: &WineConfig) -> Arc<dyn GameLauncher> {
+    Arc::new(wine::WineLauncher::new(config.clone()))
+}
+
+pub struct LauncherState {
+    config: Mutex<WineConfig>,
+    launcher: Mutex<Arc<dyn GameLauncher>>,
+}
+
+impl LauncherState {
+    pub fn load(app_handle: &AppHandle) -> Self {
+        let config: WineConfig = fs::read_to_string(config_path(app_handle))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        let launcher = platform_launcher(&config);
+        println!("Using {} game launcher", launcher.name());
+        LauncherState {
+            config: Mutex::new(config),
+            launcher: Mutex::new(launcher),
+        }
+    }
+
+    pub fn current(&self) -> Arc<dyn GameLauncher> {
+        self.launcher.lock().unwrap().clone()
+    }
+}
+
+fn config_path(app_handle: &AppHandle) -> PathBuf {
+    app_handle.path().app_data_dir().unwrap().join(CONFIG_FILE)
+}
+
+/// Where a build keeps the client and the two stubs it expects to see running.
+pub struct BuildLayout {
+    pub client: PathBuf,
+    pub launcher_stub: Option<PathBuf>,
+    pub eac_stub: Option<PathBuf>,
+}
+
+impl BuildLayout {
+    pub fn from_root(root: &Path) -> Result<Self, String> {
+        let binaries = root.join(BINARIES_DIR);
+        let client = binaries.join(CLIENT_EXE);
+        if !client.is_file() {
+            return Err(format!("{} is missing {}", root.display(), CLIENT_EXE));
+        }
+        let existing = |name: &str| Some(binaries.join(name)).filter(|path| path.is_file());
+        Ok(BuildLayout {
+            launcher_stub: existing(LAUNCHER_STUB_EXE),
+            eac_stub: existing(EAC_STUB_EXE),
+            client,
+        })
+    }
+}
+
+pub struct LaunchedBuild {
+    pub client: Box<dyn GameProcess>,
+    /// The launcher and EAC stubs, kept frozen for as long as the client runs.
+    pub helpers: Vec<Box<dyn GameProcess>>,
+}
+
+/// Starts the stubs suspended and then the client, suspended as well when
+/// `suspend_client` is set so payloads can be injected before it runs. The
+/// stubs are killed again if the client fails to start. `client_env` only
+/// applies to the client.
+pub fn launch_build(
+    launcher: &dyn GameLauncher,
+    root: &Path,
+    client_args: &[String],
+    client_env: &[(String, String)],
+    suspend_client: bool,
+) -> Result<LaunchedBuild, String> {
+    let layout = BuildLayout::from_root(root)?;
+
+    let mut helpers = Vec::new();
+    let stubs = [
+        (ProcessRole::Launcher, &layout.launcher_stub),
+        (ProcessRole::AntiCheat, &layout.eac_stub),
+    ];
+    for (role, stub) in stubs {
+        if let Some(stub) = stub {
+            match launcher.spawn(&LaunchSpec::new(role, stub).args(client_args).suspended()) {
+                Ok(process) => helpers.push(process),
+                Err(e) => {
+                    kill_all(&mut helpers);
+                    return Err(e);
+                }
+            }
+        }
+    }
+
+    let mut spec = LaunchSpec::new(ProcessRole::Client, &layout.client)
+        .args(client_args)
+        .captured();
+    spec.env = client_env.to_vec();
+    spec.suspended = suspend_client;
+    match launcher.spawn(&spec) {
+        Ok(client) => Ok(LaunchedBuild { client, helpers }),
+        Err(e) => {
+            kill_all(&mut helpers);
+            Err(e)
+        }
+    }
+}
+
+fn kill_all(processes: &mut [Box<dyn GameProcess>]) {
+    for process in processes {
+        if let Err(e) = process.kill() {
+            println!("Failed to kill {:?} process {}: {}", process.role(), process.pid(), e);
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn get_launcher_config(state: State<'_, LauncherState>) -> Result<WineConfig, LauncherError> {
+    Ok(state.config.lock().unwrap().clone())
+}
+
+#[tauri::command]
+pub async fn set_launcher_config(
+    config: WineConfig,
+    state: State<'_, LauncherState>,
+    app_handle: AppHandle,
+) -> Result<(), LauncherError> {
+    let content = serde_json::to_string_pretty(&config)?;
+    write_atomic(&config_path(&app_handle), content).map_err(|e| LauncherError::io("Failed to save launcher config", e))?;
+
+    *state.launcher.lock().unwrap() = platform_launcher(&config);
+    *state.config.lock().unwrap() = config;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fs::{fake_build, TempDir};
+
+    fn args() -> Vec<String> {
+        vec!["-epicapp=Fortnite".to_string(), "-AUTH_TYPE=exchangecode".to_string()]
+    }
+
+    #[test]
+    fn plain_arguments_are_left_alone() {
+        assert_eq!(
+            command_line("C:\\Builds\\client.exe", &args()),
+            "C:\\Builds\\client.exe -epicapp=Fortnite -AUTH_TYPE=exchangecode"
+        );
+    }
+
+    #[test]
+    fn quotes_arguments_the_way_windows_splits_them() {
+        let quoted = |arg: &str| {
+            let mut command_line = String::new();
+            quote_arg(arg, &mut command_line);
+            command_line
+        };
+        assert_eq!(quoted(""), "\"\"");
+        assert_eq!(quoted("C:\\Program Files\\Rewind"), "\"C:\\Program Files\\Rewind\"");
+        assert_eq!(quoted("-name=\"Rewind\""), "\"-name=\\\"Rewind\\\"\"");
+        assert_eq!(quoted("-dir=C:\\My Builds\\"), "\"-dir=C:\\My Builds\\\\\"");
+        // Backslashes only double up in front of a quote.
+        assert_eq!(quoted("a\\\\b c"), "\"a\\\\b c\"");
+        assert_eq!(quoted("a\\\"b"), "\"a\\\\\\\"b\"");
+        assert_eq!(quoted("tab\there"), "\"tab\there\"");
+    }
+
+    #[test]
+    fn command_line_quotes_the_executable_too() {
+        let args = vec!["-log".to_string(), "two words".to_string()];
+        assert_eq!(
+            command_line("C:\\Program Files\\client.exe", &args),
+            "\"C:\\Program Files\\client.exe\" -log \"two words\""
+        );
+    }
+
+    #[test]
+    fn starts_the_stubs_frozen_before_the_client() {
+        let dir = TempDir::new("launch", "order");
+        let root = fake_build(&dir, "build", true);
+        let launcher = FakeLauncher::default();
+        let env = vec![("UE_LOG".to_string(), "1".to_string())];
+
+        let launched = launch_build(&launcher, &root, &args(), &env, false).unwrap();
+        assert_eq!(launched.client.role(), ProcessRole::Client);
+        assert_eq!(launched.helpers.len(), 2);
+
+        let records = launcher.records();
+        let roles: Vec<ProcessRole> = records.iter().map(|record| record.spec.role).collect();
+        assert_eq!(roles, [ProcessRole::Launcher, ProcessRole::AntiCheat, ProcessRole::Client]);
+        for stub in &records[..2] {
+            assert!(stub.suspended);
+            assert_eq!(stub.spec.args, args());
+            assert!(stub.spec.env.is_empty());
+            assert!(!stub.spec.capture_output);
+        }
+        let client = &records[2];
+        assert!(!client.suspended);
+        assert!(client.spec.capture_output);
+        assert_eq!(client.spec.env, env);
+        assert_eq!(client.spec.working_dir, root.join(BINARIES_DIR));
+    }
+
+    #[test]
+    fn holds_the_client_for_injection() {
+        let dir = TempDir::new("launch", "hold");
+        let root = fake_build(&dir, "build", false);
+        let launcher = FakeLauncher::default();
+
+        let mut launched = launch_build(&launcher, &root, &args(), &[], true).unwrap();
+        assert!(launched.helpers.is_empty());
+        assert!(launcher.records()[0].suspended);
+
+        let dll = root.join("payload.dll");
+        launched.client.inject(&dll).unwrap();
+        launched.client.resume().unwrap();
+        let client = &launcher.records()[0];
+        assert_eq!(client.injected, [dll]);
+        assert!(!client.suspended);
+    }
+
+    #[test]
+    fn a_client_that_fails_to_start_takes_the_stubs_down() {
+        let dir = TempDir::new("launch", "client-fails");
+        let root = fake_build(&dir, "build", true);
+        let launcher = FakeLauncher::default();
+        launcher.fail_next(ProcessRole::Client);
+
+        assert!(launch_build(&launcher, &root, &args(), &[], false).is_err());
+        let records = launcher.records();
+        assert_eq!(records.len(), 2);
+        assert!(records.iter().all(|record| record.killed));
+    }
+
+    #[test]
+    fn a_stub_that_fails_to_start_stops_the_launch() {
+        let dir = TempDir::new("launch", "stub-fails");
+        let root = fake_build(&dir, "build", true);
+        let launcher = FakeLauncher::default();
+        launcher.fail_next(ProcessRole::AntiCheat);
+
+        assert!(launch_build(&launcher, &root, &args(), &[], false).is_err());
+        let records = launcher.records();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].spec.role, ProcessRole::Launcher);
+        assert!(records[0].killed);
+    }
+
+    #[test]
+    fn refuses_a_folder_without_a_client() {
+        let dir = TempDir::new("launch", "no-client");
+        let root = fake_build(&dir, "build", false);
+        fs::remove_file(root.join(BINARIES_DIR).join(CLIENT_EXE)).unwrap();
+        let launcher = FakeLauncher::default();
+
+        let e = launch_build(&launcher, &root, &args(), &[], false).err().unwrap();
+        assert!(e.contains(CLIENT_EXE));
+        assert!(launcher.records().is_empty());
+    }
+
+    #[test]
+    fn fake_processes_exit_when_told() {
+        let launcher = FakeLauncher::default();
+        let mut listens = launcher
+            .spawn(&LaunchSpec::new(ProcessRole::Client, "client.exe"))
+            .unwrap();
+        listens.request_close().unwrap();
+        assert_eq!(listens.try_wait().unwrap(), None);
+
+        launcher.exit_on_close(0);
+        listens.request_close().unwrap();
+        assert_eq!(listens.try_wait().unwrap(), Some(ProcessExit { code: Some(0) }));
+        listens.kill().unwrap();
+        assert!(!launcher.records()[0].killed);
+
+        let mut stubborn = launcher
+            .spawn(&LaunchSpec::new(ProcessRole::Launcher, "stub.exe"))
+            .unwrap();
+        launcher.exit(stubborn.pid(), Some(-1));
+        assert_eq!(stubborn.try_wait().unwrap(), Some(ProcessExit { code: Some(-1) }));
+        assert!(!launcher.records()[1].close_requested);
+    }
+}
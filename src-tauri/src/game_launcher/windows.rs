@@ -0,0 +1,382 @@
+use std::ffi::{OsStr, OsString};
+use std::fs::File;
+use std::mem;
+use std::os::windows::ffi::{OsStrExt, OsStringExt};
+use std::os::windows::io::FromRawHandle;
+use std::path::Path;
+use std::ptr;
+use winapi::shared::minwindef::{BOOL, DWORD, FALSE, HMODULE, LPARAM, MAX_PATH, TRUE};
+use winapi::shared::windef::HWND;
+use winapi::um::handleapi::{CloseHandle, SetHandleInformation};
+use winapi::um::libloaderapi::{GetModuleHandleW, GetProcAddress};
+use winapi::um::memoryapi::{VirtualAllocEx, VirtualFreeEx, WriteProcessMemory};
+use winapi::um::minwinbase::{LPTHREAD_START_ROUTINE, SECURITY_ATTRIBUTES};
+use winapi::um::namedpipeapi::CreatePipe;
+use winapi::um::psapi::{EnumProcessModulesEx, GetModuleFileNameExW, LIST_MODULES_ALL};
+use winapi::um::processthreadsapi::{
+    CreateProcessW, CreateRemoteThread, GetExitCodeProcess, GetExitCodeThread, ResumeThread, SuspendThread, TerminateProcess, PROCESS_INFORMATION,
+    STARTUPINFOW,
+};
+use winapi::um::synchapi::WaitForSingleObject;
+use winapi::um::winbase::{
+    CREATE_SUSPENDED, CREATE_UNICODE_ENVIRONMENT, HANDLE_FLAG_INHERIT, STARTF_USESTDHANDLES, WAIT_OBJECT_0,
+};
+use winapi::um::winnt::{HANDLE, MEM_COMMIT, MEM_RELEASE, MEM_RESERVE, PAGE_READWRITE};
+use winapi::um::winuser::{EnumWindows, GetWindowThreadProcessId, PostMessageW, WM_CLOSE};
+
+// How long LoadLibraryW gets to return inside the game.
+const INJECT_TIMEOUT_MS: DWORD = 10_000;
+
+use super::{command_line, GameLauncher, GameProcess, LaunchSpec, OutputStream, ProcessExit, ProcessOutput, ProcessRole};
+
+fn to_wide(value: &OsStr) -> Vec<u16> {
+    value.encode_wide().chain(std::iter::once(0)).collect()
+}
+
+/// The inherited environment with `spec.env` applied, as a double-NUL
+/// terminated UTF-16 block sorted by name without regard to case, the way
+/// Windows keeps it. Variables that aren't valid Unicode are passed through
+/// untouched.
+fn environment_block(spec: &LaunchSpec) -> Vec<u16> {
+    let overridden = |key: &OsStr| {
+        spec.env
+            .iter()
+            .any(|(k, _)| key.to_str().is_some_and(|key| k.eq_ignore_ascii_case(key)))
+    };
+    let mut vars: Vec<(OsString, OsString)> = std::env::vars_os().filter(|(key, _)| !overridden(key)).collect();
+    vars.extend(spec.env.iter().map(|(key, value)| (key.into(), value.into())));
+    vars.sort_by_cached_key(|(key, _)| key.to_string_lossy().to_uppercase());
+
+    let mut block = Vec::new();
+    for (key, value) in vars {
+        block.extend(key.encode_wide());
+        block.push(u16::from(b'='));
+        block.extend(value.encode_wide());
+        block.push(0);
+    }
+    block.push(0);
+    block
+}
+
+/// A pipe whose write end the child inherits and whose read end stays ours.
+struct OutputPipe {
+    read: HANDLE,
+    write: HANDLE,
+}
+
+impl OutputPipe {
+    fn new() -> Result<Self, String> {
+        let mut attributes = SECURITY_ATTRIBUTES {
+            nLength: mem::size_of::<SECURITY_ATTRIBUTES>() as DWORD,
+            lpSecurityDescriptor: ptr::null_mut(),
+            bInheritHandle: TRUE,
+        };
+        let mut read = ptr::null_mut();
+        let mut write = ptr::null_mut();
+        unsafe {
+            if CreatePipe(&mut read, &mut write, &mut attributes, 0) == 0 {
+                return Err(format!("Failed to create output pipe: {}", std::io::Error::last_os_error()));
+            }
+            SetHandleInformation(read, HANDLE_FLAG_INHERIT, 0);
+        }
+        Ok(OutputPipe { read, write })
+    }
+
+    /// Drops our copy of the write end so reads end when the child exits.
+    fn into_reader(mut self) -> File {
+        unsafe { CloseHandle(self.write) };
+        self.write = ptr::null_mut();
+        let read = mem::replace(&mut self.read, ptr::null_mut());
+        unsafe { File::from_raw_handle(read as _) }
+    }
+}
+
+/// What `close_windows` is looking for and how many it found.
+struct CloseRequest {
+    pid: DWORD,
+    closed: usize,
+}
+
+unsafe extern "system" fn close_window(window: HWND, param: LPARAM) -> BOOL {
+    let request = &mut *(param as *mut CloseRequest);
+    let mut pid: DWORD = 0;
+    GetWindowThreadProcessId(window, &mut pid);
+    if pid == request.pid && PostMessageW(window, WM_CLOSE, 0, 0) != 0 {
+        request.closed += 1;
+    }
+    TRUE
+}
+
+/// Posts `WM_CLOSE` to every top-level window `pid` owns and returns how many
+/// there were.
+fn close_windows(pid: DWORD) -> usize {
+    let mut request = CloseRequest { pid, closed: 0 };
+    unsafe { EnumWindows(Some(close_window), &mut request as *mut CloseRequest as LPARAM) };
+    request.closed
+}
+
+impl Drop for OutputPipe {
+    fn drop(&mut self) {
+        for handle in [self.read, self.write] {
+            if !handle.is_null() {
+                unsafe { CloseHandle(handle) };
+            }
+        }
+    }
+}
+
+/// Starts processes with `CreateProcessW`, the way the launcher always has.
+pub struct WindowsLauncher;
+
+impl GameLauncher for WindowsLauncher {
+    fn name(&self) -> &'static str {
+        "windows"
+    }
+
+    fn spawn(&self, spec: &LaunchSpec) -> Result<Box<dyn GameProcess>, String> {
+        let mut command_line = to_wide(OsStr::new(&command_line(&spec.executable.to_string_lossy(), &spec.args)));
+        let working_dir = to_wide(spec.working_dir.as_os_str());
+        let mut environment = (!spec.env.is_empty()).then(|| environment_block(spec));
+
+        let mut flags: DWORD = 0;
+        if spec.suspended {
+            flags |= CREATE_SUSPENDED;
+        }
+        if environment.is_some() {
+            flags |= CREATE_UNICODE_ENVIRONMENT;
+        }
+
+        let mut startup_info: STARTUPINFOW = unsafe { mem::zeroed() };
+        startup_info.cb = mem::size_of::<STARTUPINFOW>() as DWORD;
+        let mut process_info: PROCESS_INFORMATION = unsafe { mem::zeroed() };
+
+        let pipes = if spec.capture_output {
+            let pipes = (OutputPipe::new()?, OutputPipe::new()?);
+            startup_info.dwFlags |= STARTF_USESTDHANDLES;
+            startup_info.hStdOutput = pipes.0.write;
+            startup_info.hStdError = pipes.1.write;
+            Some(pipes)
+        } else {
+            None
+        };
+
+        let created = unsafe {
+            CreateProcessW(
+                ptr::null(),
+                command_line.as_mut_ptr(),
+                ptr::null_mut(),
+                ptr::null_mut(),
+                if pipes.is_some() { TRUE } else { FALSE },
+                flags,
+                environment
+                    .as_mut()
+                    .map(|block| block.as_mut_ptr() as *mut _)
+                    .unwrap_or(ptr::null_mut()),
+                working_dir.as_ptr(),
+                &mut startup_info,
+                &mut process_info,
+            )
+        };
+        if created == 0 {
+            return Err(format!(
+                "Failed to start {}: {}",
+                spec.executable.display(),
+                std::io::Error::last_os_error()
+            ));
+        }
+
+        let output: ProcessOutput = match pipes {
+            Some((stdout, stderr)) => vec![
+                (OutputStream::Stdout, Box::new(stdout.into_reader())),
+                (OutputStream::Stderr, Box::new(stderr.into_reader())),
+            ],
+            None => Vec::new(),
+        };
+
+        println!("Started {:?} process with PID: {}", spec.role, process_info.dwProcessId);
+        Ok(Box::new(WindowsProcess {
+            role: spec.role,
+            pid: process_info.dwProcessId,
+            process: process_info.hProcess,
+            thread: process_info.hThread,
+            exit: None,
+            output,
+        }))
+    }
+}
+
+struct WindowsProcess {
+    role: ProcessRole,
+    pid: u32,
+    process: HANDLE,
+    thread: HANDLE,
+    exit: Option<ProcessExit>,
+    output: ProcessOutput,
+}
+
+// The handles are only used through &mut self.
+unsafe impl Send for WindowsProcess {}
+
+impl GameProcess for WindowsProcess {
+    fn pid(&self) -> u32 {
+        self.pid
+    }
+
+    fn role(&self) -> ProcessRole {
+        self.role
+    }
+
+    fn suspend(&mut self) -> Result<(), String> {
+        if unsafe { SuspendThread(self.thread) } == DWORD::MAX {
+            return Err(format!("Failed to suspend {}: {}", self.pid, std::io::Error::last_os_error()));
+        }
+        Ok(())
+    }
+
+    fn resume(&mut self) -> Result<(), String> {
+        if unsafe { ResumeThread(self.thread) } == DWORD::MAX {
+            return Err(format!("Failed to resume {}: {}", self.pid, std::io::Error::last_os_error()));
+        }
+        Ok(())
+    }
+
+    fn try_wait(&mut self) -> Result<Option<ProcessExit>, String> {
+        if self.exit.is_some() {
+            return Ok(self.exit);
+        }
+        if unsafe { WaitForSingleObject(self.process, 0) } != WAIT_OBJECT_0 {
+            return Ok(None);
+        }
+
+        let mut code: DWORD = 0;
+        if unsafe { GetExitCodeProcess(self.process, &mut code) } == 0 {
+            return Err(std::io::Error::last_os_error().to_string());
+        }
+        self.exit = Some(ProcessExit {
+            code: Some(code as i32),
+        });
+        Ok(self.exit)
+    }
+
+    fn request_close(&mut self) -> Result<(), String> {
+        if self.try_wait()?.is_some() {
+            return Ok(());
+        }
+        if close_windows(self.pid) == 0 {
+            return Err(format!("{} has no window to close", self.pid));
+        }
+        Ok(())
+    }
+
+    fn kill(&mut self) -> Result<(), String> {
+        if self.try_wait()?.is_some() {
+            return Ok(());
+        }
+        if unsafe { TerminateProcess(self.process, 1) } == 0 {
+            return Err(format!("Failed to terminate {}: {}", self.pid, std::io::Error::last_os_error()));
+        }
+        Ok(())
+    }
+
+    fn take_output(&mut self) -> ProcessOutput {
+        mem::take(&mut self.output)
+    }
+
+    /// Writes the DLL path into the process and runs `LoadLibraryW` on it from
+    /// a remote thread.
+    fn inject(&mut self, dll: &Path) -> Result<(), String> {
+        let path = to_wide(dll.as_os_str());
+        let size = path.len() * mem::size_of::<u16>();
+
+        unsafe {
+            let remote = VirtualAllocEx(self.process, ptr::null_mut(), size, MEM_COMMIT | MEM_RESERVE, PAGE_READWRITE);
+            if remote.is_null() {
+                return Err(format!("Failed to allocate memory in {}: {}", self.pid, std::io::Error::last_os_error()));
+            }
+            if WriteProcessMemory(self.process, remote, path.as_ptr() as *const _, size, ptr::null_mut()) == 0 {
+                let error = std::io::Error::last_os_error();
+                VirtualFreeEx(self.process, remote, 0, MEM_RELEASE);
+                return Err(format!("Failed to write to {}: {}", self.pid, error));
+            }
+
+            // kernel32 sits at the same address in every process of a session.
+            let kernel32 = GetModuleHandleW(to_wide(OsStr::new("kernel32.dll")).as_ptr());
+            let load_library = GetProcAddress(kernel32, b"LoadLibraryW\0".as_ptr() as *const _);
+            if load_library.is_null() {
+                VirtualFreeEx(self.process, remote, 0, MEM_RELEASE);
+                return Err("LoadLibraryW is not available".to_string());
+            }
+            let start: LPTHREAD_START_ROUTINE = mem::transmute(load_library);
+
+            let thread = CreateRemoteThread(self.process, ptr::null_mut(), 0, start, remote, 0, ptr::null_mut());
+            if thread.is_null() {
+                let error = std::io::Error::last_os_error();
+                VirtualFreeEx(self.process, remote, 0, MEM_RELEASE);
+                return Err(format!("Failed to start the loader thread in {}: {}", self.pid, error));
+            }
+
+            let waited = WaitForSingleObject(thread, INJECT_TIMEOUT_MS);
+            let mut module: DWORD = 0;
+            GetExitCodeThread(thread, &mut module);
+            CloseHandle(thread);
+            if waited != WAIT_OBJECT_0 {
+                // The thread may still read the path, so the memory is left alone.
+                return Err(format!("Timed out injecting {}", dll.display()));
+            }
+            VirtualFreeEx(self.process, remote, 0, MEM_RELEASE);
+
+            if module == 0 {
+                return Err(format!("The game failed to load {}", dll.display()));
+            }
+        }
+        println!("Injected {} into {}", dll.display(), self.pid);
+        Ok(())
+    }
+
+    fn modules(&mut self) -> Result<Vec<String>, String> {
+        let handle_size = mem::size_of::<HMODULE>();
+        let mut handles: Vec<HMODULE> = Vec::new();
+        // Modules can load between asking for the count and reading them, so
+        // retry until the buffer is big enough.
+        loop {
+            let mut needed: DWORD = 0;
+            let listed = unsafe {
+                EnumProcessModulesEx(
+                    self.process,
+                    handles.as_mut_ptr(),
+                    (handles.len() * handle_size) as DWORD,
+                    &mut needed,
+                    LIST_MODULES_ALL,
+                )
+            };
+            if listed == 0 {
+                return Err(format!("Failed to list the modules of {}: {}", self.pid, std::io::Error::last_os_error()));
+            }
+            let count = needed as usize / handle_size;
+            if count <= handles.len() {
+                handles.truncate(count);
+                break;
+            }
+            handles.resize(count, ptr::null_mut());
+        }
+
+        let mut buffer = [0u16; MAX_PATH * 4];
+        Ok(handles
+            .into_iter()
+            .filter_map(|module| {
+                let length =
+                    unsafe { GetModuleFileNameExW(self.process, module, buffer.as_mut_ptr(), buffer.len() as DWORD) };
+                (length != 0).then(|| OsString::from_wide(&buffer[..length as usize]).to_string_lossy().to_string())
+            })
+            .collect())
+    }
+}
+
+impl Drop for WindowsProcess {
+    fn drop(&mut self) {
+        unsafe {
+            CloseHandle(self.thread);
+            CloseHandle(self.process);
+        }
+    }
+}
@@ -0,0 +1,260 @@
+use std::fs;
+use std::os::unix::process::CommandExt;
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+
+use crate::process_tree;
+
+use super::{
+    GameLauncher, GameProcess, LaunchSpec, OutputStream, ProcessExit, ProcessOutput, ProcessRole, WineConfig,
+    WineRunner,
+};
+
+/// Runs builds through Wine or Proton. Each process gets its own process
+/// group so the Wine helpers it starts are suspended and killed with it.
+pub struct WineLauncher {
+    config: WineConfig,
+}
+
+impl WineLauncher {
+    pub fn new(config: WineConfig) -> Self {
+        WineLauncher { config }
+    }
+
+    fn command(&self, spec: &LaunchSpec) -> Result<Command, String> {
+        let mut command = match self.config.runner {
+            WineRunner::Wine => {
+                let mut command = Command::new(self.config.binary.as_deref().unwrap_or("wine"));
+                if let Some(prefix) = &self.config.prefix {
+                    command.env("WINEPREFIX", prefix);
+                }
+                command
+            }
+            WineRunner::Proton => {
+                let script = self
+                    .config
+                    .binary
+                    .as_deref()
+                    .ok_or("Set the path to the proton script to use Proton")?;
+                let prefix = self
+                    .config
+                    .prefix
+                    .as_deref()
+                    .ok_or("Set a compat data folder to use Proton")?;
+                let mut command = Command::new(script);
+                command
+                    .arg("run")
+                    .env("STEAM_COMPAT_DATA_PATH", prefix)
+                    .env("STEAM_COMPAT_CLIENT_INSTALL_PATH", "");
+                command
+            }
+        };
+
+        command
+            .arg(&spec.executable)
+            .args(&spec.args)
+            .current_dir(&spec.working_dir)
+            .env("WINEDEBUG", "-all")
+            .envs(&self.config.env)
+            .envs(spec.env.iter().map(|(k, v)| (k, v)))
+            .stdin(Stdio::null())
+            .process_group(0);
+        if spec.capture_output {
+            command.stdout(Stdio::piped()).stderr(Stdio::piped());
+        }
+        Ok(command)
+    }
+}
+
+impl GameLauncher for WineLauncher {
+    fn name(&self) -> &'static str {
+        match self.config.runner {
+            WineRunner::Wine => "wine",
+            WineRunner::Proton => "proton",
+        }
+    }
+
+    /// Unix can't create a process suspended, so a suspended spec is stopped
+    /// right after it starts. Wine is still loading at that point and the
+    /// game itself has not run.
+    fn spawn(&self, spec: &LaunchSpec) -> Result<Box<dyn GameProcess>, String> {
+        let child = self
+            .command(spec)?
+            .spawn()
+            .map_err(|e| format!("Failed to start {} under {}: {}", spec.executable.display(), self.name(), e))?;
+
+        println!("Started {:?} process under {} with PID: {}", spec.role, self.name(), child.id());
+        let mut process = WineProcess {
+            role: spec.role,
+            child,
+            exit: None,
+        };
+        if spec.suspended {
+            if let Err(e) = process.suspend() {
+                let _ = process.kill();
+                return Err(e);
+            }
+        }
+        Ok(Box::new(process))
+    }
+}
+
+struct WineProcess {
+    role: ProcessRole,
+    child: Child,
+    exit: Option<ProcessExit>,
+}
+
+impl WineProcess {
+    fn signal_group(&self, signal: libc::c_int) -> Result<(), String> {
+        // The child leads its own group, so its PID is the group ID.
+        if unsafe { libc::kill(-(self.child.id() as libc::pid_t), signal) } != 0 {
+            return Err(std::io::Error::last_os_error().to_string());
+        }
+        Ok(())
+    }
+}
+
+impl GameProcess for WineProcess {
+    fn pid(&self) -> u32 {
+        self.child.id()
+    }
+
+    fn role(&self) -> ProcessRole {
+        self.role
+    }
+
+    fn suspend(&mut self) -> Result<(), String> {
+        self.signal_group(libc::SIGSTOP)
+            .map_err(|e| format!("Failed to suspend {}: {}", self.pid(), e))
+    }
+
+    fn resume(&mut self) -> Result<(), String> {
+        self.signal_group(libc::SIGCONT)
+            .map_err(|e| format!("Failed to resume {}: {}", self.pid(), e))
+    }
+
+    fn try_wait(&mut self) -> Result<Option<ProcessExit>, String> {
+        if self.exit.is_none() {
+            self.exit = self
+                .child
+                .try_wait()
+                .map_err(|e| e.to_string())?
+                .map(|status| ProcessExit { code: status.code() });
+        }
+        Ok(self.exit)
+    }
+
+    /// Wine shuts a process down normally on SIGTERM.
+    fn request_close(&mut self) -> Result<(), String> {
+        if self.try_wait()?.is_some() {
+            return Ok(());
+        }
+        self.signal_group(libc::SIGTERM)
+            .map_err(|e| format!("Failed to ask {} to close: {}", self.pid(), e))
+    }
+
+    fn kill(&mut self) -> Result<(), String> {
+        if self.try_wait()?.is_some() {
+            return Ok(());
+        }
+        self.signal_group(libc::SIGKILL)
+            .map_err(|e| format!("Failed to kill {}: {}", self.pid(), e))?;
+        // Reap it so it doesn't linger as a zombie.
+        let status = self.child.wait().map_err(|e| e.to_string())?;
+        self.exit = Some(ProcessExit { code: status.code() });
+        Ok(())
+    }
+
+    fn take_output(&mut self) -> ProcessOutput {
+        let mut output: ProcessOutput = Vec::new();
+        if let Some(stdout) = self.child.stdout.take() {
+            output.push((OutputStream::Stdout, Box::new(stdout)));
+        }
+        if let Some(stderr) = self.child.stderr.take() {
+            output.push((OutputStream::Stderr, Box::new(stderr)));
+        }
+        output
+    }
+
+    /// There is no way to reach into a Wine process from the Linux side.
+    fn inject(&mut self, dll: &Path) -> Result<(), String> {
+        Err(format!("Can't inject {} under Wine, payloads need Windows", dll.display()))
+    }
+
+    /// Wine maps every module it loads from a file, so the game's modules
+    /// are the `.exe` and `.dll` files mapped into the group's processes.
+    fn modules(&mut self) -> Result<Vec<String>, String> {
+        let system = process_tree::snapshot();
+        let mut pids = vec![self.pid()];
+        pids.extend(process_tree::descendants(&system, self.pid()).into_iter().map(|identity| identity.pid));
+
+        let mut modules = Vec::new();
+        let mut read_any = false;
+        for pid in pids {
+            let Ok(maps) = fs::read_to_string(format!("/proc/{}/maps", pid)) else {
+                continue;
+            };
+            read_any = true;
+            for path in mapped_modules(&maps) {
+                if !modules.contains(&path) {
+                    modules.push(path);
+                }
+            }
+        }
+        if !read_any {
+            return Err(format!("Can't read the memory map of {}", self.pid()));
+        }
+        Ok(modules)
+    }
+}
+
+/// The `.exe` and `.dll` files in a `/proc/<pid>/maps` listing, each once,
+/// in the order they first appear.
+fn mapped_modules(maps: &str) -> Vec<String> {
+    let mut modules: Vec<String> = Vec::new();
+    for line in maps.lines() {
+        let Some(path) = mapped_path(line) else {
+            continue;
+        };
+        let lower = path.to_ascii_lowercase();
+        if (lower.ends_with(".dll") || lower.ends_with(".exe")) && !modules.iter().any(|m| m == path) {
+            modules.push(path.to_string());
+        }
+    }
+    modules
+}
+
+/// The path after the address, perms, offset, device and inode fields. The
+/// path itself may hold spaces.
+fn mapped_path(line: &str) -> Option<&str> {
+    let mut rest = line;
+    for _ in 0..5 {
+        rest = rest.trim_start().split_once(char::is_whitespace)?.1;
+    }
+    Some(rest.trim()).filter(|path| !path.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lists_mapped_modules_once() {
+        let maps = "\
+140000000-140001000 r--p 00000000 08:01 1234   /games/Fortnite Game/FortniteClient-Win64-Shipping.exe
+140001000-140200000 r-xp 00001000 08:01 1234   /games/Fortnite Game/FortniteClient-Win64-Shipping.exe
+7f0000000000-7f0000001000 r--p 00000000 08:01 99   /prefix/drive_c/windows/system32/KERNEL32.DLL
+7f0000002000-7f0000003000 rw-p 00000000 00:00 0
+7f0000004000-7f0000005000 r--p 00000000 08:01 42   /usr/lib/wine/x86_64-unix/ntdll.so
+7ffd00000000-7ffd00021000 rw-p 00000000 00:00 0    [stack]
+";
+        assert_eq!(
+            mapped_modules(maps),
+            vec![
+                "/games/Fortnite Game/FortniteClient-Win64-Shipping.exe",
+                "/prefix/drive_c/windows/system32/KERNEL32.DLL",
+            ]
+        );
+    }
+}
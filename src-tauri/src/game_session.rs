@@ -0,0 +1,877 @@
+use futures_util::future::join_all;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::mem;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::crash_report::{crash_report_path, new_crash_folders, write_crash_bundle, CrashContext, LOG_TAIL_LINES};
+use crate::error::LauncherError;
+use crate::game_launcher::{launch_build, LaunchedBuild, LauncherState, OutputStream, ProcessExit, ProcessRole};
+use crate::launch_profiles::redact_args;
+use crate::library_metadata::{add_play_time, record_play_started};
+use crate::process_tree::{self, ProcessIdentity};
+use crate::session_log::{logs_dir, prune_session_logs, read_log, LogFilter, LogLine, LogSource, SessionLog};
+use crate::VersionState;
+
+const MONITOR_INTERVAL: Duration = Duration::from_millis(500);
+// Play time is written out this often while the game runs, so closing the
+// launcher mid-session loses at most this much.
+const PLAY_FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+// The client's module list is read this often while it runs. A crashed
+// client's list is gone, so the report gets the last one taken.
+const MODULE_SNAPSHOT_INTERVAL: Duration = Duration::from_secs(30);
+// How long a client gets to close on its own before it is killed, unless the
+// caller says otherwise.
+const DEFAULT_STOP_TIMEOUT: Duration = Duration::from_secs(10);
+const STOP_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum GameState {
+    Launching,
+    Injecting,
+    Running,
+    Exited { code: Option<i32> },
+    Crashed { code: Option<i32> },
+}
+
+impl GameState {
+    pub fn is_active(&self) -> bool {
+        matches!(self, GameState::Launching | GameState::Injecting | GameState::Running)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionInfo {
+    pub id: String,
+    pub build: String,
+    /// The account it was launched as.
+    pub account_id: Option<String>,
+    pub pid: Option<u32>,
+    #[serde(flatten)]
+    pub state: GameState,
+}
+
+/// One launch of one build: the client, its helper stubs and where it is in
+/// its lifecycle.
+pub struct GameSession {
+    id: String,
+    build: String,
+    account_id: Option<String>,
+    args: Vec<String>,
+    started_at: SystemTime,
+    state: Mutex<GameState>,
+    processes: Mutex<Option<LaunchedBuild>>,
+    client_held: AtomicBool,
+    stop_requested: AtomicBool,
+    log: Mutex<Option<Arc<SessionLog>>>,
+    injected_payloads: Mutex<Vec<String>>,
+    loaded_modules: Mutex<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct LogEvent<'a> {
+    session_id: &'a str,
+    #[serde(flatten)]
+    line: &'a LogLine,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StopStep {
+    CloseRequest,
+    Wait,
+    Terminate,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StepOutcome {
+    pub step: StopStep,
+    pub ok: bool,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StopOutcome {
+    /// Exited on its own after being asked to.
+    Closed,
+    Terminated,
+    /// Gone before we got to it, or its PID now belongs to something else.
+    AlreadyExited,
+    Failed,
+}
+
+/// How stopping one process went, step by step.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProcessStopResult {
+    pub pid: u32,
+    pub name: String,
+    /// `None` for processes the client started itself.
+    pub role: Option<ProcessRole>,
+    pub outcome: StopOutcome,
+    pub steps: Vec<StepOutcome>,
+}
+
+impl ProcessStopResult {
+    fn new(pid: u32, name: String, role: Option<ProcessRole>) -> Self {
+        ProcessStopResult {
+            pid,
+            name,
+            role,
+            outcome: StopOutcome::Failed,
+            steps: Vec::new(),
+        }
+    }
+
+    fn step(&mut self, step: StopStep, ok: bool, message: impl Into<String>) {
+        self.steps.push(StepOutcome {
+            step,
+            ok,
+            message: message.into(),
+        });
+    }
+
+    fn summary(&self) -> String {
+        let steps: Vec<String> = self
+            .steps
+            .iter()
+            .map(|s| format!("{:?}: {}", s.step, s.message))
+            .collect();
+        format!("{} ({}) {:?}: {}", self.name, self.pid, self.outcome, steps.join("; "))
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionStopResult {
+    pub session_id: String,
+    pub build: String,
+    pub processes: Vec<ProcessStopResult>,
+}
+
+/// Kills a process the client started, by PID, if the PID is still its.
+fn terminate_child(identity: &ProcessIdentity) -> ProcessStopResult {
+    let mut result = ProcessStopResult::new(identity.pid, identity.name.clone(), None);
+    match process_tree::terminate(identity) {
+        Ok(true) => {
+            result.outcome = StopOutcome::Terminated;
+            result.step(StopStep::Terminate, true, "Killed");
+        }
+        Ok(false) => {
+            result.outcome = StopOutcome::AlreadyExited;
+            result.step(StopStep::Terminate, true, "Already gone or its PID was reused; left alone");
+        }
+        Err(e) => result.step(StopStep::Terminate, false, e.to_string()),
+    }
+    result
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct CrashReportEvent {
+    session_id: String,
+    build: String,
+    path: String,
+}
+
+impl GameSession {
+    fn new(build: String, account_id: Option<String>, args: Vec<String>) -> Self {
+        let started_at = SystemTime::now();
+        let id = format!(
+            "{:x}",
+            started_at
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_nanos())
+                .unwrap_or(0)
+        );
+        GameSession {
+            id,
+            build,
+            account_id,
+            args,
+            started_at,
+            state: Mutex::new(GameState::Launching),
+            processes: Mutex::new(None),
+            client_held: AtomicBool::new(false),
+            stop_requested: AtomicBool::new(false),
+            log: Mutex::new(None),
+            injected_payloads: Mutex::new(Vec::new()),
+            loaded_modules: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Remembers a DLL injected into the client, for the crash report.
+    fn record_payload(&self, path: &str) {
+        self.note(&format!("Injected {}", path));
+        self.injected_payloads.lock().unwrap().push(path.to_string());
+    }
+
+    /// Reads the client's module list, for the crash report. A failed read
+    /// keeps the previous list.
+    fn snapshot_modules(&self) {
+        let modules = match self.processes.lock().unwrap().as_mut() {
+            Some(launched) => launched.client.modules(),
+            None => return,
+        };
+        match modules {
+            Ok(modules) => *self.loaded_modules.lock().unwrap() = modules,
+            Err(e) => self.note(&format!("Failed to read the loaded modules: {}", e)),
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn state(&self) -> GameState {
+        *self.state.lock().unwrap()
+    }
+
+    pub fn client_pid(&self) -> Option<u32> {
+        self.processes
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|launched| launched.client.pid())
+    }
+
+    pub fn info(&self) -> SessionInfo {
+        SessionInfo {
+            id: self.id.clone(),
+            build: self.build.clone(),
+            account_id: self.account_id.clone(),
+            pid: self.client_pid(),
+            state: self.state(),
+        }
+    }
+
+    fn set_state(&self, app_handle: &AppHandle, state: GameState) {
+        *self.state.lock().unwrap() = state;
+        self.note(&format!("State changed to {:?}", state));
+        let _ = app_handle.emit("game-state-changed", self.info());
+    }
+
+    /// Writes a launcher line into the session log, if there is one.
+    fn note(&self, text: &str) {
+        if let Some(log) = self.log.lock().unwrap().as_ref() {
+            log.write(LogSource::Launcher, text);
+        }
+    }
+
+    /// Opens the session's log under `logs/<id>`, forwarding each line as a
+    /// `game-log-line` event.
+    fn open_log(&self, app_handle: &AppHandle) {
+        let root = logs_dir(app_handle);
+        let events = app_handle.clone();
+        let session_id = self.id.clone();
+        let created = SessionLog::create(&root.join(&self.id), move |line| {
+            let _ = events.emit("game-log-line", LogEvent {
+                session_id: &session_id,
+                line,
+            });
+        });
+        match created {
+            Ok(log) => *self.log.lock().unwrap() = Some(log),
+            Err(e) => println!("Failed to create log for {}: {}", self.build, e),
+        }
+        prune_session_logs(&root);
+    }
+
+    fn finish_log(&self) {
+        if let Some(log) = self.log.lock().unwrap().as_ref() {
+            log.finish();
+        }
+    }
+
+    /// Starts copying the client's output and the build's UE log into the
+    /// session log.
+    fn capture_output(&self, launched: &mut LaunchedBuild) {
+        let Some(log) = self.log.lock().unwrap().clone() else {
+            return;
+        };
+        for (stream, reader) in launched.client.take_output() {
+            let source = match stream {
+                OutputStream::Stdout => LogSource::Stdout,
+                OutputStream::Stderr => LogSource::Stderr,
+            };
+            log.capture(source, reader);
+        }
+        log.tail_game_log(Path::new(&self.build));
+    }
+
+    /// Resumes the client if it was held for injection, marks the session
+    /// running and starts watching it.
+    pub fn run(self: &Arc<Self>, app_handle: &AppHandle) -> Result<(), LauncherError> {
+        if self.client_held.load(Ordering::SeqCst) {
+            let resumed = match self.processes.lock().unwrap().as_mut() {
+                Some(launched) => launched.client.resume(),
+                None => Err("The game is not running".to_string()),
+            };
+            if let Err(e) = resumed {
+                self.abort(app_handle);
+                return Err(LauncherError::Launch(e));
+            }
+            self.client_held.store(false, Ordering::SeqCst);
+        }
+
+        self.set_state(app_handle, GameState::Running);
+        self.snapshot_modules();
+        record_play_started(app_handle, &self.build);
+        tauri::async_runtime::spawn(monitor(app_handle.clone(), self.clone()));
+        Ok(())
+    }
+
+    /// Injects `dll` into a client held for injection. A failure stops the
+    /// session, since the game must not run without its payloads.
+    pub fn inject(&self, app_handle: &AppHandle, dll: &Path) -> Result<(), LauncherError> {
+        let injected = match self.processes.lock().unwrap().as_mut() {
+            Some(launched) if self.client_held.load(Ordering::SeqCst) => launched.client.inject(dll),
+            _ => Err("The game is not waiting for injection".to_string()),
+        };
+        match injected {
+            Ok(()) => {
+                self.record_payload(&dll.to_string_lossy());
+                Ok(())
+            }
+            Err(e) => {
+                self.note(&format!("Injection failed: {}", e));
+                self.abort(app_handle);
+                Err(LauncherError::Launch(e))
+            }
+        }
+    }
+
+    /// Kills the client straight away, for launches that fail halfway. The
+    /// monitor reports the exit and takes the helpers down with it.
+    fn abort(&self, app_handle: &AppHandle) {
+        self.stop_requested.store(true, Ordering::SeqCst);
+        if let Some(launched) = self.processes.lock().unwrap().as_mut() {
+            if let Err(e) = launched.client.kill() {
+                println!("Failed to stop {}: {}", self.build, e);
+            }
+        }
+
+        // Nothing watches a client still held for injection, so finish here.
+        if self.client_held.swap(false, Ordering::SeqCst) {
+            self.clean_up();
+            self.set_state(app_handle, GameState::Exited { code: None });
+            self.finish_log();
+        }
+    }
+
+    /// Stops the session without cutting the game's own shutdown short: the
+    /// client is asked to close and given `timeout` to do so, then whatever
+    /// it started is killed children first, then the client, then the helper
+    /// stubs. Processes we hold a handle to are killed through it, so a
+    /// reused PID can't be hit; the client's children are matched by start
+    /// time and name first.
+    pub async fn shutdown(&self, app_handle: &AppHandle, timeout: Duration) -> SessionStopResult {
+        self.stop_requested.store(true, Ordering::SeqCst);
+        let held = self.client_held.load(Ordering::SeqCst);
+        let system = process_tree::snapshot();
+        let name_of = |pid: u32| {
+            ProcessIdentity::of(&system, pid)
+                .map(|identity| identity.name)
+                .unwrap_or_default()
+        };
+
+        // Taken so the monitor doesn't kill them while we wait for the client.
+        let (client_pid, mut helpers) = match self.processes.lock().unwrap().as_mut() {
+            Some(launched) => (Some(launched.client.pid()), mem::take(&mut launched.helpers)),
+            None => (None, Vec::new()),
+        };
+
+        let mut processes = Vec::new();
+        if let Some(pid) = client_pid {
+            let mut client = ProcessStopResult::new(pid, name_of(pid), Some(ProcessRole::Client));
+            let closed = if held {
+                client.step(StopStep::CloseRequest, false, "Still held for injection, so it can't close itself");
+                false
+            } else {
+                self.close_client(&mut client, timeout).await
+            };
+
+            // Looked up again now: the client may have started or lost
+            // children while it was asked to close.
+            let current = process_tree::snapshot();
+            for child in process_tree::descendants(&current, pid) {
+                processes.push(terminate_child(&child));
+            }
+
+            if closed {
+                client.outcome = StopOutcome::Closed;
+            } else {
+                // The monitor drops the processes once the client has exited.
+                let killed = match self.processes.lock().unwrap().as_mut() {
+                    Some(launched) => launched.client.kill().map(|()| true),
+                    None => Ok(false),
+                };
+                match killed {
+                    Ok(true) => {
+                        client.outcome = StopOutcome::Terminated;
+                        client.step(StopStep::Terminate, true, "Killed");
+                    }
+                    Ok(false) => {
+                        client.outcome = StopOutcome::AlreadyExited;
+                        client.step(StopStep::Terminate, true, "Exited before it had to be killed");
+                    }
+                    Err(e) => client.step(StopStep::Terminate, false, e),
+                }
+            }
+            processes.push(client);
+        }
+
+        for helper in &mut helpers {
+            let mut result = ProcessStopResult::new(helper.pid(), name_of(helper.pid()), Some(helper.role()));
+            match helper.try_wait() {
+                Ok(Some(_)) => {
+                    result.outcome = StopOutcome::AlreadyExited;
+                    result.step(StopStep::Terminate, true, "Already exited");
+                }
+                _ => match helper.kill() {
+                    Ok(()) => {
+                        result.outcome = StopOutcome::Terminated;
+                        result.step(StopStep::Terminate, true, "Killed");
+                    }
+                    Err(e) => result.step(StopStep::Terminate, false, e.to_string()),
+                },
+            }
+            processes.push(result);
+        }
+
+        for result in &processes {
+            println!("Stopping {}: {}", self.build, result.summary());
+            self.note(&format!("Stopped {}", result.summary()));
+        }
+
+        // Nothing watches a client still held for injection, so finish here.
+        if self.client_held.swap(false, Ordering::SeqCst) {
+            self.clean_up();
+            self.set_state(app_handle, GameState::Exited { code: None });
+            self.finish_log();
+        }
+
+        SessionStopResult {
+            session_id: self.id.clone(),
+            build: self.build.clone(),
+            processes,
+        }
+    }
+
+    /// Asks the client to close and waits up to `timeout` for it to exit.
+    async fn close_client(&self, result: &mut ProcessStopResult, timeout: Duration) -> bool {
+        let requested = match self.processes.lock().unwrap().as_mut() {
+            Some(launched) => launched.client.request_close(),
+            None => Ok(()),
+        };
+        if let Err(e) = requested {
+            result.step(StopStep::CloseRequest, false, e);
+            return false;
+        }
+        result.step(StopStep::CloseRequest, true, "Asked the game to close");
+
+        let started = Instant::now();
+        loop {
+            if let Some(exit) = self.check_exit() {
+                let message = format!(
+                    "Exited with code {:?} after {:.1}s",
+                    exit.code,
+                    started.elapsed().as_secs_f32()
+                );
+                result.step(StopStep::Wait, true, message);
+                return true;
+            }
+            if started.elapsed() >= timeout {
+                result.step(StopStep::Wait, false, format!("Still running after {}s", timeout.as_secs()));
+                return false;
+            }
+            tokio::time::sleep(STOP_POLL_INTERVAL).await;
+        }
+    }
+
+    fn check_exit(&self) -> Option<ProcessExit> {
+        let mut processes = self.processes.lock().unwrap();
+        let Some(launched) = processes.as_mut() else {
+            return Some(ProcessExit { code: None });
+        };
+        match launched.client.try_wait() {
+            Ok(exit) => exit,
+            Err(e) => {
+                println!("Lost track of {}: {}", self.build, e);
+                Some(ProcessExit { code: None })
+            }
+        }
+    }
+
+    /// Kills whatever the client left behind and releases every handle.
+    fn clean_up(&self) {
+        if let Some(mut launched) = self.processes.lock().unwrap().take() {
+            for helper in &mut launched.helpers {
+                if let Err(e) = helper.kill() {
+                    println!("Failed to kill {:?} process {}: {}", helper.role(), helper.pid(), e);
+                }
+            }
+        }
+    }
+
+    /// A user-requested stop or a clean exit is an exit. Anything ended by a
+    /// signal or an NTSTATUS exception code counts as a crash, as does an exit
+    /// that left a new folder under `Saved/Crashes` behind.
+    fn final_state(&self, exit: ProcessExit) -> GameState {
+        let crashed = !self.stop_requested.load(Ordering::SeqCst)
+            && (exit.code.is_none_or(|code| code < 0)
+                || !new_crash_folders(Path::new(&self.build), self.started_at).is_empty());
+        if crashed {
+            GameState::Crashed { code: exit.code }
+        } else {
+            GameState::Exited { code: exit.code }
+        }
+    }
+
+    /// Bundles the crash folders, the end of the session log and what was
+    /// launched into `crashes/<id>.zip`, then emits `crash-report-ready`.
+    async fn report_crash(&self, app_handle: &AppHandle, exit_code: Option<i32>) -> Result<(), LauncherError> {
+        let log = self.log.lock().unwrap().clone();
+        let log_lines = match log {
+            Some(log) => {
+                log.finish_and_wait().await;
+                let filter = LogFilter {
+                    limit: Some(LOG_TAIL_LINES),
+                    ..Default::default()
+                };
+                read_log(log.dir(), &filter).unwrap_or_default()
+            }
+            None => Vec::new(),
+        };
+
+        let context = CrashContext {
+            session_id: self.id.clone(),
+            build: self.build.clone(),
+            account_id: self.account_id.clone(),
+            version: app_handle.state::<VersionState>().0.lock().unwrap().get(&self.build).cloned(),
+            args: self.args.clone(),
+            injected_payloads: self.injected_payloads.lock().unwrap().clone(),
+            loaded_modules: self.loaded_modules.lock().unwrap().clone(),
+            exit_code,
+            launcher_version: env!("CARGO_PKG_VERSION").to_string(),
+        };
+        let crash_folders = new_crash_folders(Path::new(&self.build), self.started_at);
+        let out = crash_report_path(app_handle, &self.id)?;
+
+        let path = out.clone();
+        tokio::task::spawn_blocking(move || write_crash_bundle(&path, &context, &crash_folders, &log_lines)).await??;
+
+        println!("Wrote crash report for {} to {}", self.build, out.display());
+        let _ = app_handle.emit("crash-report-ready", CrashReportEvent {
+            session_id: self.id.clone(),
+            build: self.build.clone(),
+            path: out.to_string_lossy().to_string(),
+        });
+        Ok(())
+    }
+}
+
+async fn monitor(app_handle: AppHandle, session: Arc<GameSession>) {
+    let started = Instant::now();
+    let mut credited_secs = 0;
+    let mut last_flush = Instant::now();
+    let mut last_snapshot = Instant::now();
+    let flush = |credited_secs: &mut u64| {
+        let elapsed = started.elapsed().as_secs();
+        add_play_time(&app_handle, &session.build, elapsed - *credited_secs);
+        *credited_secs = elapsed;
+    };
+
+    let exit = loop {
+        tokio::time::sleep(MONITOR_INTERVAL).await;
+        if let Some(exit) = session.check_exit() {
+            break exit;
+        }
+        if last_flush.elapsed() >= PLAY_FLUSH_INTERVAL {
+            flush(&mut credited_secs);
+            last_flush = Instant::now();
+        }
+        if last_snapshot.elapsed() >= MODULE_SNAPSHOT_INTERVAL {
+            session.snapshot_modules();
+            last_snapshot = Instant::now();
+        }
+    };
+
+    flush(&mut credited_secs);
+    session.clean_up();
+    let state = session.final_state(exit);
+    session.set_state(&app_handle, state);
+    if let GameState::Crashed { code } = state {
+        if let Err(e) = session.report_crash(&app_handle, code).await {
+            println!("Failed to write crash report for {}: {}", session.build, e);
+        }
+    }
+    session.finish_log();
+    app_handle.state::<GameSessions>().prune();
+}
+
+#[derive(Default)]
+pub struct GameSessions(Mutex<HashMap<String, Arc<GameSession>>>);
+
+impl GameSessions {
+    fn active(&self) -> Vec<Arc<GameSession>> {
+        self.0
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|session| session.state().is_active())
+            .cloned()
+            .collect()
+    }
+
+    pub fn is_running(&self, build: &str) -> bool {
+        self.0
+            .lock()
+            .unwrap()
+            .get(build)
+            .is_some_and(|session| session.state().is_active())
+    }
+
+    /// Forgets sessions that have ended. Their final state has already gone
+    /// out as `game-state-changed`.
+    fn prune(&self) {
+        self.0.lock().unwrap().retain(|_, session| session.state().is_active());
+    }
+
+    /// Starts a session for `build`, refusing if one is already going.
+    fn begin(
+        &self,
+        app_handle: &AppHandle,
+        build: &str,
+        account_id: Option<&str>,
+        args: &[String],
+    ) -> Result<Arc<GameSession>, LauncherError> {
+        let session = {
+            let mut sessions = self.0.lock().unwrap();
+            sessions.retain(|_, session| session.state().is_active());
+            if sessions.contains_key(build) {
+                return Err(LauncherError::AlreadyRunning(format!("{} is already running", build)));
+            }
+            let session = Arc::new(GameSession::new(
+                build.to_string(),
+                account_id.map(str::to_string),
+                args.to_vec(),
+            ));
+            sessions.insert(build.to_string(), session.clone());
+            session
+        };
+        let _ = app_handle.emit("game-state-changed", session.info());
+        Ok(session)
+    }
+}
+
+/// Launches `build` with the current platform launcher. With
+/// `hold_for_injection` the client is left suspended in the Injecting state
+/// and the caller finishes with `GameSession::run`; otherwise it is running
+/// when this returns.
+pub fn start_session(
+    app_handle: &AppHandle,
+    build: &str,
+    args: &[String],
+    env: &[(String, String)],
+    account_id: Option<&str>,
+    hold_for_injection: bool,
+) -> Result<Arc<GameSession>, LauncherError> {
+    let session = app_handle
+        .state::<GameSessions>()
+        .begin(app_handle, build, account_id, &redact_args(args))?;
+    let launcher = app_handle.state::<LauncherState>().current();
+    session.open_log(app_handle);
+    session.note(&format!("Launching {} with {} and args {:?}", build, launcher.name(), session.args));
+    if let Some(account_id) = account_id {
+        session.note(&format!("Playing as account {}", account_id));
+    }
+
+    let mut launched = match launch_build(launcher.as_ref(), Path::new(build), args, env, hold_for_injection) {
+        Ok(launched) => launched,
+        Err(e) => {
+            session.note(&format!("Launch failed: {}", e));
+            session.set_state(app_handle, GameState::Exited { code: None });
+            session.finish_log();
+            return Err(LauncherError::Launch(e));
+        }
+    };
+    session.capture_output(&mut launched);
+    *session.processes.lock().unwrap() = Some(launched);
+
+    if hold_for_injection {
+        session.client_held.store(true, Ordering::SeqCst);
+        session.set_state(app_handle, GameState::Injecting);
+    } else {
+        session.run(app_handle)?;
+    }
+    Ok(session)
+}
+
+#[tauri::command]
+pub async fn get_game_sessions(sessions: State<'_, GameSessions>) -> Result<Vec<SessionInfo>, LauncherError> {
+    Ok(sessions.0.lock().unwrap().values().map(|session| session.info()).collect())
+}
+
+#[tauri::command]
+pub async fn is_game_running(sessions: State<'_, GameSessions>) -> Result<bool, LauncherError> {
+    Ok(!sessions.active().is_empty())
+}
+
+/// Stops `build`, or every running build when none is given, giving each
+/// client `timeout_secs` to close on its own before it is killed.
+#[tauri::command]
+pub async fn stop_game_process(
+    build: Option<String>,
+    timeout_secs: Option<u64>,
+    sessions: State<'_, GameSessions>,
+    app_handle: AppHandle,
+) -> Result<Vec<SessionStopResult>, LauncherError> {
+    let targets: Vec<Arc<GameSession>> = sessions
+        .active()
+        .into_iter()
+        .filter(|session| build.as_deref().is_none_or(|build| session.build == build))
+        .collect();
+
+    if targets.is_empty() {
+        return Err(LauncherError::NotFound("No game processes running".to_string()));
+    }
+    let timeout = timeout_secs.map(Duration::from_secs).unwrap_or(DEFAULT_STOP_TIMEOUT);
+    Ok(join_all(targets.iter().map(|session| session.shutdown(&app_handle, timeout))).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_launcher::FakeLauncher;
+    use crate::test_fs::{fake_build, TempDir};
+
+    /// A running session for `root` whose processes came from `launcher`.
+    fn running_session(launcher: &FakeLauncher, root: &Path) -> GameSession {
+        let session = GameSession::new(root.to_string_lossy().to_string(), None, Vec::new());
+        let launched = launch_build(launcher, root, &[], &[], false).unwrap();
+        *session.processes.lock().unwrap() = Some(launched);
+        *session.state.lock().unwrap() = GameState::Running;
+        session
+    }
+
+    #[tokio::test]
+    async fn a_client_that_listens_closes_on_request() {
+        let dir = TempDir::new("session", "close");
+        let root = fake_build(&dir, "build", true);
+        let launcher = FakeLauncher::default();
+        launcher.exit_on_close(0);
+        let session = running_session(&launcher, &root);
+        let pid = session.client_pid().unwrap();
+
+        let mut result = ProcessStopResult::new(pid, String::new(), Some(ProcessRole::Client));
+        assert!(session.close_client(&mut result, Duration::from_secs(5)).await);
+        let steps: Vec<(StopStep, bool)> = result.steps.iter().map(|s| (s.step, s.ok)).collect();
+        assert_eq!(steps, [(StopStep::CloseRequest, true), (StopStep::Wait, true)]);
+        assert_eq!(session.check_exit(), Some(ProcessExit { code: Some(0) }));
+    }
+
+    #[tokio::test]
+    async fn a_client_that_ignores_the_request_is_waited_out() {
+        let dir = TempDir::new("session", "ignore");
+        let root = fake_build(&dir, "build", true);
+        let launcher = FakeLauncher::default();
+        let session = running_session(&launcher, &root);
+        let pid = session.client_pid().unwrap();
+
+        let mut result = ProcessStopResult::new(pid, String::new(), Some(ProcessRole::Client));
+        assert!(!session.close_client(&mut result, STOP_POLL_INTERVAL).await);
+        assert_eq!(result.steps.last().map(|s| (s.step, s.ok)), Some((StopStep::Wait, false)));
+        assert!(launcher.records().iter().any(|record| record.close_requested));
+        assert_eq!(session.check_exit(), None);
+    }
+
+    #[test]
+    fn clean_up_kills_the_stubs_and_lets_go() {
+        let dir = TempDir::new("session", "clean-up");
+        let root = fake_build(&dir, "build", true);
+        let launcher = FakeLauncher::default();
+        let session = running_session(&launcher, &root);
+
+        launcher.exit(session.client_pid().unwrap(), Some(0));
+        session.clean_up();
+        let records = launcher.records();
+        assert_eq!(records[0].spec.role, ProcessRole::Launcher);
+        assert_eq!(records[1].spec.role, ProcessRole::AntiCheat);
+        assert!(records[0].killed && records[1].killed);
+        assert!(!records[2].killed);
+        assert_eq!(session.client_pid(), None);
+    }
+
+    #[test]
+    fn module_snapshots_outlive_the_client() {
+        let dir = TempDir::new("session", "modules");
+        let root = fake_build(&dir, "build", true);
+        let launcher = FakeLauncher::default();
+        let session = running_session(&launcher, &root);
+        let client = launcher.records()[2].spec.executable.to_string_lossy().to_string();
+
+        session.snapshot_modules();
+        assert_eq!(*session.loaded_modules.lock().unwrap(), [client.clone()]);
+
+        launcher.exit(session.client_pid().unwrap(), Some(-1));
+        session.snapshot_modules();
+        assert_eq!(*session.loaded_modules.lock().unwrap(), [client]);
+    }
+
+    #[test]
+    fn pruning_keeps_only_live_sessions() {
+        let sessions = GameSessions::default();
+        for (build, state) in [
+            ("running", GameState::Running),
+            ("held", GameState::Injecting),
+            ("exited", GameState::Exited { code: Some(0) }),
+            ("crashed", GameState::Crashed { code: None }),
+        ] {
+            let session = GameSession::new(build.to_string(), None, Vec::new());
+            *session.state.lock().unwrap() = state;
+            sessions.0.lock().unwrap().insert(build.to_string(), Arc::new(session));
+        }
+
+        sessions.prune();
+        let mut left: Vec<String> = sessions.0.lock().unwrap().keys().cloned().collect();
+        left.sort();
+        assert_eq!(left, ["held", "running"]);
+        assert!(sessions.is_running("running"));
+        assert!(!sessions.is_running("exited"));
+    }
+
+    #[test]
+    fn session_info_carries_the_state_inline() {
+        let session = GameSession::new("C:/Builds/8.51".to_string(), None, Vec::new());
+        *session.state.lock().unwrap() = GameState::Crashed { code: Some(-1) };
+
+        let info = serde_json::to_value(session.info()).unwrap();
+        assert_eq!(info["build"], "C:/Builds/8.51");
+        assert_eq!(info["state"], "crashed");
+        assert_eq!(info["code"], -1);
+    }
+
+    #[test]
+    fn only_unrequested_abnormal_exits_are_crashes() {
+        let dir = TempDir::new("session", "final-state");
+        let root = fake_build(&dir, "build", true);
+        let launcher = FakeLauncher::default();
+        let session = running_session(&launcher, &root);
+
+        let exited = |code| GameState::Exited { code };
+        assert_eq!(session.final_state(ProcessExit { code: Some(0) }), exited(Some(0)));
+        assert_eq!(session.final_state(ProcessExit { code: Some(1) }), exited(Some(1)));
+        assert_eq!(
+            session.final_state(ProcessExit { code: Some(-1073741819) }),
+            GameState::Crashed { code: Some(-1073741819) }
+        );
+        assert_eq!(session.final_state(ProcessExit { code: None }), GameState::Crashed { code: None });
+
+        session.stop_requested.store(true, Ordering::SeqCst);
+        assert_eq!(session.final_state(ProcessExit { code: None }), exited(None));
+    }
+}
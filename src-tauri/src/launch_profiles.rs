@@ -0,0 +1,367 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager, State};
+
+use crate::error::LauncherError;
+use crate::game_launcher::{command_line, BuildLayout, BINARIES_DIR};
+use crate::persist::write_atomic;
+
+const PROFILES_FILE: &str = "launch_profiles.json";
+
+/// A named set of extra arguments, environment variables and DLLs to inject.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LaunchProfile {
+    pub id: String,
+    pub name: String,
+    pub args: Vec<String>,
+    pub env: BTreeMap<String, String>,
+    /// Payload names from the manifest, or paths to local DLLs whose SHA-256
+    /// the manifest pins: absolute or relative to the build's Win64 folder.
+    pub dlls: Vec<String>,
+}
+
+/// Per-build changes applied on top of whichever profile is used.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BuildOverride {
+    /// Used when the launch doesn't name a profile.
+    pub profile: Option<String>,
+    pub args: Vec<String>,
+    pub env: BTreeMap<String, String>,
+    /// Replaces the profile's DLLs when set.
+    pub dlls: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProfileStore {
+    pub default_profile: Option<String>,
+    pub profiles: Vec<LaunchProfile>,
+    /// Keyed by build path.
+    pub overrides: HashMap<String, BuildOverride>,
+}
+
+impl ProfileStore {
+    fn profile(&self, id: &str) -> Option<&LaunchProfile> {
+        self.profiles.iter().find(|profile| profile.id == id)
+    }
+}
+
+/// What a launch of one build with one profile comes down to.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ResolvedLaunch {
+    pub profile: Option<String>,
+    pub args: Vec<String>,
+    pub env: Vec<(String, String)>,
+    pub dlls: Vec<String>,
+}
+
+/// `-KEY=value` and `-flag` arguments are keyed by their lowercased name so a
+/// later one can replace an earlier one. Anything else has no key.
+fn arg_key(arg: &str) -> Option<String> {
+    if !arg.starts_with('-') {
+        return None;
+    }
+    let name = arg.split('=').next().unwrap_or(arg);
+    Some(name.to_ascii_lowercase())
+}
+
+/// Appends each layer in turn. An argument whose key was already seen takes
+/// the earlier one's place, so the result depends only on the inputs.
+/// Values are kept whole, spaces and quotes included; they are quoted for
+/// the platform when the process is started.
+pub fn merge_args(layers: &[&[String]]) -> Vec<String> {
+    let mut merged: Vec<String> = Vec::new();
+    for arg in layers.iter().flat_map(|layer| layer.iter()) {
+        if arg.trim().is_empty() {
+            continue;
+        }
+        let existing = arg_key(arg).and_then(|key| {
+            merged
+                .iter()
+                .position(|earlier| arg_key(earlier).as_deref() == Some(key.as_str()))
+        });
+        match existing {
+            Some(index) => merged[index] = arg.clone(),
+            None => merged.push(arg.clone()),
+        }
+    }
+    merged
+}
+
+/// The arguments every build is started with, before logging in.
+pub fn base_args() -> Vec<String> {
+    vec![
+        "-epicapp=Fortnite".to_string(),
+        "-epicenv=Prod".to_string(),
+        "-epiclocale=en-us".to_string(),
+        "-epicportal".to_string(),
+        "-skippatchcheck".to_string(),
+        "-nobe".to_string(),
+        "-fromfl=eac".to_string(),
+        "-fltoken=3db3ba5dcbd2e16703f3978d".to_string(),
+    ]
+}
+
+/// Logs in with a one-time exchange code, which the game reads from
+/// `-AUTH_PASSWORD`. Offline launches pass the stored launcher token here
+/// instead, which the backend accepts in place of a code while it is still
+/// valid. Profiles can replace any of these with their own `-AUTH_*`
+/// arguments.
+pub fn auth_args(exchange_code: &str) -> Vec<String> {
+    vec![
+        "-AUTH_LOGIN=unused".to_string(),
+        format!("-AUTH_PASSWORD={}", exchange_code),
+        "-AUTH_TYPE=exchangecode".to_string(),
+    ]
+}
+
+/// `args` with the exchange code blanked out, for logs and crash reports.
+pub fn redact_args(args: &[String]) -> Vec<String> {
+    args.iter()
+        .map(|arg| match arg_key(arg).as_deref() {
+            Some("-auth_password") => "-AUTH_PASSWORD=<redacted>".to_string(),
+            _ => arg.clone(),
+        })
+        .collect()
+}
+
+/// Picks the profile (`profile_id`, else the build's own, else the default)
+/// and applies the build's override on top of it. Naming a profile that
+/// doesn't exist is an error; a stale build or default reference is not.
+pub fn resolve_launch(
+    store: &ProfileStore,
+    profile_id: Option<&str>,
+    build: &str,
+) -> Result<ResolvedLaunch, LauncherError> {
+    let build_override = store.overrides.get(build);
+    let profile = match profile_id {
+        Some(id) => Some(
+            store
+                .profile(id)
+                .ok_or_else(|| LauncherError::NotFound(format!("No launch profile with id {}", id)))?,
+        ),
+        None => build_override
+            .and_then(|o| o.profile.as_deref())
+            .or(store.default_profile.as_deref())
+            .and_then(|id| store.profile(id)),
+    };
+
+    let empty = BuildOverride::default();
+    let build_override = build_override.unwrap_or(&empty);
+    let profile_args = profile.map(|p| p.args.as_slice()).unwrap_or_default();
+
+    let mut env = profile.map(|p| p.env.clone()).unwrap_or_default();
+    env.extend(build_override.env.clone());
+
+    let dlls = match &build_override.dlls {
+        Some(dlls) => dlls.clone(),
+        None => profile.map(|p| p.dlls.clone()).unwrap_or_default(),
+    };
+
+    Ok(ResolvedLaunch {
+        profile: profile.map(|p| p.id.clone()),
+        args: merge_args(&[profile_args, &build_override.args]),
+        env: env.into_iter().collect(),
+        dlls,
+    })
+}
+
+/// Relative DLL paths point into the build's Win64 folder, where payloads
+/// have always been dropped.
+pub fn dll_path(build: &Path, dll: &str) -> PathBuf {
+    let path = Path::new(dll);
+    if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        build.join(BINARIES_DIR).join(path)
+    }
+}
+
+pub struct ProfileState(pub Mutex<ProfileStore>);
+
+impl ProfileState {
+    pub fn load(app_handle: &AppHandle) -> Self {
+        let store = fs::read_to_string(profiles_path(app_handle))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        ProfileState(Mutex::new(store))
+    }
+}
+
+fn profiles_path(app_handle: &AppHandle) -> PathBuf {
+    app_handle.path().app_data_dir().unwrap().join(PROFILES_FILE)
+}
+
+fn save_profiles(store: &ProfileStore, app_handle: &AppHandle) -> Result<(), LauncherError> {
+    let content = serde_json::to_string_pretty(store)?;
+    write_atomic(&profiles_path(app_handle), content).map_err(|e| LauncherError::io("Failed to save launch profiles", e))
+}
+
+#[tauri::command]
+pub async fn get_launch_profiles(state: State<'_, ProfileState>) -> Result<ProfileStore, LauncherError> {
+    Ok(state.0.lock().unwrap().clone())
+}
+
+/// Adds `profile`, or replaces the one with the same id. A profile without
+/// an id gets a new one.
+#[tauri::command]
+pub async fn save_launch_profile(
+    mut profile: LaunchProfile,
+    state: State<'_, ProfileState>,
+    app_handle: AppHandle,
+) -> Result<LaunchProfile, LauncherError> {
+    if profile.name.trim().is_empty() {
+        return Err(LauncherError::InvalidInput("Launch profiles need a name".to_string()));
+    }
+    if profile.id.is_empty() {
+        profile.id = format!(
+            "{:x}",
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_nanos())
+                .unwrap_or(0)
+        );
+    }
+
+    let mut store = state.0.lock().unwrap();
+    match store.profiles.iter_mut().find(|p| p.id == profile.id) {
+        Some(existing) => *existing = profile.clone(),
+        None => store.profiles.push(profile.clone()),
+    }
+    save_profiles(&store, &app_handle)?;
+    Ok(profile)
+}
+
+#[tauri::command]
+pub async fn delete_launch_profile(
+    id: String,
+    state: State<'_, ProfileState>,
+    app_handle: AppHandle,
+) -> Result<(), LauncherError> {
+    let mut store = state.0.lock().unwrap();
+    store.profiles.retain(|profile| profile.id != id);
+    if store.default_profile.as_deref() == Some(id.as_str()) {
+        store.default_profile = None;
+    }
+    for build_override in store.overrides.values_mut() {
+        if build_override.profile.as_deref() == Some(id.as_str()) {
+            build_override.profile = None;
+        }
+    }
+    save_profiles(&store, &app_handle)
+}
+
+#[tauri::command]
+pub async fn set_default_launch_profile(
+    id: Option<String>,
+    state: State<'_, ProfileState>,
+    app_handle: AppHandle,
+) -> Result<(), LauncherError> {
+    let mut store = state.0.lock().unwrap();
+    if let Some(id) = &id {
+        store
+            .profile(id)
+            .ok_or_else(|| LauncherError::NotFound(format!("No launch profile with id {}", id)))?;
+    }
+    store.default_profile = id;
+    save_profiles(&store, &app_handle)
+}
+
+/// Sets or, with `None`, clears the override for the build at `path`.
+#[tauri::command]
+pub async fn set_build_launch_override(
+    path: String,
+    build_override: Option<BuildOverride>,
+    state: State<'_, ProfileState>,
+    app_handle: AppHandle,
+) -> Result<(), LauncherError> {
+    let mut store = state.0.lock().unwrap();
+    match build_override {
+        Some(build_override) => store.overrides.insert(path, build_override),
+        None => store.overrides.remove(&path),
+    };
+    save_profiles(&store, &app_handle)
+}
+
+#[derive(Debug, Serialize)]
+pub struct LaunchPreview {
+    #[serde(flatten)]
+    pub launch: ResolvedLaunch,
+    /// The client's command line without the login arguments.
+    pub command_line: String,
+}
+
+/// Shows what launching `path` with `profile` would run.
+#[tauri::command]
+pub async fn preview_launch(
+    path: String,
+    profile: Option<String>,
+    state: State<'_, ProfileState>,
+) -> Result<LaunchPreview, LauncherError> {
+    let launch = resolve_launch(&state.0.lock().unwrap(), profile.as_deref(), &path)?;
+    let layout = BuildLayout::from_root(Path::new(&path)).map_err(LauncherError::CorruptBuild)?;
+    let args = merge_args(&[&base_args(), &launch.args]);
+    Ok(LaunchPreview {
+        command_line: command_line(&layout.client.to_string_lossy(), &args),
+        launch,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn keys_are_the_lowercased_flag_name() {
+        assert_eq!(arg_key("-AUTH_TYPE=exchangecode").as_deref(), Some("-auth_type"));
+        assert_eq!(arg_key("-nobe").as_deref(), Some("-nobe"));
+        assert_eq!(arg_key("-map=a=b").as_deref(), Some("-map"));
+        assert_eq!(arg_key("Athena_Terrain"), None);
+    }
+
+    #[test]
+    fn later_layers_replace_earlier_arguments_in_place() {
+        let base = args(&["-epicapp=Fortnite", "-nobe", "-AUTH_TYPE=epic"]);
+        let profile = args(&["-auth_type=exchangecode", "-log"]);
+        let build = args(&["-EPICAPP=Rewind"]);
+
+        assert_eq!(
+            merge_args(&[&base, &profile, &build]),
+            args(&["-EPICAPP=Rewind", "-nobe", "-auth_type=exchangecode", "-log"])
+        );
+    }
+
+    #[test]
+    fn blank_arguments_are_dropped_and_keyless_ones_kept() {
+        let layer = args(&["", "  ", "Athena_Terrain", "Athena_Terrain", "-log"]);
+        assert_eq!(merge_args(&[&layer]), args(&["Athena_Terrain", "Athena_Terrain", "-log"]));
+    }
+
+    #[test]
+    fn values_are_kept_whole() {
+        let layer = args(&["-name=\"Rewind Player\"", "-path=C:\\Program Files\\"]);
+        assert_eq!(merge_args(&[&layer]), layer);
+    }
+
+    #[test]
+    fn redacts_the_exchange_code_whatever_its_case() {
+        let launch = merge_args(&[&base_args(), &auth_args("secret-code"), &args(&["-auth_password=other"])]);
+        let redacted = redact_args(&launch);
+
+        assert!(redacted.contains(&"-AUTH_PASSWORD=<redacted>".to_string()));
+        assert!(redacted.iter().all(|arg| !arg.contains("secret-code") && !arg.contains("other")));
+        assert_eq!(redacted.len(), launch.len());
+        assert!(redacted.contains(&"-AUTH_TYPE=exchangecode".to_string()));
+    }
+}
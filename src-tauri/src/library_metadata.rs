@@ -0,0 +1,433 @@
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering as CmpOrdering;
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+
+use crate::error::LauncherError;
+use crate::{save_versions, thumbnail_url, StoredVersion, VersionState};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VersionMetadata {
+    pub display_name: Option<String>,
+    pub tags: Vec<String>,
+    pub notes: String,
+    pub favorite: bool,
+    pub added_at: Option<u64>,
+    pub last_played_at: Option<u64>,
+    pub play_time_secs: u64,
+}
+
+impl VersionMetadata {
+    pub fn added_now() -> Self {
+        Self {
+            added_at: Some(unix_now()),
+            ..Default::default()
+        }
+    }
+}
+
+/// Fields left out are kept as they are. An empty `display_name` clears it.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct MetadataUpdate {
+    pub display_name: Option<String>,
+    pub tags: Option<Vec<String>>,
+    pub notes: Option<String>,
+    pub favorite: Option<bool>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortField {
+    #[default]
+    Name,
+    Version,
+    AddedAt,
+    LastPlayed,
+    PlayTime,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct LibraryQuery {
+    pub search: Option<String>,
+    /// Builds must carry every one of these tags.
+    pub tags: Vec<String>,
+    pub favorites_only: bool,
+    pub sort: SortField,
+    pub descending: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LibraryEntry {
+    pub path: String,
+    pub version: String,
+    pub technical_version: String,
+    pub splash_image: String,
+    pub display_name: String,
+    pub metadata: VersionMetadata,
+}
+
+impl From<StoredVersion> for LibraryEntry {
+    fn from(version: StoredVersion) -> Self {
+        let display_name = version
+            .metadata
+            .display_name
+            .clone()
+            .unwrap_or_else(|| version.version.clone());
+        LibraryEntry {
+            splash_image: version.splash_key.as_deref().map(thumbnail_url).unwrap_or_default(),
+            path: version.path,
+            version: version.version,
+            technical_version: version.technical_version,
+            display_name,
+            metadata: version.metadata,
+        }
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Trims tags, drops empty ones and removes case-insensitive duplicates.
+fn normalize_tags(tags: Vec<String>) -> Vec<String> {
+    let mut normalized: Vec<String> = Vec::new();
+    for tag in tags {
+        let tag = tag.trim();
+        if !tag.is_empty() && !normalized.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+            normalized.push(tag.to_string());
+        }
+    }
+    normalized
+}
+
+pub fn apply_update(metadata: &mut VersionMetadata, update: MetadataUpdate) {
+    if let Some(name) = update.display_name {
+        let name = name.trim();
+        metadata.display_name = (!name.is_empty()).then(|| name.to_string());
+    }
+    if let Some(tags) = update.tags {
+        metadata.tags = normalize_tags(tags);
+    }
+    if let Some(notes) = update.notes {
+        metadata.notes = notes;
+    }
+    if let Some(favorite) = update.favorite {
+        metadata.favorite = favorite;
+    }
+}
+
+/// "8.51 (CL-6165369)" sorts as [8, 51] so 10.40 comes after 9.10.
+fn version_key(version: &str) -> Vec<u64> {
+    version
+        .split(" (CL-")
+        .next()
+        .unwrap_or(version)
+        .split(|c: char| !c.is_ascii_digit())
+        .filter_map(|part| part.parse().ok())
+        .collect()
+}
+
+fn matches(entry: &LibraryEntry, query: &LibraryQuery) -> bool {
+    if query.favorites_only && !entry.metadata.favorite {
+        return false;
+    }
+    let has_tags = query
+        .tags
+        .iter()
+        .all(|wanted| entry.metadata.tags.iter().any(|tag| tag.eq_ignore_ascii_case(wanted)));
+    if !has_tags {
+        return false;
+    }
+
+    match query.search.as_deref().map(str::trim) {
+        Some(search) if !search.is_empty() => {
+            let needle = search.to_lowercase();
+            let contains = |field: &str| field.to_lowercase().contains(&needle);
+            contains(&entry.display_name)
+                || contains(&entry.version)
+                || contains(&entry.technical_version)
+                || contains(&entry.metadata.notes)
+                || contains(&entry.path)
+                || entry.metadata.tags.iter().any(|tag| contains(tag))
+        }
+        _ => true,
+    }
+}
+
+/// Orders `Some` values by `descending` and always puts `None` last, so
+/// never-played builds don't crowd the top of a "recently played" list.
+fn compare_optional(a: Option<u64>, b: Option<u64>, descending: bool) -> CmpOrdering {
+    match (a, b) {
+        (Some(a), Some(b)) if descending => b.cmp(&a),
+        (Some(a), Some(b)) => a.cmp(&b),
+        (Some(_), None) => CmpOrdering::Less,
+        (None, Some(_)) => CmpOrdering::Greater,
+        (None, None) => CmpOrdering::Equal,
+    }
+}
+
+/// Filters and sorts the library for `query`. Ties fall back to the display
+/// name so the order is stable between calls.
+pub fn apply_query(versions: Vec<StoredVersion>, query: &LibraryQuery) -> Vec<LibraryEntry> {
+    let mut entries: Vec<LibraryEntry> = versions
+        .into_iter()
+        .map(LibraryEntry::from)
+        .filter(|entry| matches(entry, query))
+        .collect();
+
+    let by_name = |a: &LibraryEntry, b: &LibraryEntry| {
+        a.display_name
+            .to_lowercase()
+            .cmp(&b.display_name.to_lowercase())
+            .then_with(|| a.path.cmp(&b.path))
+    };
+    let directed = |ordering: CmpOrdering| if query.descending { ordering.reverse() } else { ordering };
+
+    entries.sort_by(|a, b| {
+        let primary = match query.sort {
+            SortField::Name => directed(by_name(a, b)),
+            SortField::Version => directed(version_key(&a.version).cmp(&version_key(&b.version))),
+            SortField::PlayTime => directed(a.metadata.play_time_secs.cmp(&b.metadata.play_time_secs)),
+            SortField::AddedAt => compare_optional(a.metadata.added_at, b.metadata.added_at, query.descending),
+            SortField::LastPlayed => {
+                compare_optional(a.metadata.last_played_at, b.metadata.last_played_at, query.descending)
+            }
+        };
+        primary.then_with(|| by_name(a, b))
+    });
+
+    entries
+}
+
+fn update_stored<F: FnOnce(&mut VersionMetadata)>(app_handle: &AppHandle, path: &str, update: F) {
+    let state = app_handle.state::<VersionState>();
+    let mut versions = state.0.lock().unwrap();
+    if let Some(version) = versions.get_mut(path) {
+        update(&mut version.metadata);
+        if let Err(e) = save_versions(&versions, app_handle) {
+            println!("Failed to save metadata for {}: {}", path, e);
+        }
+    }
+}
+
+/// Stamps `path` as played now. Called when a game session starts running.
+pub fn record_play_started(app_handle: &AppHandle, path: &str) {
+    update_stored(app_handle, path, |metadata| metadata.last_played_at = Some(unix_now()));
+}
+
+pub fn add_play_time(app_handle: &AppHandle, path: &str, secs: u64) {
+    if secs > 0 {
+        update_stored(app_handle, path, |metadata| metadata.play_time_secs += secs);
+    }
+}
+
+#[tauri::command]
+pub async fn query_versions(
+    query: LibraryQuery,
+    state: tauri::State<'_, VersionState>,
+) -> Result<Vec<LibraryEntry>, LauncherError> {
+    let versions: Vec<StoredVersion> = state.0.lock().unwrap().values().cloned().collect();
+    Ok(apply_query(versions, &query))
+}
+
+/// Applies `update` to the build at `path` and returns the updated build.
+fn update_metadata(
+    versions: &mut HashMap<String, StoredVersion>,
+    path: &str,
+    update: MetadataUpdate,
+) -> Result<StoredVersion, LauncherError> {
+    let version = versions
+        .get_mut(path)
+        .ok_or_else(|| LauncherError::NotFound(format!("{} is not in the library", path)))?;
+    apply_update(&mut version.metadata, update);
+    Ok(version.clone())
+}
+
+#[tauri::command]
+pub async fn update_version_metadata(
+    path: String,
+    update: MetadataUpdate,
+    state: tauri::State<'_, VersionState>,
+    app_handle: AppHandle,
+) -> Result<LibraryEntry, LauncherError> {
+    let mut versions = state.0.lock().unwrap();
+    let updated = update_metadata(&mut versions, &path, update)?;
+
+    save_versions(&versions, &app_handle)?;
+    Ok(LibraryEntry::from(updated))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn stored(path: &str, version: &str, metadata: VersionMetadata) -> StoredVersion {
+        StoredVersion {
+            path: path.to_string(),
+            version: version.to_string(),
+            technical_version: format!("++Fortnite+Release-{}", version),
+            splash_key: None,
+            metadata,
+        }
+    }
+
+    fn library() -> Vec<StoredVersion> {
+        vec![
+            stored(
+                "C:/Builds/9.10",
+                "9.10 (CL-6639283)",
+                VersionMetadata {
+                    tags: vec!["LAN".to_string()],
+                    added_at: Some(300),
+                    last_played_at: Some(50),
+                    play_time_secs: 120,
+                    ..Default::default()
+                },
+            ),
+            stored(
+                "C:/Builds/10.40",
+                "10.40 (CL-9380822)",
+                VersionMetadata {
+                    display_name: Some("Zero Point".to_string()),
+                    favorite: true,
+                    added_at: Some(100),
+                    play_time_secs: 3600,
+                    ..Default::default()
+                },
+            ),
+            stored(
+                "C:/Builds/8.51",
+                "8.51 (CL-6165369)",
+                VersionMetadata {
+                    tags: vec!["lan".to_string(), "Creative".to_string()],
+                    notes: "Good for testing".to_string(),
+                    last_played_at: Some(900),
+                    ..Default::default()
+                },
+            ),
+        ]
+    }
+
+    fn names(entries: &[LibraryEntry]) -> Vec<&str> {
+        entries.iter().map(|entry| entry.display_name.as_str()).collect()
+    }
+
+    fn query(json: serde_json::Value) -> LibraryQuery {
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn versions_sort_by_number_not_text() {
+        assert_eq!(version_key("8.51 (CL-6165369)"), [8, 51]);
+        assert_eq!(version_key("10.40"), [10, 40]);
+        assert!(version_key("10.40") > version_key("9.10"));
+        assert_eq!(version_key("unknown"), Vec::<u64>::new());
+    }
+
+    #[test]
+    fn missing_values_sort_last_either_way() {
+        assert_eq!(compare_optional(Some(1), Some(2), false), CmpOrdering::Less);
+        assert_eq!(compare_optional(Some(1), Some(2), true), CmpOrdering::Greater);
+        for descending in [false, true] {
+            assert_eq!(compare_optional(Some(1), None, descending), CmpOrdering::Less);
+            assert_eq!(compare_optional(None, Some(1), descending), CmpOrdering::Greater);
+            assert_eq!(compare_optional(None, None, descending), CmpOrdering::Equal);
+        }
+    }
+
+    #[test]
+    fn sorts_by_each_field() {
+        let sorted = |json| names(&apply_query(library(), &query(json))).join(", ");
+        assert_eq!(
+            sorted(json!({})),
+            "8.51 (CL-6165369), 9.10 (CL-6639283), Zero Point"
+        );
+        assert_eq!(
+            sorted(json!({ "sort": "version" })),
+            "8.51 (CL-6165369), 9.10 (CL-6639283), Zero Point"
+        );
+        assert_eq!(
+            sorted(json!({ "sort": "version", "descending": true })),
+            "Zero Point, 9.10 (CL-6639283), 8.51 (CL-6165369)"
+        );
+        assert_eq!(
+            sorted(json!({ "sort": "play_time", "descending": true })),
+            "Zero Point, 9.10 (CL-6639283), 8.51 (CL-6165369)"
+        );
+        assert_eq!(
+            sorted(json!({ "sort": "added_at" })),
+            "Zero Point, 9.10 (CL-6639283), 8.51 (CL-6165369)"
+        );
+        assert_eq!(
+            sorted(json!({ "sort": "last_played", "descending": true })),
+            "8.51 (CL-6165369), 9.10 (CL-6639283), Zero Point"
+        );
+    }
+
+    #[test]
+    fn filters_by_search_tags_and_favorites() {
+        let filtered = |json| names(&apply_query(library(), &query(json))).join(", ");
+        assert_eq!(filtered(json!({ "search": "zero" })), "Zero Point");
+        assert_eq!(filtered(json!({ "search": "TESTING" })), "8.51 (CL-6165369)");
+        assert_eq!(filtered(json!({ "search": "creative" })), "8.51 (CL-6165369)");
+        assert_eq!(
+            filtered(json!({ "search": "  " })),
+            "8.51 (CL-6165369), 9.10 (CL-6639283), Zero Point"
+        );
+        assert_eq!(
+            filtered(json!({ "tags": ["LAN"] })),
+            "8.51 (CL-6165369), 9.10 (CL-6639283)"
+        );
+        assert_eq!(filtered(json!({ "tags": ["lan", "creative"] })), "8.51 (CL-6165369)");
+        assert_eq!(filtered(json!({ "favorites_only": true })), "Zero Point");
+    }
+
+    #[test]
+    fn tags_are_trimmed_and_deduplicated() {
+        let tags = ["  LAN ", "lan", "", "Creative", "  "].map(String::from).to_vec();
+        assert_eq!(normalize_tags(tags), ["LAN", "Creative"]);
+    }
+
+    #[test]
+    fn updates_only_what_is_given() {
+        let mut versions: HashMap<String, StoredVersion> =
+            library().into_iter().map(|version| (version.path.clone(), version)).collect();
+        let update = |json| serde_json::from_value::<MetadataUpdate>(json).unwrap();
+
+        let updated = update_metadata(
+            &mut versions,
+            "C:/Builds/10.40",
+            update(json!({ "tags": [" Event ", "event"] })),
+        )
+        .unwrap();
+        assert_eq!(updated.metadata.tags, ["Event"]);
+        assert_eq!(updated.metadata.display_name.as_deref(), Some("Zero Point"));
+        assert!(updated.metadata.favorite);
+        assert_eq!(updated.metadata.play_time_secs, 3600);
+
+        let updated = update_metadata(
+            &mut versions,
+            "C:/Builds/10.40",
+            update(json!({ "display_name": "  ", "favorite": false, "notes": "Live event" })),
+        )
+        .unwrap();
+        assert_eq!(updated.metadata.display_name, None);
+        assert!(!updated.metadata.favorite);
+        assert_eq!(updated.metadata.notes, "Live event");
+        assert_eq!(updated.metadata.tags, ["Event"]);
+        assert_eq!(versions["C:/Builds/10.40"].metadata.notes, "Live event");
+
+        assert!(matches!(
+            update_metadata(&mut versions, "C:/Builds/missing", MetadataUpdate::default()),
+            Err(LauncherError::NotFound(_))
+        ));
+    }
+}
@@ -0,0 +1,254 @@
+use futures_util::stream::{self, StreamExt};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter, State};
+
+use crate::error::LauncherError;
+use crate::library_metadata::VersionMetadata;
+use crate::{detect_version, save_versions, StoredVersion, VersionState};
+
+const SHIPPING_EXE: &str = "FortniteGame/Binaries/Win64/FortniteClient-Win64-Shipping.exe";
+const DEFAULT_MAX_DEPTH: usize = 6;
+const SCAN_WORKERS: usize = 4;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ScanFailure {
+    pub path: String,
+    pub error: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LibraryScanReport {
+    pub found: usize,
+    pub added: Vec<String>,
+    pub already_present: Vec<String>,
+    pub failed: Vec<ScanFailure>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ScanProgress {
+    root: String,
+    scanned: usize,
+    total: usize,
+    path: String,
+    ok: bool,
+}
+
+pub fn is_build_root(dir: &Path) -> bool {
+    dir.join(SHIPPING_EXE).is_file()
+}
+
+/// Walks `root` up to `max_depth` levels deep and returns every directory that
+/// holds a shipping exe. A build root is not descended into, symlinks are not
+/// followed, and unreadable directories are skipped.
+pub fn find_build_roots(root: &Path, max_depth: usize) -> Vec<PathBuf> {
+    let mut found = Vec::new();
+    let mut seen = HashSet::new();
+    let mut stack = vec![(root.to_path_buf(), 0usize)];
+
+    while let Some((dir, depth)) = stack.pop() {
+        let canonical = fs::canonicalize(&dir).unwrap_or_else(|_| dir.clone());
+        if !seen.insert(canonical) {
+            continue;
+        }
+
+        if is_build_root(&dir) {
+            found.push(dir);
+            continue;
+        }
+
+        if depth >= max_depth {
+            continue;
+        }
+
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                println!("Skipping unreadable directory {}: {}", dir.display(), e);
+                continue;
+            }
+        };
+
+        for entry in entries.flatten() {
+            if entry.file_type().map(|t| t.is_dir()).unwrap_or(false) {
+                stack.push((entry.path(), depth + 1));
+            }
+        }
+    }
+
+    found.sort();
+    found
+}
+
+#[tauri::command]
+pub async fn scan_library(
+    root: String,
+    max_depth: Option<usize>,
+    state: State<'_, VersionState>,
+    app_handle: AppHandle,
+) -> Result<LibraryScanReport, LauncherError> {
+    let root_path = PathBuf::from(&root);
+    if !root_path.is_dir() {
+        return Err(LauncherError::NotFound(format!("{} is not a directory", root)));
+    }
+
+    let depth = max_depth.unwrap_or(DEFAULT_MAX_DEPTH);
+    let candidates = tokio::task::spawn_blocking(move || find_build_roots(&root_path, depth)).await?;
+    let found = candidates.len();
+
+    let known: HashSet<PathBuf> = {
+        let versions = state.0.lock().unwrap();
+        versions.keys().filter_map(|p| fs::canonicalize(p).ok()).collect()
+    };
+
+    let mut already_present = Vec::new();
+    let mut pending = Vec::new();
+    for candidate in candidates {
+        let path = candidate.to_string_lossy().to_string();
+        match fs::canonicalize(&candidate) {
+            Ok(canonical) if known.contains(&canonical) => already_present.push(path),
+            _ => pending.push(path),
+        }
+    }
+
+    let total = pending.len();
+    let mut results = stream::iter(pending.into_iter().map(|path| {
+        let app_handle = app_handle.clone();
+        async move {
+            let detect = {
+                let path = path.clone();
+                async move { detect_version(&path, &app_handle).await }
+            };
+            let result = match tokio::spawn(detect).await {
+                Ok(result) => result.map_err(|e| e.to_string()),
+                Err(e) => Err(e.to_string()),
+            };
+            (path, result)
+        }
+    }))
+    .buffer_unordered(SCAN_WORKERS);
+
+    // The pins keep the new thumbnails from being collected before the
+    // versions that use them are saved.
+    let mut detected = Vec::new();
+    let mut pins = Vec::new();
+    let mut failed = Vec::new();
+    let mut scanned = 0;
+
+    while let Some((path, result)) = results.next().await {
+        scanned += 1;
+        let _ = app_handle.emit("library-scan-progress", ScanProgress {
+            root: root.clone(),
+            scanned,
+            total,
+            path: path.clone(),
+            ok: result.is_ok(),
+        });
+
+        match result {
+            Ok((info, pin)) => {
+                detected.push(StoredVersion {
+                    path,
+                    version: info.version,
+                    technical_version: info.technical_version,
+                    splash_key: Some(info.splash_key),
+                    metadata: VersionMetadata::added_now(),
+                });
+                pins.push(pin);
+            }
+            Err(error) => failed.push(ScanFailure { path, error }),
+        }
+    }
+
+    let added: Vec<String> = detected.iter().map(|v| v.path.clone()).collect();
+
+    if !detected.is_empty() {
+        let mut versions = state.0.lock().unwrap();
+        for version in detected {
+            versions.insert(version.path.clone(), version);
+        }
+        save_versions(&versions, &app_handle)?;
+    }
+    drop(pins);
+
+    Ok(LibraryScanReport {
+        found,
+        added,
+        already_present,
+        failed,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fs::{fake_build, TempDir};
+
+    #[test]
+    fn stops_at_the_depth_limit() {
+        let dir = TempDir::new("scan", "depth");
+        let shallow = fake_build(&dir, "shallow", false);
+        let deep = fake_build(&dir.join("a/b/c"), "deep", false);
+
+        assert_eq!(find_build_roots(&dir, 1), [shallow.clone()]);
+        assert_eq!(find_build_roots(&dir, 3), [shallow.clone()]);
+        let mut all = vec![shallow, deep];
+        all.sort();
+        assert_eq!(find_build_roots(&dir, 4), all);
+    }
+
+    #[test]
+    fn does_not_descend_into_a_build_root() {
+        let dir = TempDir::new("scan", "nested");
+        let outer = fake_build(&dir, "outer", false);
+        fake_build(&outer, "inner", false);
+
+        assert_eq!(find_build_roots(&dir, DEFAULT_MAX_DEPTH), [outer]);
+    }
+
+    #[test]
+    fn a_folder_that_is_a_build_is_its_own_result() {
+        let dir = TempDir::new("scan", "self");
+        let root = fake_build(&dir, "build", false);
+
+        assert_eq!(find_build_roots(&root, 0), [root]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn does_not_follow_symlinks() {
+        let dir = TempDir::new("scan", "symlinks");
+        let elsewhere = TempDir::new("scan", "symlinks-target");
+        let root = dir.join("library");
+        let build = fake_build(&root, "build", false);
+        fake_build(&elsewhere, "linked", false);
+        std::os::unix::fs::symlink(&root, root.join("loop")).unwrap();
+        std::os::unix::fs::symlink(&*elsewhere, root.join("elsewhere")).unwrap();
+
+        assert_eq!(find_build_roots(&root, DEFAULT_MAX_DEPTH), [build]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn skips_unreadable_folders() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = TempDir::new("scan", "unreadable");
+        let open = fake_build(&dir, "open", false);
+        let locked = dir.join("locked");
+        let hidden = fake_build(&locked, "hidden", false);
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o000)).unwrap();
+        // Root reads it regardless, in which case there is nothing to skip.
+        let readable = fs::read_dir(&locked).is_ok();
+
+        let found = find_build_roots(&dir, DEFAULT_MAX_DEPTH);
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o755)).unwrap();
+        if readable {
+            assert_eq!(found, [hidden, open]);
+        } else {
+            assert_eq!(found, [open]);
+        }
+    }
+}
@@ -0,0 +1,381 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine as _;
+use reqwest::{Client, StatusCode};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::complete_login;
+use crate::error::LauncherError;
+use crate::offline::unix_now;
+use crate::token_manager::TokenResponse;
+use crate::token_store::rand_bytes;
+
+pub const AUTHORIZE_URL: &str = "your_authorize_url";
+pub const TOKEN_URL: &str = "your_token_url";
+pub const CLIENT_ID: &str = "rewind-launcher";
+const CALLBACK_PATH: &str = "/callback";
+// Long enough to log in to Discord from scratch, short enough that a
+// forgotten attempt doesn't hold a port all day.
+const LOGIN_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+const TOKEN_TIMEOUT: Duration = Duration::from_secs(10);
+// Browsers send a few KB of headers at most; anything bigger isn't one.
+const MAX_REQUEST_SIZE: usize = 16 * 1024;
+// A connection that hasn't sent its request by now isn't a browser, and
+// would otherwise keep the real redirect waiting behind it.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A PKCE verifier and its S256 challenge.
+pub struct Pkce {
+    pub verifier: String,
+    pub challenge: String,
+}
+
+impl Pkce {
+    pub fn generate() -> Self {
+        Pkce::from_verifier(URL_SAFE_NO_PAD.encode(rand_bytes::<32>()))
+    }
+
+    pub fn from_verifier(verifier: String) -> Self {
+        let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()));
+        Pkce { verifier, challenge }
+    }
+}
+
+/// One browser login: a listener on a free loopback port, and the PKCE
+/// verifier and `state` nonce the redirect has to match.
+pub struct LoopbackLogin {
+    listener: TcpListener,
+    pub redirect_uri: String,
+    pub state: String,
+    pub pkce: Pkce,
+}
+
+impl LoopbackLogin {
+    pub async fn bind() -> std::io::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let port = listener.local_addr()?.port();
+        Ok(LoopbackLogin {
+            listener,
+            redirect_uri: format!("http://127.0.0.1:{}{}", port, CALLBACK_PATH),
+            state: hex::encode(rand_bytes::<16>()),
+            pkce: Pkce::generate(),
+        })
+    }
+
+    /// Where to send the browser to log in.
+    pub fn authorize_url(&self, authorize_url: &str) -> String {
+        format!(
+            "{}?response_type=code&client_id={}&redirect_uri={}&code_challenge={}&code_challenge_method=S256&state={}",
+            authorize_url,
+            urlencoding::encode(CLIENT_ID),
+            urlencoding::encode(&self.redirect_uri),
+            self.pkce.challenge,
+            self.state,
+        )
+    }
+
+    /// Waits for the browser to come back with an authorization code, and
+    /// tells the user in the browser how it went. Other requests, like the
+    /// favicon, get a 404 and a redirect for some other login a 400; both are
+    /// otherwise ignored, so neither can end this login.
+    pub async fn wait_for_code(&self, timeout: Duration) -> Result<String, LauncherError> {
+        tokio::time::timeout(timeout, async {
+            loop {
+                let (mut stream, _) = self
+                    .listener
+                    .accept()
+                    .await
+                    .map_err(|e| LauncherError::io("Failed to accept the login redirect", e))?;
+                let Ok(Some(target)) = tokio::time::timeout(REQUEST_TIMEOUT, read_request_target(&mut stream)).await
+                else {
+                    continue;
+                };
+                let Some(query) = callback_query(&target) else {
+                    respond(&mut stream, "404 Not Found", "Not found").await;
+                    continue;
+                };
+                if !state_matches(&query, &self.state) {
+                    respond(&mut stream, "400 Bad Request", "This isn't the login Rewind is waiting for.").await;
+                    continue;
+                }
+                let result = parse_callback(&query, &self.state);
+                let page = match &result {
+                    Ok(_) => "Logged in. You can close this tab and go back to Rewind.".to_string(),
+                    Err(e) => format!("Login failed: {}. Go back to Rewind and try again.", e),
+                };
+                respond(&mut stream, "200 OK", &page).await;
+                return result;
+            }
+        })
+        .await
+        .map_err(|_| LauncherError::Timeout("Timed out waiting for the browser login".to_string()))?
+    }
+}
+
+/// The path and query of the request on `stream`, if it is a GET.
+async fn read_request_target(stream: &mut TcpStream) -> Option<String> {
+    let mut request = Vec::new();
+    let mut buf = [0u8; 1024];
+    while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+        let read = stream.read(&mut buf).await.ok()?;
+        if read == 0 || request.len() + read > MAX_REQUEST_SIZE {
+            return None;
+        }
+        request.extend_from_slice(&buf[..read]);
+    }
+    let request = String::from_utf8_lossy(&request);
+    let mut parts = request.lines().next()?.split_whitespace();
+    match (parts.next(), parts.next()) {
+        (Some("GET"), Some(target)) => Some(target.to_string()),
+        _ => None,
+    }
+}
+
+/// The query of `target` if it is the callback path.
+fn callback_query(target: &str) -> Option<HashMap<String, String>> {
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    if path != CALLBACK_PATH {
+        return None;
+    }
+    Some(
+        query
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .map(|(key, value)| {
+                let decode = |s: &str| {
+                    urlencoding::decode(&s.replace('+', " "))
+                        .map(|s| s.into_owned())
+                        .unwrap_or_default()
+                };
+                (decode(key), decode(value))
+            })
+            .collect(),
+    )
+}
+
+fn state_matches(query: &HashMap<String, String>, expected_state: &str) -> bool {
+    query.get("state").map(String::as_str) == Some(expected_state)
+}
+
+/// The code from a redirect, as long as it answers the login that sent
+/// `expected_state`. A mismatched state means the redirect came from some
+/// other login, or was forged, and its code must not be used.
+pub fn parse_callback(query: &HashMap<String, String>, expected_state: &str) -> Result<String, LauncherError> {
+    if !state_matches(query, expected_state) {
+        return Err(LauncherError::InvalidInput(
+            "The login response doesn't belong to this login attempt".to_string(),
+        ));
+    }
+    if let Some(error) = query.get("error") {
+        let description = query.get("error_description").unwrap_or(error);
+        return Err(LauncherError::AuthExpired(format!(
+            "The login was refused ({})",
+            description
+        )));
+    }
+    match query.get("code") {
+        Some(code) if !code.is_empty() => Ok(code.clone()),
+        _ => Err(LauncherError::InvalidInput(
+            "The login response has no code".to_string(),
+        )),
+    }
+}
+
+/// `text` made safe to put inside HTML. The error pages quote what the
+/// redirect sent, which anyone can make a browser send.
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+async fn respond(stream: &mut TcpStream, status: &str, body: &str) {
+    let body = format!(
+        "<!doctype html><html><head><meta charset=\"utf-8\"><title>Rewind</title></head><body><p>{}</p></body></html>",
+        escape_html(body)
+    );
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    if let Err(e) = stream.write_all(response.as_bytes()).await {
+        println!("Failed to answer the login redirect: {}", e);
+    }
+    let _ = stream.shutdown().await;
+}
+
+/// Trades an authorization code for a launcher token at `url`.
+pub async fn exchange_authorization_code(
+    client: &Client,
+    url: &str,
+    code: &str,
+    verifier: &str,
+    redirect_uri: &str,
+) -> Result<TokenResponse, LauncherError> {
+    let response = client
+        .post(url)
+        .form(&[
+            ("grant_type", "authorization_code"),
+            ("client_id", CLIENT_ID),
+            ("code", code),
+            ("code_verifier", verifier),
+            ("redirect_uri", redirect_uri),
+        ])
+        .timeout(TOKEN_TIMEOUT)
+        .send()
+        .await?;
+    match response.status() {
+        StatusCode::BAD_REQUEST | StatusCode::UNAUTHORIZED => {
+            return Err(LauncherError::AuthExpired(
+                "The login code was rejected. Try logging in again.".to_string(),
+            ))
+        }
+        status if !status.is_success() => return Err(LauncherError::api(status)),
+        _ => {}
+    }
+    Ok(response.json().await?)
+}
+
+/// Runs `login` to the end: the redirect, then the code exchange.
+pub async fn finish_login(
+    login: LoopbackLogin,
+    token_url: &str,
+    timeout: Duration,
+) -> Result<TokenResponse, LauncherError> {
+    let code = login.wait_for_code(timeout).await?;
+    drop(login.listener);
+    exchange_authorization_code(
+        &Client::new(),
+        token_url,
+        &code,
+        &login.pkce.verifier,
+        &login.redirect_uri,
+    )
+    .await
+}
+
+/// Logs in through the browser without the `rewindlauncher://` scheme, for
+/// when it isn't registered. Returns the page to open; the outcome arrives
+/// as `login-success` or `login-error`, same as a deep-link login.
+#[tauri::command]
+pub async fn start_loopback_login(app_handle: AppHandle) -> Result<String, LauncherError> {
+    let login = LoopbackLogin::bind()
+        .await
+        .map_err(|e| LauncherError::io("Failed to start the login listener", e))?;
+    let url = login.authorize_url(AUTHORIZE_URL);
+    println!("Waiting for the browser login on {}", login.redirect_uri);
+
+    tauri::async_runtime::spawn(async move {
+        match finish_login(login, TOKEN_URL, LOGIN_TIMEOUT).await {
+            Ok(token) => complete_login(&app_handle, token.into_stored(unix_now())).await,
+            Err(e) => {
+                println!("Browser login failed: {}", e);
+                let _ = app_handle.emit("login-error", e.to_string());
+            }
+        }
+    });
+    Ok(url)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_http::{Response, TestServer};
+    use serde_json::json;
+
+    fn query(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn challenge_matches_the_rfc_7636_example() {
+        let pkce = Pkce::from_verifier("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk".to_string());
+        assert_eq!(pkce.challenge, "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM");
+    }
+
+    #[test]
+    fn callback_needs_this_logins_state_and_a_code() {
+        assert_eq!(parse_callback(&query(&[("state", "s1"), ("code", "c")]), "s1").unwrap(), "c");
+        assert!(matches!(
+            parse_callback(&query(&[("state", "other"), ("code", "c")]), "s1"),
+            Err(LauncherError::InvalidInput(_))
+        ));
+        assert!(matches!(
+            parse_callback(&query(&[("code", "c")]), "s1"),
+            Err(LauncherError::InvalidInput(_))
+        ));
+        let refused = parse_callback(
+            &query(&[("state", "s1"), ("error", "access_denied"), ("error_description", "User said no")]),
+            "s1",
+        );
+        assert!(matches!(&refused, Err(LauncherError::AuthExpired(m)) if m.contains("User said no")));
+        assert!(matches!(
+            parse_callback(&query(&[("state", "s1"), ("code", "")]), "s1"),
+            Err(LauncherError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn escapes_what_the_redirect_sent() {
+        assert_eq!(
+            escape_html("<script>alert('x')</script> & \"y\""),
+            "&lt;script&gt;alert(&#39;x&#39;)&lt;/script&gt; &amp; &quot;y&quot;"
+        );
+    }
+
+    #[tokio::test]
+    async fn finishes_a_login_through_the_loopback_redirect() {
+        let token_server = TestServer::start(|_| {
+            Response::json(200, json!({ "launcherToken": "launcher-token", "refreshToken": "refresh", "expiresIn": 60 }))
+        })
+        .await;
+        let login = LoopbackLogin::bind().await.unwrap();
+        let redirect_uri = login.redirect_uri.clone();
+        let state = login.state.clone();
+        let verifier = login.pkce.verifier.clone();
+        let token_url = format!("{}/token", token_server.url);
+        let finished = tokio::spawn(async move { finish_login(login, &token_url, Duration::from_secs(10)).await });
+
+        let browser = Client::new();
+        let favicon = redirect_uri.replace(CALLBACK_PATH, "/favicon.ico");
+        assert_eq!(browser.get(&favicon).send().await.unwrap().status(), 404);
+        let forged = browser
+            .get(format!("{}?code=forged&state=other", redirect_uri))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(forged.status(), 400);
+        let page = browser
+            .get(format!("{}?code=the-code&state={}", redirect_uri, state))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(page.status(), 200);
+        assert!(page.text().await.unwrap().contains("Logged in"));
+
+        let token = finished.await.unwrap().unwrap();
+        assert_eq!(token.launcher_token, "launcher-token");
+        assert_eq!(token.refresh_token.as_deref(), Some("refresh"));
+        let requests = token_server.requests();
+        assert_eq!(requests.len(), 1);
+        let form = requests[0].body_text();
+        assert!(form.contains("grant_type=authorization_code"), "{}", form);
+        assert!(form.contains("code=the-code"), "{}", form);
+        assert!(form.contains(&format!("code_verifier={}", verifier)), "{}", form);
+    }
+}
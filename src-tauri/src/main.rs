@@ -4,7 +4,6 @@
     windows_subsystem = "windows"
 )]
 
-use sysinfo::Pid;
 use tauri::Manager;
 use tauri::Emitter;
 use regex::Regex;
@@ -14,13 +13,78 @@ use serde::{Serialize, Deserialize};
 use std::fs;
 use std::path::PathBuf;
 use std::fs::File;
-use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::io::{self, Write};
 use std::path::Path;
-use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
 use std::sync::Mutex;
 
+mod error;
+use error::LauncherError;
 mod discord_rpc;
 use discord_rpc::{DiscordRpcState, discord_rpc_init, discord_rpc_set_activity, discord_rpc_clear_activity, discord_rpc_disconnect};
+mod version_scan;
+use version_scan::{parse_release, scan_release_string, ReleaseInfo};
+mod pe_version;
+use pe_version::read_pe_version;
+mod library_scan;
+use library_scan::scan_library;
+mod persist;
+mod versions_file;
+use versions_file::{LoadedVersions, RecoveryNotice};
+mod thumbnail_cache;
+use thumbnail_cache::{
+    backfill_thumbnails, collect_thumbnail_garbage, handle_thumbnail_request, set_thumbnail_options,
+    splash_path, thumbnail_url, ThumbnailCache, ThumbnailPin, THUMBNAIL_SCHEME,
+};
+mod build_verify;
+use build_verify::{create_manifest, verify_version};
+mod downloader;
+use downloader::{cancel_download, pause_download, resume_download, set_download_limit, start_download, DownloadManager};
+mod archive_import;
+use archive_import::import_archive;
+mod safe_delete;
+use safe_delete::{
+    check_deletable, plan_remove_version, protected_dirs, resume_pending_deletions, schedule_deletion,
+    undo_delete_version, DeletionState,
+};
+mod library_metadata;
+use library_metadata::{query_versions, update_version_metadata, VersionMetadata};
+mod game_launcher;
+use game_launcher::{get_launcher_config, set_launcher_config, LauncherState};
+mod process_tree;
+mod game_session;
+use game_session::{get_game_sessions, is_game_running, start_session, stop_game_process, GameSessions};
+mod session_log;
+use session_log::get_session_log;
+mod crash_report;
+use crash_report::export_crash_report;
+mod launch_profiles;
+use launch_profiles::{
+    auth_args, base_args, delete_launch_profile, get_launch_profiles, merge_args, preview_launch,
+    resolve_launch, save_launch_profile, set_build_launch_override, set_default_launch_profile, ProfileState,
+};
+mod payloads;
+use payloads::{get_payload_manifest, resolve_payloads, set_payload_manifest};
+mod offline;
+use offline::{get_network_status, offline_token, resolve_offline, set_network_mode, LaunchReport, NetworkState};
+mod preflight;
+use preflight::preflight;
+mod token_store;
+use token_store::TokenState;
+mod token_manager;
+use token_manager::{active_token, get_auth_state, refresh_loop, TokenManager};
+mod accounts;
+use accounts::{
+    active_account, add_account, adopt_unassigned_token, forget_account, list_accounts, remove_account,
+    switch_account, touch_account, AccountState,
+};
+mod exchange_code;
+use exchange_code::exchange_code;
+mod loopback_login;
+use loopback_login::start_loopback_login;
+#[cfg(test)]
+mod test_fs;
+#[cfg(test)]
+mod test_http;
 use std::collections::HashMap;
 use std::process::{exit};
 
@@ -28,6 +92,11 @@ use std::process::{exit};
 struct StoredToken {
     token: String,
     stored_at: u64,
+    #[serde(default)]
+    refresh_token: Option<String>,
+    /// Set when the backend said when the token expires.
+    #[serde(default)]
+    expires_at: Option<u64>,
 }
 
 #[derive(Debug, serde::Deserialize, Clone)]
@@ -43,7 +112,6 @@ struct UserInfo {
     #[serde(rename = "accountId")]
     account_id: String,
     email: String,
-    password: String,
     #[serde(rename = "avatar_url")]
     avatar_url: String,
     #[serde(rename = "favoriteSkin")]
@@ -57,7 +125,6 @@ struct UserInfo {
 }
 
 use std::{sync::{Arc}, process::Command};
-use std::sync::atomic::{AtomicU32, Ordering};
 
 #[derive(Debug, serde::Deserialize, Clone)]
 struct TokenInfo {
@@ -89,10 +156,6 @@ struct AppState {
     login_data: Mutex<Option<UserInfo>>,
 }
 
-static CURRENT_GAME_PID: AtomicU32 = AtomicU32::new(0);
-static LAUNCHER_PID: AtomicU32 = AtomicU32::new(0);
-static EAC_PID: AtomicU32 = AtomicU32::new(0);
-
 fn get_app_state() -> Arc<Mutex<AppState>> {
     Arc::new(Mutex::new(AppState::default()))
 }
@@ -101,8 +164,46 @@ async fn decode_launcher_token(token: &str) -> Result<UserInfo, Box<dyn std::err
     Ok(user_info)
 }
 
+/// Finishes a login however the token arrived: adds the account and tells
+/// the webview who logged in.
+async fn complete_login(app_handle: &tauri::AppHandle, stored: StoredToken) {
+    let window = app_handle.get_webview_window("main").unwrap();
+    window.show().unwrap();
+    window.set_focus().unwrap();
+
+    match decode_launcher_token(&stored.token).await {
+        Ok(user_info) => {
+            if let Err(e) = add_account(app_handle, &user_info, &stored) {
+                println!("could not store token: {}", e);
+                window.emit("login-error", "Couldn't save the login.").unwrap();
+                return;
+            }
+
+            let payload = serde_json::json!({
+                "username": user_info.username,
+                "accountId": user_info.account_id,
+                "email": user_info.email,
+                "avatar_url": user_info.avatar_url,
+                "favoriteSkin": user_info.favorite_skin,
+                "mtxCurrency": user_info.mtx_currency,
+                "hype": user_info.hype,
+                "role": {
+                    "name": user_info.role.name,
+                    "color": user_info.role.color
+                }
+            });
+
+            window.emit("login-success", payload).unwrap();
+        },
+        Err(e) => {
+            println!("error decoding token: {}", e);
+            window.emit("login-error", "Token Authorization failed.").unwrap();
+        }
+    }
+}
+
 #[tauri::command]
-async fn fetch_events() -> Result<Vec<Event>, String> {
+async fn fetch_events() -> Result<Vec<Event>, LauncherError> {
     
 }
 
@@ -110,21 +211,22 @@ fn get_token_path(app_handle: &tauri::AppHandle) -> PathBuf {
 
 }
 
-fn store_token(token: &str, app_handle: &tauri::AppHandle) -> Result<(), String> {
-    
-}
-
 fn get_stored_token(app_handle: &tauri::AppHandle) -> Option<String> {
-    
+    active_token(app_handle).map(|(_, stored)| stored.token)
 }
 
+/// Logs the active account out, forgetting it and its token. Other accounts
+/// stay logged in.
 #[tauri::command]
-fn clear_stored_token(app_handle: tauri::AppHandle) -> Result<(), String> {
-    
+fn clear_stored_token(app_handle: tauri::AppHandle) -> Result<(), LauncherError> {
+    match active_account(&app_handle) {
+        Some(account_id) => forget_account(&app_handle, &account_id).map(|_| ()),
+        None => Ok(()),
+    }
 }
 
 #[tauri::command]
-async fn check_stored_token(app_handle: tauri::AppHandle) -> Result<serde_json::Value, String> {
+async fn check_stored_token(app_handle: tauri::AppHandle) -> Result<serde_json::Value, LauncherError> {
     
 }
 
@@ -142,17 +244,17 @@ struct VersionCheckResponse {
 const CURRENT_VERSION: &str = "0.0.15";
 
 #[tauri::command]
-async fn check_version() -> Result<VersionCheckResponse, String> {
+async fn check_version() -> Result<VersionCheckResponse, LauncherError> {
 
 }
 
 #[tauri::command]
-async fn download_and_install_update(download_url: String, app_handle: tauri::AppHandle) -> Result<(), String> {
+async fn download_and_install_update(download_url: String, app_handle: tauri::AppHandle) -> Result<(), LauncherError> {
 
     let temp_dir = std::env::temp_dir();
     let file_name = match download_url.split('/').last() {
         Some(name) => name,
-        None => return Err("Could not extract filename".to_string())
+        None => return Err(LauncherError::InvalidInput("Could not extract filename".to_string()))
     };
     
     let download_path = temp_dir.join(file_name);
@@ -160,22 +262,19 @@ async fn download_and_install_update(download_url: String, app_handle: tauri::Ap
     let client = reqwest::Client::new();
     let response = client.get(&download_url)
         .send()
-        .await
-        .map_err(|e| format!("Failed to download update: {}", e))?;
+        .await?;
     
     if !response.status().is_success() {
-        return Err(format!("Failed to download update,: {}", response.status()));
+        return Err(LauncherError::api(response.status()));
     }
 
     let mut file = File::create(&download_path)
-        .map_err(|e| format!("Failed to create file: {}", e))?;
+        .map_err(|e| LauncherError::io("Failed to create file", e))?;
     
-    let content = response.bytes()
-        .await
-        .map_err(|e| format!("Failed to get response: {}", e))?;
+    let content = response.bytes().await?;
     
     file.write_all(&content)
-        .map_err(|e| format!("Failed to write to file: {}", e))?;
+        .map_err(|e| LauncherError::io("Failed to write to file", e))?;
 
     let path_str = download_path.to_string_lossy().to_string();
     
@@ -190,94 +289,161 @@ async fn download_and_install_update(download_url: String, app_handle: tauri::Ap
     );
     
     fs::write(&batch_path, batch_content)
-        .map_err(|e| format!("Failed to create bat file: {}", e))?;
+        .map_err(|e| LauncherError::io("Failed to create bat file", e))?;
     
     let _child = std::process::Command::new("cmd")
         .args(["/C", batch_path.to_string_lossy().as_ref()])
         .spawn()
-        .map_err(|e| format!("Failed to execute bat file: {}", e))?;
+        .map_err(|e| LauncherError::io("Failed to execute bat file", e))?;
 
     app_handle.exit(0);
     
     Ok(())
 }
 
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum VersionSource {
+    BuildString,
+    VersionResource,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum VersionConfidence {
+    High,
+    Medium,
+    Low,
+}
+
 #[derive(Serialize)]
 struct VersionInfo {
     version: String,
     technical_version: String,
+    splash_key: String,
     splash_image: String,
+    source: VersionSource,
+    confidence: VersionConfidence,
+    product_version: Option<String>,
+    file_version: Option<String>,
+    link_timestamp: Option<u32>,
 }
 
-#[tauri::command]
-async fn detect_fortnite_version(path: String) -> Result<VersionInfo, String> {
-    let exe_path = Path::new(&path).join("FortniteGame/Binaries/Win64/FortniteClient-Win64-Shipping.exe");
-    let splash_path = Path::new(&path).join("FortniteGame/Content/Splash/Splash.bmp");
-
-    if !exe_path.exists() {
-        return Err("FortniteClient-Win64-Shipping.exe Not Found!!".to_string());
-    }
-
-    if !splash_path.exists() {
-        return Err("Splash.bmp Not found!!".to_string());
-    }
-
-    let mut file = File::open(&exe_path).map_err(|e| e.to_string())?;
-    let mut buffer = Vec::new();
-    file.read_to_end(&mut buffer).map_err(|e| e.to_string())?;
-
-    let image_data = tokio::fs::read(&splash_path)
-        .await
-        .map_err(|e| e.to_string())?;
-    let base64_string = BASE64.encode(image_data);
-    let splash_image = format!("data:image/bmp;base64,{}", base64_string);
+struct ExeVersion {
+    release: Option<ReleaseInfo>,
+    source: VersionSource,
+    confidence: VersionConfidence,
+    product_version: Option<String>,
+    file_version: Option<String>,
+    link_timestamp: Option<u32>,
+}
 
-    let pattern = "++Fortnite+Release-";
-    let pattern_bytes: Vec<u16> = pattern.encode_utf16().collect();
-    let pattern_raw: Vec<u8> = pattern_bytes.iter()
-        .flat_map(|&x| x.to_le_bytes())
-        .collect();
+/// Reads what the shipping exe says about its version. Blocking: it reads
+/// through the whole file when the build string isn't near the start.
+fn read_exe_version(exe_path: &Path) -> Result<ExeVersion, LauncherError> {
+    let mut file = File::open(exe_path)?;
 
-    let mut indices = Vec::new();
-    for (i, window) in buffer.windows(pattern_raw.len()).enumerate() {
-        if window == pattern_raw {
-            indices.push(i);
+    let pe_info = match read_pe_version(&mut file) {
+        Ok(info) => Some(info),
+        Err(e) => {
+            println!("Could not read PE version info from {}: {}", exe_path.display(), e);
+            None
         }
-    }
+    };
+    let product_version = pe_info.as_ref().and_then(|info| info.product_version.clone());
+    let file_version = pe_info.as_ref().and_then(|info| info.file_version.clone());
+    let link_timestamp = pe_info.as_ref().map(|info| info.link_timestamp);
+
+    let (release, source, confidence) = match scan_release_string(&mut file)? {
+        Some(release) => (Some(release), VersionSource::BuildString, VersionConfidence::High),
+        None => {
+            let release = product_version.as_deref()
+                .and_then(parse_release)
+                .or_else(|| file_version.as_deref().and_then(parse_release));
+            (release, VersionSource::VersionResource, VersionConfidence::Medium)
+        }
+    };
 
-    for &index in &indices {
-        file.seek(SeekFrom::Start(index as u64)).map_err(|e| e.to_string())?;
-        let mut version_buffer = vec![0u8; 200];
-        file.read(&mut version_buffer).map_err(|e| e.to_string())?;
+    Ok(ExeVersion {
+        release,
+        source,
+        confidence,
+        product_version,
+        file_version,
+        link_timestamp,
+    })
+}
 
-        let version_str = String::from_utf16_lossy(
-            &version_buffer.chunks(2)
-                .map(|chunk| u16::from_le_bytes([chunk[0], chunk[1]]))
-                .collect::<Vec<u16>>()
-        );
+#[tauri::command]
+async fn detect_fortnite_version(path: String, app_handle: tauri::AppHandle) -> Result<VersionInfo, LauncherError> {
+    let (version_info, _pin) = detect_version(&path, &app_handle).await?;
+    Ok(version_info)
+}
 
-        let re = Regex::new(r"\+\+Fortnite\+Release-(\d{1,2}\.\d{1,2}|Live|Next|Cert)-CL-(\d+)")
-            .map_err(|e| e.to_string())?;
+/// Reads the build's version and caches its splash, in that order so a build
+/// whose version can't be read leaves no thumbnail behind. The thumbnail
+/// stays pinned until the returned guard is dropped, which callers that
+/// store the version do only once it is saved.
+async fn detect_version(
+    path: &str,
+    app_handle: &tauri::AppHandle,
+) -> Result<(VersionInfo, ThumbnailPin), LauncherError> {
+    let exe_path = Path::new(path).join("FortniteGame/Binaries/Win64/FortniteClient-Win64-Shipping.exe");
+    let splash_path = splash_path(path);
 
-        if let Some(captures) = re.captures(&version_str) {
-            let version_num = captures.get(1).map_or("Unknown", |m| m.as_str());
-            let cl_num = captures.get(2).map_or("Unknown", |m| m.as_str());
-            
-           
-            let version = format!("{} (CL-{})", version_num, cl_num);
-            
-       
-            let technical_version = format!("{}.0-CL-{}", version_num, cl_num);
+    if !exe_path.exists() {
+        return Err(LauncherError::NotFound("FortniteClient-Win64-Shipping.exe Not Found!!".to_string()));
+    }
 
-            return Ok(VersionInfo {
-                version,
-                technical_version,
-                splash_image,
-            });
-        }
+    if !splash_path.exists() {
+        return Err(LauncherError::NotFound("Splash.bmp Not found!!".to_string()));
     }
 
-    Err("Version not found".to_string())
+    let ExeVersion {
+        release,
+        source,
+        confidence,
+        product_version,
+        file_version,
+        link_timestamp,
+    } = tokio::task::spawn_blocking(move || read_exe_version(&exe_path)).await??;
+
+    let (version, technical_version, source, confidence) = if let Some(release) = release {
+        (
+            format!("{} (CL-{})", release.release, release.cl),
+            format!("{}.0-CL-{}", release.release, release.cl),
+            source,
+            confidence,
+        )
+    } else if let Some(version) = product_version.clone().or_else(|| file_version.clone()) {
+        // Only bare numeric versions left; good enough to list the build, not
+        // to match it against the builds endpoint.
+        (
+            version.clone(),
+            file_version.clone().unwrap_or(version),
+            VersionSource::VersionResource,
+            VersionConfidence::Low,
+        )
+    } else {
+        return Err(LauncherError::CorruptBuild("Version not found".to_string()));
+    };
+
+    let image_data = tokio::fs::read(&splash_path).await?;
+    let cache = app_handle.state::<Arc<ThumbnailCache>>().inner().clone();
+    let pin = tokio::task::spawn_blocking(move || cache.store(&image_data)).await??;
+
+    let version_info = VersionInfo {
+        version,
+        technical_version,
+        splash_key: pin.key().to_string(),
+        splash_image: thumbnail_url(pin.key()),
+        source,
+        confidence,
+        product_version,
+        file_version,
+        link_timestamp,
+    };
+    Ok((version_info, pin))
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -285,7 +451,10 @@ struct StoredVersion {
     path: String,
     version: String,
     technical_version: String,
-    splash_image: String,
+    #[serde(default)]
+    splash_key: Option<String>,
+    #[serde(default)]
+    metadata: VersionMetadata,
 }
 
 #[derive(Debug, Serialize)]
@@ -296,10 +465,14 @@ struct Version {
     splash_image: String,
     access_type: String,
     build_name: String,
+    display_name: String,
+    metadata: VersionMetadata,
 }
 
 struct VersionState(Mutex<HashMap<String, StoredVersion>>);
 
+struct VersionsRecoveryState(Mutex<Option<RecoveryNotice>>);
+
 fn get_versions_file_path(app_handle: &tauri::AppHandle) -> PathBuf {
     app_handle
         .path()
@@ -308,30 +481,41 @@ fn get_versions_file_path(app_handle: &tauri::AppHandle) -> PathBuf {
         .join("versions.json")
 }
 
-fn load_versions(app_handle: &tauri::AppHandle) -> HashMap<String, StoredVersion> {
-    let path = get_versions_file_path(app_handle);
-    if path.exists() {
-        if let Ok(content) = fs::read_to_string(path) {
-            if let Ok(versions) = serde_json::from_str(&content) {
-                return versions;
-            }
-        }
-    }
-    HashMap::new()
+fn load_versions(app_handle: &tauri::AppHandle) -> LoadedVersions {
+    versions_file::load(&get_versions_file_path(app_handle))
 }
 
-fn save_versions(versions: &HashMap<String, StoredVersion>, app_handle: &tauri::AppHandle) -> Result<(), String> {
-    let path = get_versions_file_path(app_handle);
-    
-    if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
-    }
-    
-    let content = serde_json::to_string_pretty(versions)
-        .map_err(|e| e.to_string())?;
-    
-    fs::write(path, content).map_err(|e| e.to_string())?;
-    Ok(())
+fn save_versions(versions: &HashMap<String, StoredVersion>, app_handle: &tauri::AppHandle) -> Result<(), LauncherError> {
+    versions_file::save(&get_versions_file_path(app_handle), versions)
+}
+
+#[tauri::command]
+async fn get_versions_recovery(
+    state: tauri::State<'_, VersionsRecoveryState>,
+) -> Result<Option<RecoveryNotice>, LauncherError> {
+    Ok(state.0.lock().unwrap().clone())
+}
+
+/// Detects the build at `path` and stores it in the library.
+async fn register_version(path: String, app_handle: &tauri::AppHandle) -> Result<(), LauncherError> {
+    let (version_info, _pin) = detect_version(&path, app_handle).await?;
+
+    let state = app_handle.state::<VersionState>();
+    let mut versions = state.0.lock().unwrap();
+    // Re-adding a build keeps the labels and play time it already has.
+    let metadata = versions
+        .get(&path)
+        .map(|existing| existing.metadata.clone())
+        .unwrap_or_else(VersionMetadata::added_now);
+    versions.insert(path.clone(), StoredVersion {
+        path,
+        version: version_info.version,
+        technical_version: version_info.technical_version,
+        splash_key: Some(version_info.splash_key),
+        metadata,
+    });
+
+    save_versions(&versions, app_handle)
 }
 
 #[tauri::command]
@@ -339,22 +523,8 @@ async fn add_version(
     path: String,
     state: tauri::State<'_, VersionState>,
     app_handle: tauri::AppHandle,
-) -> Result<Vec<Version>, String> {
-    let version_info = detect_fortnite_version(path.clone()).await?;
-    
-
-    {
-        let mut versions = state.0.lock().unwrap();
-        versions.insert(path.clone(), StoredVersion {
-            path,
-            version: version_info.version,
-            technical_version: version_info.technical_version,
-            splash_image: version_info.splash_image,
-        });
-        
-        save_versions(&versions, &app_handle)?;
-    } 
-
+) -> Result<Vec<Version>, LauncherError> {
+    register_version(path, &app_handle).await?;
 
     get_versions_with_status(state).await
 }
@@ -362,7 +532,7 @@ async fn add_version(
 #[tauri::command]
 async fn get_versions(
     state: tauri::State<'_, VersionState>,
-) -> Result<Vec<StoredVersion>, String> {
+) -> Result<Vec<StoredVersion>, LauncherError> {
     let versions = state.0.lock().unwrap();
     Ok(versions.values().cloned().collect())
 }
@@ -373,29 +543,21 @@ async fn remove_version(
     delete_files: bool,
     state: tauri::State<'_, VersionState>,
     app_handle: tauri::AppHandle,
-) -> Result<Vec<Version>, String> {
+) -> Result<Vec<Version>, LauncherError> {
+    // Files go first: if they can't be moved aside, the build stays listed
+    // rather than leaving an unlisted folder behind.
+    if delete_files {
+        let root = check_deletable(Path::new(&path), &protected_dirs(&app_handle))?;
+        let version = state.0.lock().unwrap().get(&path).cloned();
+        schedule_deletion(&app_handle, &root, version)?;
+    }
 
     {
         let mut versions = state.0.lock().unwrap();
         versions.remove(&path);
         save_versions(&versions, &app_handle)?;
     }
-
-    if delete_files {
-        let path_obj = Path::new(&path);
-        
-        if let Some(parent) = path_obj.parent() {
-            match fs::remove_dir_all(parent) {
-                Ok(_) => (),
-                Err(e) => {
-                    println!("Failed to delete parent directory {}: {}", parent.display(), e);
-                    return Err(format!("Failed to delete directory: {}", e));
-                }
-            }
-        } else {
-            return Err("Could not determine parent directory".to_string());
-        }
-    }
+    collect_thumbnail_garbage(&app_handle);
 
     get_versions_with_status(state).await
 }
@@ -425,12 +587,12 @@ struct VersionWithStatus {
 }
 
 #[tauri::command]
-async fn fetch_builds() -> Result<BuildsResponse, String> {
+async fn fetch_builds() -> Result<BuildsResponse, LauncherError> {
     
 }
 
 #[tauri::command]
-async fn get_versions_with_status(state: tauri::State<'_, VersionState>) -> Result<Vec<Version>, String> {
+async fn get_versions_with_status(state: tauri::State<'_, VersionState>) -> Result<Vec<Version>, LauncherError> {
 
     let (stored_versions, builds) = tokio::join!(
         async {
@@ -460,10 +622,12 @@ async fn get_versions_with_status(state: tauri::State<'_, VersionState>) -> Resu
         let matching_build = builds_map.get(&version_number);
 
         Version {
+            display_name: v.metadata.display_name.clone().unwrap_or_else(|| v.version.clone()),
+            metadata: v.metadata,
             path: v.path,
             version: v.version,
             technical_version: v.technical_version,
-            splash_image: v.splash_image,
+            splash_image: v.splash_key.as_deref().map(thumbnail_url).unwrap_or_default(),
             access_type: matching_build
                 .map(|b| b.access_type.clone())
                 .unwrap_or_else(|| "unknown".to_string()),
@@ -478,34 +642,78 @@ async fn get_versions_with_status(state: tauri::State<'_, VersionState>) -> Resu
 
 use tauri::command;
 
-use tokio::process::Command as TokioCommand;
-use tokio::io::{self as tokio_io, AsyncBufReadExt, BufReader};
-
-use winapi::um::processthreadsapi::{CreateProcessW, ResumeThread, SuspendThread};
-use winapi::um::winbase::{CREATE_SUSPENDED};
-use winapi::um::handleapi::CloseHandle;
-use winapi::um::winnt::{PROCESS_ALL_ACCESS};
 use reqwest::Client;
-use sysinfo::{System};
 use tokio::time::{sleep, Duration};
 
 #[tauri::command]
-async fn version_card_clicked(path: String, email: String, password: String, version: String) -> Result<(), String> {
-    
-}
-
-fn str_to_wide(path: &str) -> Vec<u16> {
-    let mut wide_path: Vec<u16> = path.encode_utf16().collect();
-    wide_path.push(0);
-    wide_path
-}
+async fn version_card_clicked(
+    path: String,
+    version: String,
+    profile: Option<String>,
+    profiles: tauri::State<'_, ProfileState>,
+    network: tauri::State<'_, NetworkState>,
+    app_handle: tauri::AppHandle,
+) -> Result<LaunchReport, LauncherError> {
+    let launch = resolve_launch(&profiles.0.lock().unwrap(), profile.as_deref(), &path)?;
+
+    let mode = *network.0.lock().unwrap();
+    let offline = resolve_offline(mode).await;
+    let mut report = LaunchReport {
+        offline: offline.is_some(),
+        ..Default::default()
+    };
+    let offline_login = match &offline {
+        Some(reason) => {
+            report.skip("server_status", reason);
+            let stored = offline_token(&app_handle).ok_or_else(|| {
+                LauncherError::AuthExpired(
+                    "Playing offline needs a login that hasn't expired. Log in again once you're online.".to_string(),
+                )
+            })?;
+            report.skip("token_check", "reused the stored token");
+            Some(stored.token)
+        }
+        None => {
+            if !check_server_status().await?.is_server_ready {
+                return Err(LauncherError::NetworkDown("Servers are offline".to_string()));
+            }
+            None
+        }
+    };
 
-fn launch_and_suspend(path: &str) -> Result<u32, String> {
+    let dlls = resolve_payloads(&app_handle, Path::new(&path), &launch.dlls, offline.is_some()).await?;
+    if offline.is_some() && !dlls.is_empty() {
+        report.skip("payload_download", "used the verified cached payloads");
+    }
 
-}
+    // Asked for last so the code is as fresh as possible when the game uses it.
+    let code = match offline_login {
+        Some(token) => {
+            report.skip("exchange_code", "logged the game in with the stored token");
+            token
+        }
+        None => exchange_code(&app_handle).await?,
+    };
+    let args = merge_args(&[&base_args(), &auth_args(&code), &launch.args]);
 
-async fn download_dll(url: &str, path: &str) -> Result<(), String> {
-    
+    let account = active_account(&app_handle);
+    println!(
+        "Launching {} from {} with profile {:?} as {:?}",
+        version, path, launch.profile, account
+    );
+    let session = start_session(&app_handle, &path, &args, &launch.env, account.as_deref(), !dlls.is_empty())?;
+    if let Some(account) = &account {
+        touch_account(&app_handle, account);
+    }
+    if !dlls.is_empty() {
+        for dll in &dlls {
+            session.inject(&app_handle, dll)?;
+        }
+        session.run(&app_handle)?;
+    }
+    report.session_id = session.id().to_string();
+    report.account_id = account;
+    Ok(report)
 }
 
 #[derive(Debug, serde::Deserialize, serde::Serialize)]
@@ -515,7 +723,7 @@ struct ServerStatus {
 }
 
 #[tauri::command]
-async fn check_server_status() -> Result<ServerStatus, String> {
+async fn check_server_status() -> Result<ServerStatus, LauncherError> {
    
 }
 
@@ -534,14 +742,14 @@ fn get_stats_file_path() -> PathBuf {
 }
 
 #[tauri::command]
-async fn fetch_server_stats() -> Result<ServerStats, String> {
+async fn fetch_server_stats() -> Result<ServerStats, LauncherError> {
     let client = reqwest::Client::new();
     let url = "your_server_url";
     
     match client.get(url).send().await {
         Ok(response) => {
             if !response.status().is_success() {
-                return Err(format!("API returned error status: {}", response.status()));
+                return Err(LauncherError::api(response.status()));
             }
             
             match response.json::<ServerStats>().await {
@@ -553,10 +761,10 @@ async fn fetch_server_stats() -> Result<ServerStats, String> {
                     }
                     Ok(stats)
                 },
-                Err(e) => Err(format!("Failed to parse server stats: {}", e))
+                Err(e) => Err(e.into())
             }
         },
-        Err(e) => Err(format!("Could not fetch server stats: {}", e))
+        Err(e) => Err(e.into())
     }
 }
 
@@ -586,32 +794,33 @@ fn get_shop_cache_path() -> PathBuf {
 }
 
 #[tauri::command]
-async fn fetch_shop_items() -> Result<ShopData, String> {
+async fn fetch_shop_items() -> Result<ShopData, LauncherError> {
     let client = reqwest::Client::new();
     let catalog_url = "your_catalog_url";
     
     let response = match client.get(catalog_url).send().await {
         Ok(resp) => resp,
-        Err(e) => return Err(format!("Failed to fetch catalog: {}", e)),
+        Err(e) => return Err(e.into()),
     };
     
-    if !response.status().is_success() {
-        return Err(format!("API returned error status: {}", response.status()));
+    let status = response.status();
+    if !status.is_success() {
+        return Err(LauncherError::api(status));
     }
     
     let catalog_data: serde_json::Value = match response.json().await {
         Ok(data) => data,
-        Err(e) => return Err(format!("Failed to parse catalog data: {}", e)),
+        Err(e) => return Err(e.into()),
     };
     
     let storefronts_value = match catalog_data.get("storefronts") {
         Some(sf) => sf,
-        None => return Err("No storefronts found in response".to_string()),
+        None => return Err(LauncherError::Api { status: status.as_u16(), message: "No storefronts found in response".to_string() }),
     };
     
     let storefronts = match storefronts_value.as_array() {
         Some(arr) => arr,
-        None => return Err("Storefronts is not an array".to_string()),
+        None => return Err(LauncherError::Api { status: status.as_u16(), message: "Storefronts is not an array".to_string() }),
     };
     
     let mut organized_items = ShopData {
@@ -833,99 +1042,6 @@ async fn fetch_item_details(client: &reqwest::Client, id: &str) -> Result<(Strin
     Ok((rarity.to_string(), name.to_string()))
 }
 
-#[tauri::command]
-async fn stop_game_process() -> Result<(), String> {
-    let main_pid = CURRENT_GAME_PID.load(Ordering::SeqCst);
-    let launcher_pid = LAUNCHER_PID.load(Ordering::SeqCst);
-    let eac_pid = EAC_PID.load(Ordering::SeqCst);
-
-    if main_pid == 0 && launcher_pid == 0 && eac_pid == 0 {
-        return Err("No game processes running".to_string());
-    }
-
-    let mut system = System::new();
-    system.refresh_processes();
-
-    let mut killed_any = false;
-
-    if main_pid != 0 {
-        if let Some(process) = system.process(Pid::from_u32(main_pid)) {
-            if process.kill() {
-                println!("Successfully terminated main game process with PID: {}", main_pid);
-                killed_any = true;
-            }
-        }
-        CURRENT_GAME_PID.store(0, Ordering::SeqCst);
-    }
-
-    if launcher_pid != 0 {
-        if let Some(process) = system.process(Pid::from_u32(launcher_pid)) {
-            if process.kill() {
-                println!("Successfully terminated launcher process with PID: {}", launcher_pid);
-                killed_any = true;
-            }
-        }
-        LAUNCHER_PID.store(0, Ordering::SeqCst);
-    }
-
-    if eac_pid != 0 {
-        if let Some(process) = system.process(Pid::from_u32(eac_pid)) {
-            if process.kill() {
-                println!("Successfully terminated EAC process with PID: {}", eac_pid);
-                killed_any = true;
-            }
-        }
-        EAC_PID.store(0, Ordering::SeqCst);
-    }
-
-    if killed_any {
-        Ok(())
-    } else {
-        Err("Failed to terminate any game processes".to_string())
-    }
-}
-
-#[tauri::command]
-async fn is_game_running() -> Result<bool, String> {
-    let main_pid = CURRENT_GAME_PID.load(Ordering::SeqCst);
-    let launcher_pid = LAUNCHER_PID.load(Ordering::SeqCst);
-    let eac_pid = EAC_PID.load(Ordering::SeqCst);
-
-    if main_pid == 0 && launcher_pid == 0 && eac_pid == 0 {
-        return Ok(false);
-    }
-
-    let mut system = System::new();
-    system.refresh_processes();
-
-    let mut any_running = false;
-
-    if main_pid != 0 {
-        if system.process(Pid::from_u32(main_pid)).is_some() {
-            any_running = true;
-        } else {
-            CURRENT_GAME_PID.store(0, Ordering::SeqCst);
-        }
-    }
-
-    if launcher_pid != 0 {
-        if system.process(Pid::from_u32(launcher_pid)).is_some() {
-            any_running = true;
-        } else {
-            LAUNCHER_PID.store(0, Ordering::SeqCst);
-        }
-    }
-    if eac_pid != 0 {
-        if system.process(Pid::from_u32(eac_pid)).is_some() {
-            any_running = true;
-        } else {
-            EAC_PID.store(0, Ordering::SeqCst);
-        }
-    }
-
-    Ok(any_running)
-}
-
 #[derive(Debug, serde::Deserialize, serde::Serialize, Clone)]
 struct Session {
     started: bool,
@@ -940,22 +1056,22 @@ struct Session {
 }
 
 #[tauri::command]
-async fn fetch_sessions() -> Result<Vec<Session>, String> {
+async fn fetch_sessions() -> Result<Vec<Session>, LauncherError> {
     let client = reqwest::Client::new();
     let url = "ur_sessions_url";
     
     match client.get(url).send().await {
         Ok(response) => {
             if !response.status().is_success() {
-                return Err(format!("API returned error status: {}", response.status()));
+                return Err(LauncherError::api(response.status()));
             }
             
             match response.json::<Vec<Session>>().await {
                 Ok(sessions) => Ok(sessions),
-                Err(e) => Err(format!("Failed to parse sessions: {}", e))
+                Err(e) => Err(e.into())
             }
         },
-        Err(e) => Err(format!("Could not fetch sessions: {}", e))
+        Err(e) => Err(e.into())
     }
 }
 
@@ -969,11 +1085,31 @@ fn main() {
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_shell::init())
         .manage(versions_state)
+        .manage(VersionsRecoveryState(Mutex::new(None)))
+        .manage(DeletionState::default())
+        .manage(GameSessions::default())
         .manage(discord_rpc_state.clone())
+        .manage(Arc::new(DownloadManager::new()))
+        .register_uri_scheme_protocol(THUMBNAIL_SCHEME, |ctx, request| {
+            handle_thumbnail_request(ctx.app_handle(), request.uri().path())
+        })
         .setup(|app| {
-            let versions = load_versions(&app.handle());
+            app.manage(Arc::new(ThumbnailCache::load(&app.handle())));
+            app.manage(LauncherState::load(&app.handle()));
+            app.manage(ProfileState::load(&app.handle()));
+            app.manage(NetworkState::load(&app.handle()));
+            app.manage(TokenState::load(&app.handle()));
+            app.manage(AccountState::load(&app.handle()));
+            app.manage(TokenManager::load(&app.handle()));
+            tauri::async_runtime::spawn(adopt_unassigned_token(app.handle().clone()));
+            tauri::async_runtime::spawn(refresh_loop(app.handle().clone()));
+
+            let loaded = load_versions(&app.handle());
             let state = app.state::<VersionState>();
-            *state.0.lock().unwrap() = versions;
+            *state.0.lock().unwrap() = loaded.versions;
+            *app.state::<VersionsRecoveryState>().0.lock().unwrap() = loaded.recovery;
+            tauri::async_runtime::spawn(backfill_thumbnails(app.handle().clone()));
+            resume_pending_deletions(&app.handle());
             
             let window = app.get_webview_window("main").unwrap();
             let app_handle = app.handle().clone();
@@ -989,66 +1125,32 @@ fn main() {
             });
                 
             if let Err(err) = tauri_plugin_deep_link::register("Rewind", move |request| {
-                println!("Received request: {}", request);
-
-                let re = Regex::new(r"(?i)rewindlauncher://auth/?[?]launcherToken=(.+)").unwrap();
+                let re = Regex::new(r"(?i)rewindlauncher://auth/?[?]launcherToken=([^&]+)(?:&refreshToken=([^&]+))?").unwrap();
                 
                 if let Some(captures) = re.captures(request.as_str()) {
                     if let Some(result) = captures.get(1) {
-                        let token = urlencoding::decode(result.as_str())
-                            .unwrap_or_else(|_| result.as_str().to_string().into())
-                            .to_string();
-                        println!("Received launcher token: {}", token);
-                        
-
-                        if let Err(e) = store_token(&token, &app_handle) {
-                            println!("could not store token: {}", e);
-                        }
-                        
-                        let window_clone = window.clone();
-                        
+                        let decode = |value: &str| {
+                            urlencoding::decode(value)
+                                .unwrap_or_else(|_| value.to_string().into())
+                                .to_string()
+                        };
+                        let token = decode(result.as_str());
+                        let refresh_token = captures.get(2).map(|value| decode(value.as_str()));
+                        println!("Received a login from the browser");
+
+                        let stored = StoredToken {
+                            token,
+                            stored_at: offline::unix_now(),
+                            refresh_token,
+                            expires_at: None,
+                        };
+                        let app_handle = app_handle.clone();
                         tauri::async_runtime::spawn(async move {
-                            window_clone.show().unwrap();
-                            window_clone.set_focus().unwrap();
-
-                            match decode_launcher_token(&token).await {
-                                Ok(user_info) => {
-                                    println!("usah info:");
-                                    println!("Username: {}", user_info.username);
-                                    println!("Email: {}", user_info.email);
-                                    println!("Password: {}", user_info.password);
-                                    println!("Account ID: {}", user_info.account_id);
-                                    println!("Favorite skin: {}", user_info.favorite_skin);
-                                    println!("Role name: {}", user_info.role.name);
-                                    println!("Role color: {}", user_info.role.color);
-
-                                    let payload = serde_json::json!({
-                                        "username": user_info.username,
-                                        "accountId": user_info.account_id,
-                                        "email": user_info.email,
-                                        "password": user_info.password,
-                                        "avatar_url": user_info.avatar_url,
-                                        "favoriteSkin": user_info.favorite_skin,
-                                        "mtxCurrency": user_info.mtx_currency,
-                                        "hype": user_info.hype,
-                                        "role": {
-                                            "name": user_info.role.name,
-                                            "color": user_info.role.color
-                                        }
-                                    });
-                                    
-                                    window_clone.emit("login-success", payload).unwrap();
-                                },
-                                Err(e) => {
-                                    println!("error decoding token: {}", e);
-                                    window_clone.emit("login-error", "Token Authorization failed.").unwrap();
-                                }
-                            }
+                            complete_login(&app_handle, stored).await;
                         });
                     }
                 } else {
-                    println!("No matching regex found in request: {}", request);
-                    println!("Expected format: rewindlauncher://auth?launcherToken=<token>");
+                    println!("Ignoring a deep link that isn't a login");
                 }
             }) {
                 println!("Failed to register deep link handler: {}", err);
@@ -1060,14 +1162,36 @@ fn main() {
             fetch_events,
             check_stored_token,
             clear_stored_token,
+            start_loopback_login,
+            get_auth_state,
+            list_accounts,
+            switch_account,
+            remove_account,
             check_version,
             detect_fortnite_version,
             add_version,
+            scan_library,
+            import_archive,
             get_versions,
+            get_versions_recovery,
+            query_versions,
+            update_version_metadata,
+            set_thumbnail_options,
             remove_version,
+            plan_remove_version,
+            undo_delete_version,
+            verify_version,
+            create_manifest,
+            start_download,
+            pause_download,
+            resume_download,
+            cancel_download,
+            set_download_limit,
             fetch_builds,
             get_versions_with_status,
             version_card_clicked,
+            get_launcher_config,
+            set_launcher_config,
             check_server_status,
             fetch_server_stats,
             download_and_install_update,
@@ -1075,6 +1199,20 @@ fn main() {
             fetch_sessions,
             stop_game_process,
             is_game_running,
+            get_game_sessions,
+            get_session_log,
+            export_crash_report,
+            get_launch_profiles,
+            save_launch_profile,
+            delete_launch_profile,
+            set_default_launch_profile,
+            set_build_launch_override,
+            preview_launch,
+            get_payload_manifest,
+            set_payload_manifest,
+            get_network_status,
+            set_network_mode,
+            preflight,
             discord_rpc_init,
             discord_rpc_set_activity,
             discord_rpc_clear_activity,
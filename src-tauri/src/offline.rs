@@ -0,0 +1,205 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager, State};
+
+use crate::error::LauncherError;
+use crate::persist::write_atomic;
+use crate::token_manager::{active_token, token_expiry};
+use crate::{check_server_status, ServerStatus, StoredToken};
+
+const MODE_FILE: &str = "network.json";
+// A backend that takes longer than this to answer counts as down.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+// Tokens that don't say when they expire are trusted offline for this long
+// after they were stored.
+const OFFLINE_TOKEN_MAX_AGE: u64 = 7 * 24 * 60 * 60;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NetworkMode {
+    /// Go offline only when the backend can't be reached.
+    #[default]
+    Auto,
+    Online,
+    Offline,
+}
+
+pub struct NetworkState(pub Mutex<NetworkMode>);
+
+impl NetworkState {
+    pub fn load(app_handle: &AppHandle) -> Self {
+        let mode = fs::read_to_string(mode_path(app_handle))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        NetworkState(Mutex::new(mode))
+    }
+}
+
+fn mode_path(app_handle: &AppHandle) -> PathBuf {
+    app_handle.path().app_data_dir().unwrap().join(MODE_FILE)
+}
+
+/// A launch step that offline mode left out, and why.
+#[derive(Debug, Clone, Serialize)]
+pub struct SkippedStep {
+    pub step: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LaunchReport {
+    pub session_id: String,
+    /// The account the game was launched as.
+    pub account_id: Option<String>,
+    pub offline: bool,
+    pub skipped: Vec<SkippedStep>,
+}
+
+impl LaunchReport {
+    pub fn skip(&mut self, step: &str, reason: &str) {
+        println!("Skipping {}: {}", step, reason);
+        self.skipped.push(SkippedStep {
+            step: step.to_string(),
+            reason: reason.to_string(),
+        });
+    }
+}
+
+/// The server status, or `None` when the endpoint doesn't answer at all.
+/// Whether it says the servers are ready is the online gate's business.
+pub async fn probe_backend() -> Option<ServerStatus> {
+    tokio::time::timeout(PROBE_TIMEOUT, check_server_status()).await.ok()?.ok()
+}
+
+/// Why a launch in `mode` would run offline, given whether the backend
+/// answered, or `None` if it would run online.
+pub fn offline_reason(mode: NetworkMode, reachable: bool) -> Option<String> {
+    match mode {
+        NetworkMode::Online => None,
+        NetworkMode::Offline => Some("offline mode is on".to_string()),
+        NetworkMode::Auto => (!reachable).then(|| "the backend can't be reached".to_string()),
+    }
+}
+
+/// Decides whether this launch is offline, and the reason to report if so.
+pub async fn resolve_offline(mode: NetworkMode) -> Option<String> {
+    let reachable = mode == NetworkMode::Auto && probe_backend().await.is_some();
+    offline_reason(mode, reachable)
+}
+
+/// Whether `stored` can still be trusted at `now` without asking the backend.
+pub fn token_usable_offline(stored: &StoredToken, now: u64) -> bool {
+    let expires_at = token_expiry(stored).unwrap_or(stored.stored_at + OFFLINE_TOKEN_MAX_AGE);
+    now < expires_at
+}
+
+pub fn stored_token(app_handle: &AppHandle) -> Option<StoredToken> {
+    active_token(app_handle).map(|(_, stored)| stored)
+}
+
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// The stored token, if there is one that is still valid offline.
+pub fn offline_token(app_handle: &AppHandle) -> Option<StoredToken> {
+    stored_token(app_handle).filter(|stored| token_usable_offline(stored, unix_now()))
+}
+
+#[derive(Debug, Serialize)]
+pub struct NetworkStatus {
+    pub mode: NetworkMode,
+    /// Why launches would run offline right now, if they would.
+    pub offline_reason: Option<String>,
+}
+
+#[tauri::command]
+pub async fn get_network_status(state: State<'_, NetworkState>) -> Result<NetworkStatus, LauncherError> {
+    let mode = *state.0.lock().unwrap();
+    Ok(NetworkStatus {
+        mode,
+        offline_reason: resolve_offline(mode).await,
+    })
+}
+
+#[tauri::command]
+pub async fn set_network_mode(
+    mode: NetworkMode,
+    state: State<'_, NetworkState>,
+    app_handle: AppHandle,
+) -> Result<(), LauncherError> {
+    let content = serde_json::to_string(&mode)?;
+    write_atomic(&mode_path(&app_handle), content).map_err(|e| LauncherError::io("Failed to save network mode", e))?;
+    *state.0.lock().unwrap() = mode;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+    use base64::Engine;
+
+    fn stored(token: &str, stored_at: u64, expires_at: Option<u64>) -> StoredToken {
+        StoredToken {
+            token: token.to_string(),
+            stored_at,
+            refresh_token: None,
+            expires_at,
+        }
+    }
+
+    fn jwt(exp: u64) -> String {
+        format!("e30.{}.sig", URL_SAFE_NO_PAD.encode(format!("{{\"exp\":{}}}", exp)))
+    }
+
+    #[test]
+    fn goes_offline_only_when_asked_or_unreachable() {
+        assert_eq!(offline_reason(NetworkMode::Online, false), None);
+        assert_eq!(offline_reason(NetworkMode::Auto, true), None);
+        assert_eq!(
+            offline_reason(NetworkMode::Auto, false).as_deref(),
+            Some("the backend can't be reached")
+        );
+        assert_eq!(offline_reason(NetworkMode::Offline, true).as_deref(), Some("offline mode is on"));
+    }
+
+    #[test]
+    fn trusts_tokens_until_they_expire() {
+        let now = 1_000_000;
+        assert!(token_usable_offline(&stored("opaque", now - 60, Some(now + 60)), now));
+        assert!(!token_usable_offline(&stored("opaque", now - 60, Some(now)), now));
+        assert!(token_usable_offline(&stored(&jwt(now + 60), now - 60, None), now));
+        assert!(!token_usable_offline(&stored(&jwt(now - 1), now - 60, None), now));
+    }
+
+    #[test]
+    fn trusts_tokens_without_an_expiry_for_a_week() {
+        let now = 1_000_000;
+        assert!(token_usable_offline(&stored("opaque", now - OFFLINE_TOKEN_MAX_AGE + 1, None), now));
+        assert!(!token_usable_offline(&stored("opaque", now - OFFLINE_TOKEN_MAX_AGE, None), now));
+    }
+
+    #[test]
+    fn reports_skipped_steps_in_order() {
+        let mut report = LaunchReport {
+            offline: true,
+            ..Default::default()
+        };
+        report.skip("server_status", "offline mode is on");
+        report.skip("exchange_code", "logged the game in with the stored token");
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["offline"], true);
+        assert_eq!(json["skipped"][0]["step"], "server_status");
+        assert_eq!(json["skipped"][0]["reason"], "offline mode is on");
+        assert_eq!(json["skipped"][1]["step"], "exchange_code");
+    }
+}
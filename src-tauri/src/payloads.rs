@@ -0,0 +1,469 @@
+use ed25519_dalek::{Signature, VerifyingKey};
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+use crate::error::LauncherError;
+use crate::launch_profiles::dll_path;
+use crate::persist::write_atomic;
+
+const PAYLOADS_DIR: &str = "payloads";
+const MANIFEST_FILE: &str = "manifest.json";
+// No payload we ship comes close; anything bigger is not what we asked for.
+const MAX_PAYLOAD_SIZE: u64 = 64 * 1024 * 1024;
+/// Hex ed25519 keys payloads are signed with. They are built in rather than
+/// read from the manifest so that whoever can write the manifest can't also
+/// vouch for what it points at.
+const PAYLOAD_PUBLIC_KEYS: &[&str] = &["your_payload_public_key"];
+
+/// One DLL the launcher can inject.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PayloadEntry {
+    pub name: String,
+    pub url: String,
+    /// Hex SHA-256 of the DLL. Also its file name in the cache.
+    pub sha256: String,
+    /// Hex ed25519 signature over the DLL's bytes. Required for anything
+    /// downloaded.
+    #[serde(default)]
+    pub signature: Option<String>,
+}
+
+/// The payloads profiles can refer to by name. A payload only runs if it
+/// matches its pinned hash and, when signed, verifies against one of the
+/// built-in keys. Downloaded payloads must be signed.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PayloadManifest {
+    pub payloads: Vec<PayloadEntry>,
+}
+
+impl PayloadManifest {
+    pub fn get(&self, name: &str) -> Option<&PayloadEntry> {
+        self.payloads.iter().find(|entry| entry.name == name)
+    }
+
+    /// Rejects entries that could never verify or whose hash isn't a safe
+    /// file name, so mistakes show up when the manifest is saved.
+    pub fn validate(&self) -> Result<(), LauncherError> {
+        for entry in &self.payloads {
+            if entry.sha256.len() != 64 || !entry.sha256.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(LauncherError::InvalidInput(format!(
+                    "{} has an invalid SHA-256: {}",
+                    entry.name, entry.sha256
+                )));
+            }
+            if let Some(signature) = &entry.signature {
+                parse_signature(signature).map_err(|e| LauncherError::InvalidInput(format!("{}: {}", entry.name, e)))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The built-in payload keys.
+pub fn trusted_keys() -> Vec<VerifyingKey> {
+    PAYLOAD_PUBLIC_KEYS.iter().filter_map(|key| parse_key(key).ok()).collect()
+}
+
+fn parse_key(key: &str) -> Result<VerifyingKey, String> {
+    let bytes: [u8; 32] = hex::decode(key.trim())
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| format!("Invalid public key: {}", key))?;
+    VerifyingKey::from_bytes(&bytes).map_err(|_| format!("Invalid public key: {}", key))
+}
+
+fn parse_signature(signature: &str) -> Result<Signature, String> {
+    let bytes: [u8; 64] = hex::decode(signature.trim())
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or("Invalid signature")?;
+    Ok(Signature::from_bytes(&bytes))
+}
+
+/// Checks `data` against the entry's pinned hash and, if it has one, its
+/// signature by one of `keys`.
+pub fn verify_payload(keys: &[VerifyingKey], entry: &PayloadEntry, data: &[u8]) -> Result<(), LauncherError> {
+    let actual = hex::encode(Sha256::digest(data));
+    if !actual.eq_ignore_ascii_case(&entry.sha256) {
+        return Err(LauncherError::CorruptBuild(format!(
+            "{} failed verification: expected SHA-256 {}, got {}",
+            entry.name, entry.sha256, actual
+        )));
+    }
+
+    if let Some(signature) = &entry.signature {
+        let signature =
+            parse_signature(signature).map_err(|e| LauncherError::CorruptBuild(format!("{}: {}", entry.name, e)))?;
+        let verified = keys.iter().any(|key| key.verify_strict(data, &signature).is_ok());
+        if !verified {
+            return Err(LauncherError::CorruptBuild(format!(
+                "{} failed verification: bad signature",
+                entry.name
+            )));
+        }
+    }
+    Ok(())
+}
+
+pub fn payloads_dir(app_handle: &AppHandle) -> PathBuf {
+    app_handle.path().app_data_dir().unwrap().join(PAYLOADS_DIR)
+}
+
+pub fn load_manifest(dir: &Path) -> Result<PayloadManifest, LauncherError> {
+    match fs::read_to_string(dir.join(MANIFEST_FILE)) {
+        Ok(content) => {
+            let manifest: PayloadManifest = serde_json::from_str(&content)?;
+            manifest.validate()?;
+            Ok(manifest)
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(PayloadManifest::default()),
+        Err(e) => Err(LauncherError::io("Failed to read payload manifest", e)),
+    }
+}
+
+pub fn cached_path(dir: &Path, entry: &PayloadEntry) -> PathBuf {
+    dir.join(format!("{}.dll", entry.sha256.to_ascii_lowercase()))
+}
+
+/// `Ok(Some(path))` when the cached copy verifies, `Ok(None)` when there is
+/// none. Nothing on disk is changed, whatever the outcome.
+pub fn inspect_cached(dir: &Path, keys: &[VerifyingKey], entry: &PayloadEntry) -> Result<Option<PathBuf>, LauncherError> {
+    let path = cached_path(dir, entry);
+    let data = match fs::read(&path) {
+        Ok(data) => data,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(LauncherError::io(format!("Failed to read cached {}", entry.name), e)),
+    };
+    verify_payload(keys, entry, &data)?;
+    Ok(Some(path))
+}
+
+/// Like `inspect_cached`, but a cached copy that no longer verifies is
+/// deleted and reported.
+pub fn check_cached(dir: &Path, keys: &[VerifyingKey], entry: &PayloadEntry) -> Result<Option<PathBuf>, LauncherError> {
+    let result = inspect_cached(dir, keys, entry);
+    if let Err(LauncherError::CorruptBuild(_)) = result {
+        let _ = fs::remove_file(cached_path(dir, entry));
+    }
+    result
+}
+
+/// Downloads the payload and only puts it in the cache once its hash and
+/// signature verify. Unsigned entries aren't downloaded at all.
+pub async fn download_payload(
+    client: &reqwest::Client,
+    dir: &Path,
+    keys: &[VerifyingKey],
+    entry: &PayloadEntry,
+) -> Result<PathBuf, LauncherError> {
+    if entry.signature.is_none() {
+        return Err(LauncherError::CorruptBuild(format!(
+            "{} isn't signed, so it won't be downloaded",
+            entry.name
+        )));
+    }
+    let response = client.get(&entry.url).send().await?.error_for_status()?;
+    if response.content_length().is_some_and(|length| length > MAX_PAYLOAD_SIZE) {
+        return Err(LauncherError::CorruptBuild(format!(
+            "{} is larger than any payload should be",
+            entry.name
+        )));
+    }
+
+    let mut data = Vec::new();
+    let mut body = response.bytes_stream();
+    while let Some(piece) = body.next().await {
+        data.extend_from_slice(&piece?);
+        if data.len() as u64 > MAX_PAYLOAD_SIZE {
+            return Err(LauncherError::CorruptBuild(format!(
+                "{} is larger than any payload should be",
+                entry.name
+            )));
+        }
+    }
+    verify_payload(keys, entry, &data)?;
+    store_cached(dir, entry, &data)
+}
+
+/// Puts verified `data` in the cache under the entry's hash.
+fn store_cached(dir: &Path, entry: &PayloadEntry, data: &[u8]) -> Result<PathBuf, LauncherError> {
+    fs::create_dir_all(dir)?;
+    let path = cached_path(dir, entry);
+    let temp_path = path.with_extension("part");
+    fs::write(&temp_path, data).map_err(|e| LauncherError::io(format!("Failed to cache {}", entry.name), e))?;
+    fs::rename(&temp_path, &path).map_err(|e| LauncherError::io(format!("Failed to cache {}", entry.name), e))?;
+    println!("Cached payload {} as {}", entry.name, path.display());
+    Ok(path)
+}
+
+/// A DLL the user pointed at directly. It is only used if the manifest pins
+/// its SHA-256, and what gets injected is the verified copy in the cache, so
+/// the file can't be swapped out between the check and the injection.
+pub fn local_payload(
+    dir: &Path,
+    manifest: &PayloadManifest,
+    keys: &[VerifyingKey],
+    path: &Path,
+) -> Result<PathBuf, LauncherError> {
+    let (entry, data) = verify_local(manifest, keys, path)?;
+    store_cached(dir, entry, &data)
+}
+
+/// Reads the DLL at `path` and checks it against the entry that pins it,
+/// without caching it.
+pub fn verify_local<'a>(
+    manifest: &'a PayloadManifest,
+    keys: &[VerifyingKey],
+    path: &Path,
+) -> Result<(&'a PayloadEntry, Vec<u8>), LauncherError> {
+    let data = match fs::read(path) {
+        Ok(data) => data,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Err(LauncherError::NotFound(format!("{} doesn't exist", path.display())))
+        }
+        Err(e) => return Err(LauncherError::io(format!("Failed to read {}", path.display()), e)),
+    };
+    let sha256 = hex::encode(Sha256::digest(&data));
+    let entry = manifest
+        .payloads
+        .iter()
+        .find(|entry| entry.sha256.eq_ignore_ascii_case(&sha256))
+        .ok_or_else(|| {
+            LauncherError::InvalidInput(format!(
+                "{} isn't in the payload manifest. Add it with SHA-256 {} to use it.",
+                path.display(),
+                sha256
+            ))
+        })?;
+    verify_payload(keys, entry, &data)?;
+    Ok((entry, data))
+}
+
+/// Verified paths for a profile's DLLs, in order. Manifest names come from
+/// the cache, downloaded first if needed unless `offline`; anything else is a
+/// local file the user pointed at, which the manifest has to pin.
+pub async fn resolve_payloads(
+    app_handle: &AppHandle,
+    build: &Path,
+    dlls: &[String],
+    offline: bool,
+) -> Result<Vec<PathBuf>, LauncherError> {
+    let dir = payloads_dir(app_handle);
+    let manifest = load_manifest(&dir)?;
+    let keys = trusted_keys();
+    let client = reqwest::Client::new();
+
+    let mut paths = Vec::new();
+    for dll in dlls {
+        let Some(entry) = manifest.get(dll) else {
+            paths.push(local_payload(&dir, &manifest, &keys, &dll_path(build, dll))?);
+            continue;
+        };
+
+        let cached = match check_cached(&dir, &keys, entry) {
+            Ok(cached) => cached,
+            Err(e) => {
+                println!("Discarded cached payload: {}", e);
+                None
+            }
+        };
+        let path = match cached {
+            Some(path) => path,
+            None if offline => {
+                return Err(LauncherError::NetworkDown(format!(
+                    "{} isn't cached yet and can't be downloaded offline",
+                    entry.name
+                )))
+            }
+            None => download_payload(&client, &dir, &keys, entry).await?,
+        };
+        paths.push(path);
+    }
+    Ok(paths)
+}
+
+#[tauri::command]
+pub async fn get_payload_manifest(app_handle: AppHandle) -> Result<PayloadManifest, LauncherError> {
+    load_manifest(&payloads_dir(&app_handle))
+}
+
+#[tauri::command]
+pub async fn set_payload_manifest(manifest: PayloadManifest, app_handle: AppHandle) -> Result<(), LauncherError> {
+    manifest.validate()?;
+    let content = serde_json::to_string_pretty(&manifest)?;
+    write_atomic(&payloads_dir(&app_handle).join(MANIFEST_FILE), content).map_err(|e| LauncherError::io("Failed to save payload manifest", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fs::TempDir;
+    use crate::test_http::{Response, TestServer};
+    use ed25519_dalek::{Signer, SigningKey};
+
+    const DLL: &[u8] = b"MZ not really a dll";
+
+    fn signing_key() -> SigningKey {
+        SigningKey::from_bytes(&[7; 32])
+    }
+
+    fn keys() -> Vec<VerifyingKey> {
+        vec![signing_key().verifying_key()]
+    }
+
+    fn signed_manifest(data: &[u8]) -> PayloadManifest {
+        PayloadManifest {
+            payloads: vec![PayloadEntry {
+                name: "client".to_string(),
+                url: "http://127.0.0.1/client.dll".to_string(),
+                sha256: hex::encode(Sha256::digest(data)),
+                signature: Some(hex::encode(signing_key().sign(data).to_bytes())),
+            }],
+        }
+    }
+
+    #[test]
+    fn verifies_hash_and_signature() {
+        let manifest = signed_manifest(DLL);
+        let entry = &manifest.payloads[0];
+        assert!(verify_payload(&keys(), entry, DLL).is_ok());
+        assert!(matches!(
+            verify_payload(&keys(), entry, b"something else"),
+            Err(LauncherError::CorruptBuild(_))
+        ));
+
+        let mut forged = manifest.clone();
+        forged.payloads[0].signature = Some(hex::encode([0u8; 64]));
+        assert!(matches!(
+            verify_payload(&keys(), &forged.payloads[0], DLL),
+            Err(LauncherError::CorruptBuild(_))
+        ));
+
+        // Signed, but by a key that isn't built in.
+        let stranger = vec![SigningKey::from_bytes(&[8; 32]).verifying_key()];
+        assert!(matches!(
+            verify_payload(&stranger, entry, DLL),
+            Err(LauncherError::CorruptBuild(_))
+        ));
+    }
+
+    #[test]
+    fn refuses_a_manifest_that_does_not_validate() {
+        let dir = TempDir::new("payloads", "manifest");
+        assert!(load_manifest(&dir).unwrap().payloads.is_empty());
+
+        let mut manifest = signed_manifest(DLL);
+        manifest.payloads[0].sha256 = "../../evil".to_string();
+        fs::write(dir.join(MANIFEST_FILE), serde_json::to_string(&manifest).unwrap()).unwrap();
+        assert!(matches!(load_manifest(&dir), Err(LauncherError::InvalidInput(_))));
+
+        manifest.payloads[0].sha256 = hex::encode(Sha256::digest(DLL));
+        manifest.payloads[0].signature = Some("not hex".to_string());
+        fs::write(dir.join(MANIFEST_FILE), serde_json::to_string(&manifest).unwrap()).unwrap();
+        assert!(matches!(load_manifest(&dir), Err(LauncherError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn ignores_keys_in_the_manifest() {
+        let dir = TempDir::new("payloads", "keys");
+        let stranger = SigningKey::from_bytes(&[8; 32]);
+        let mut manifest = serde_json::to_value(signed_manifest(DLL)).unwrap();
+        manifest["public_keys"] = serde_json::json!([hex::encode(stranger.verifying_key().to_bytes())]);
+        manifest["payloads"][0]["signature"] = hex::encode(stranger.sign(DLL).to_bytes()).into();
+        fs::write(dir.join(MANIFEST_FILE), manifest.to_string()).unwrap();
+
+        let manifest = load_manifest(&dir).unwrap();
+        assert!(verify_payload(&trusted_keys(), &manifest.payloads[0], DLL).is_err());
+    }
+
+    #[test]
+    fn local_dlls_must_be_pinned() {
+        let dir = TempDir::new("payloads", "local");
+        let cache = dir.join(PAYLOADS_DIR);
+        let pinned = dir.join("pinned.dll");
+        let unpinned = dir.join("unpinned.dll");
+        fs::write(&pinned, DLL).unwrap();
+        fs::write(&unpinned, b"MZ something else").unwrap();
+        let manifest = signed_manifest(DLL);
+
+        let path = local_payload(&cache, &manifest, &keys(), &pinned).unwrap();
+        assert_eq!(path, cached_path(&cache, &manifest.payloads[0]));
+        assert_eq!(fs::read(&path).unwrap(), DLL);
+
+        match local_payload(&cache, &manifest, &keys(), &unpinned) {
+            Err(LauncherError::InvalidInput(message)) => {
+                assert!(message.contains(&hex::encode(Sha256::digest(b"MZ something else"))))
+            }
+            other => panic!("unpinned DLL was accepted: {:?}", other),
+        }
+        assert!(matches!(
+            local_payload(&cache, &manifest, &keys(), &dir.join("missing.dll")),
+            Err(LauncherError::NotFound(_))
+        ));
+
+        // Pinned by hash but with a signature that doesn't match.
+        let mut forged = manifest.clone();
+        forged.payloads[0].signature = Some(hex::encode([0u8; 64]));
+        assert!(matches!(
+            local_payload(&cache, &forged, &keys(), &pinned),
+            Err(LauncherError::CorruptBuild(_))
+        ));
+    }
+
+    /// Serves `body` for the client payload and downloads it into `cache`.
+    async fn download(cache: &Path, entry: &PayloadEntry, response: fn() -> Response) -> Result<PathBuf, LauncherError> {
+        let server = TestServer::start(move |_| response()).await;
+        let entry = PayloadEntry {
+            url: format!("{}/client.dll", server.url),
+            ..entry.clone()
+        };
+        download_payload(&reqwest::Client::new(), cache, &keys(), &entry).await
+    }
+
+    fn cache_is_empty(cache: &Path) -> bool {
+        fs::read_dir(cache).map_or(true, |mut entries| entries.next().is_none())
+    }
+
+    #[tokio::test]
+    async fn caches_a_download_that_verifies() {
+        let dir = TempDir::new("payloads", "download");
+        let entry = signed_manifest(DLL).payloads[0].clone();
+
+        let path = download(&dir, &entry, || Response::new(200, DLL)).await.unwrap();
+        assert_eq!(path, cached_path(&dir, &entry));
+        assert_eq!(fs::read(&path).unwrap(), DLL);
+    }
+
+    #[tokio::test]
+    async fn caches_nothing_from_a_bad_download() {
+        let dir = TempDir::new("payloads", "bad-download");
+        let entry = signed_manifest(DLL).payloads[0].clone();
+
+        let wrong_hash = download(&dir, &entry, || Response::new(200, "MZ something else")).await;
+        assert!(matches!(wrong_hash, Err(LauncherError::CorruptBuild(_))));
+        assert!(cache_is_empty(&dir));
+
+        let truncated = download(&dir, &entry, || Response::truncated(200, &DLL[..4], DLL.len())).await;
+        assert!(truncated.is_err());
+        assert!(cache_is_empty(&dir));
+
+        let oversize = download(&dir, &entry, || Response::new(200, vec![0u8; MAX_PAYLOAD_SIZE as usize + 1])).await;
+        assert!(matches!(oversize, Err(LauncherError::CorruptBuild(_))));
+        assert!(cache_is_empty(&dir));
+
+        let unsigned = PayloadEntry {
+            signature: None,
+            ..entry
+        };
+        assert!(matches!(
+            download(&dir, &unsigned, || Response::new(200, DLL)).await,
+            Err(LauncherError::CorruptBuild(_))
+        ));
+        assert!(cache_is_empty(&dir));
+    }
+}
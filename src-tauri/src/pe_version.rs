@@ -0,0 +1,491 @@
+use std::io::{self, Read, Seek, SeekFrom};
+
+const RT_VERSION: u32 = 16;
+const RESOURCE_DIRECTORY_INDEX: usize = 2;
+const VS_FIXEDFILEINFO_SIGNATURE: u32 = 0xFEEF_04BD;
+// Guards against garbage headers asking for absurd allocations.
+const MAX_SECTIONS: usize = 96;
+const MAX_RESOURCE_ENTRIES: usize = 4096;
+const MAX_VERSION_RESOURCE_SIZE: u32 = 64 * 1024;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PeVersionInfo {
+    pub product_version: Option<String>,
+    pub file_version: Option<String>,
+    pub link_timestamp: u32,
+}
+
+struct Section {
+    virtual_address: u32,
+    virtual_size: u32,
+    raw_pointer: u32,
+    raw_size: u32,
+}
+
+/// Reads the linker timestamp from the COFF header and the ProductVersion and
+/// FileVersion from the `VS_VERSIONINFO` resource, if the image has one. The
+/// string table values are preferred; the numeric `VS_FIXEDFILEINFO` versions
+/// are used when a string is absent.
+pub fn read_pe_version<R: Read + Seek>(reader: &mut R) -> io::Result<PeVersionInfo> {
+    let dos_header = read_at(reader, 0, 64)?;
+    if &dos_header[0..2] != b"MZ" {
+        return Err(invalid("Missing MZ signature"));
+    }
+    let nt_offset = u64::from(le32(&dos_header, 0x3C)?);
+
+    let file_header = read_at(reader, nt_offset, 24)?;
+    if &file_header[0..4] != b"PE\0\0" {
+        return Err(invalid("Missing PE signature"));
+    }
+    let section_count = le16(&file_header, 6)? as usize;
+    let link_timestamp = le32(&file_header, 8)?;
+    let optional_size = le16(&file_header, 20)? as usize;
+
+    let mut info = PeVersionInfo {
+        link_timestamp,
+        ..Default::default()
+    };
+
+    let optional_header = read_at(reader, nt_offset + 24, optional_size)?;
+    let (count_offset, directories_offset) = match le16(&optional_header, 0)? {
+        0x10B => (92, 96),
+        0x20B => (108, 112),
+        magic => return Err(invalid(&format!("Unknown optional header magic {:#x}", magic))),
+    };
+
+    if (le32(&optional_header, count_offset)? as usize) <= RESOURCE_DIRECTORY_INDEX {
+        return Ok(info);
+    }
+    let resource_rva = le32(&optional_header, directories_offset + RESOURCE_DIRECTORY_INDEX * 8)?;
+    if resource_rva == 0 {
+        return Ok(info);
+    }
+
+    if section_count > MAX_SECTIONS {
+        return Err(invalid("Too many sections"));
+    }
+    let section_table = read_at(
+        reader,
+        nt_offset + 24 + optional_size as u64,
+        section_count * 40,
+    )?;
+    let sections = section_table
+        .chunks_exact(40)
+        .map(|raw| {
+            Ok(Section {
+                virtual_size: le32(raw, 8)?,
+                virtual_address: le32(raw, 12)?,
+                raw_size: le32(raw, 16)?,
+                raw_pointer: le32(raw, 20)?,
+            })
+        })
+        .collect::<io::Result<Vec<_>>>()?;
+
+    let resource_base = rva_to_offset(&sections, resource_rva)
+        .ok_or_else(|| invalid("Resource directory is outside every section"))?;
+
+    let Some(type_dir) = find_resource_entry(reader, resource_base, 0, Some(RT_VERSION))? else {
+        return Ok(info);
+    };
+    let Some(name_dir) = find_resource_entry(reader, resource_base, subdirectory(type_dir)?, None)? else {
+        return Ok(info);
+    };
+    let Some(data_entry) = find_resource_entry(reader, resource_base, subdirectory(name_dir)?, None)? else {
+        return Ok(info);
+    };
+    if data_entry & 0x8000_0000 != 0 {
+        return Err(invalid("Version resource language entry is a directory"));
+    }
+
+    let entry = read_at(reader, resource_base + u64::from(data_entry), 16)?;
+    let data_rva = le32(&entry, 0)?;
+    let data_size = le32(&entry, 4)?.min(MAX_VERSION_RESOURCE_SIZE);
+    let data_offset = rva_to_offset(&sections, data_rva)
+        .ok_or_else(|| invalid("Version resource is outside every section"))?;
+    let blob = read_at(reader, data_offset, data_size as usize)?;
+
+    let (product_version, file_version) = parse_version_info(&blob)?;
+    info.product_version = product_version;
+    info.file_version = file_version;
+    Ok(info)
+}
+
+/// Walks a `VS_VERSIONINFO` blob and returns (ProductVersion, FileVersion).
+pub fn parse_version_info(blob: &[u8]) -> io::Result<(Option<String>, Option<String>)> {
+    let root = read_block(blob, 0, blob.len()).ok_or_else(|| invalid("Truncated VS_VERSIONINFO"))?;
+    if root.key != "VS_VERSION_INFO" {
+        return Err(invalid("Unexpected version resource key"));
+    }
+
+    let mut product_version = None;
+    let mut file_version = None;
+
+    for string_file_info in children(blob, &root) {
+        if string_file_info.key != "StringFileInfo" {
+            continue;
+        }
+        for table in children(blob, &string_file_info) {
+            for entry in children(blob, &table) {
+                let value = decode_utf16(entry.value);
+                if value.is_empty() {
+                    continue;
+                }
+                match entry.key.as_str() {
+                    "ProductVersion" if product_version.is_none() => product_version = Some(value),
+                    "FileVersion" if file_version.is_none() => file_version = Some(value),
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    if root.value.len() >= 24 && le32(root.value, 0)? == VS_FIXEDFILEINFO_SIGNATURE {
+        if file_version.is_none() {
+            file_version = Some(fixed_version(le32(root.value, 8)?, le32(root.value, 12)?));
+        }
+        if product_version.is_none() {
+            product_version = Some(fixed_version(le32(root.value, 16)?, le32(root.value, 20)?));
+        }
+    }
+
+    Ok((product_version, file_version))
+}
+
+struct Block<'a> {
+    key: String,
+    value: &'a [u8],
+    children_start: usize,
+    end: usize,
+}
+
+fn read_block(blob: &[u8], start: usize, limit: usize) -> Option<Block<'_>> {
+    let length = le16(blob, start).ok()? as usize;
+    let value_length = le16(blob, start + 2).ok()? as usize;
+    let is_text = le16(blob, start + 4).ok()? == 1;
+    if length < 6 {
+        return None;
+    }
+    let end = (start + length).min(limit);
+
+    let mut pos = start + 6;
+    let mut key_units = Vec::new();
+    loop {
+        let unit = le16(blob, pos).ok()?;
+        pos += 2;
+        if unit == 0 {
+            break;
+        }
+        key_units.push(unit);
+        if pos >= end {
+            return None;
+        }
+    }
+    pos = align4(pos);
+
+    let value_bytes = if is_text { value_length * 2 } else { value_length };
+    let value_end = (pos + value_bytes).min(end);
+    let value = blob.get(pos.min(value_end)..value_end)?;
+
+    Some(Block {
+        key: String::from_utf16_lossy(&key_units),
+        value,
+        children_start: align4(pos + value_bytes),
+        end,
+    })
+}
+
+fn children<'a>(blob: &'a [u8], parent: &Block<'a>) -> Vec<Block<'a>> {
+    let mut blocks = Vec::new();
+    let mut pos = parent.children_start;
+    while pos + 6 <= parent.end {
+        match read_block(blob, pos, parent.end) {
+            Some(block) => {
+                pos = align4(block.end);
+                blocks.push(block);
+            }
+            None => break,
+        }
+    }
+    blocks
+}
+
+fn find_resource_entry<R: Read + Seek>(
+    reader: &mut R,
+    base: u64,
+    directory: u32,
+    id: Option<u32>,
+) -> io::Result<Option<u32>> {
+    let header = read_at(reader, base + u64::from(directory), 16)?;
+    let count = le16(&header, 12)? as usize + le16(&header, 14)? as usize;
+    if count > MAX_RESOURCE_ENTRIES {
+        return Err(invalid("Too many resource directory entries"));
+    }
+
+    let entries = read_at(reader, base + u64::from(directory) + 16, count * 8)?;
+    for raw in entries.chunks_exact(8) {
+        let name = le32(raw, 0)?;
+        let offset = le32(raw, 4)?;
+        match id {
+            Some(wanted) if name != wanted => continue,
+            _ => return Ok(Some(offset)),
+        }
+    }
+    Ok(None)
+}
+
+fn subdirectory(entry: u32) -> io::Result<u32> {
+    if entry & 0x8000_0000 == 0 {
+        return Err(invalid("Expected a resource subdirectory"));
+    }
+    Ok(entry & 0x7FFF_FFFF)
+}
+
+fn rva_to_offset(sections: &[Section], rva: u32) -> Option<u64> {
+    sections.iter().find_map(|section| {
+        let size = section.virtual_size.max(section.raw_size);
+        if rva >= section.virtual_address && rva - section.virtual_address < size {
+            Some(u64::from(section.raw_pointer) + u64::from(rva - section.virtual_address))
+        } else {
+            None
+        }
+    })
+}
+
+fn read_at<R: Read + Seek>(reader: &mut R, offset: u64, len: usize) -> io::Result<Vec<u8>> {
+    reader.seek(SeekFrom::Start(offset))?;
+    let mut buffer = vec![0u8; len];
+    reader.read_exact(&mut buffer)?;
+    Ok(buffer)
+}
+
+fn fixed_version(most_significant: u32, least_significant: u32) -> String {
+    format!(
+        "{}.{}.{}.{}",
+        most_significant >> 16,
+        most_significant & 0xFFFF,
+        least_significant >> 16,
+        least_significant & 0xFFFF
+    )
+}
+
+fn decode_utf16(bytes: &[u8]) -> String {
+    let units: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|chunk| u16::from_le_bytes([chunk[0], chunk[1]]))
+        .take_while(|&unit| unit != 0)
+        .collect();
+    String::from_utf16_lossy(&units).trim().to_string()
+}
+
+fn align4(value: usize) -> usize {
+    (value + 3) & !3
+}
+
+fn le16(data: &[u8], offset: usize) -> io::Result<u16> {
+    data.get(offset..offset + 2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+        .ok_or_else(|| invalid("Unexpected end of PE data"))
+}
+
+fn le32(data: &[u8], offset: usize) -> io::Result<u32> {
+    data.get(offset..offset + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or_else(|| invalid("Unexpected end of PE data"))
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    const TIMESTAMP: u32 = 0x5E8F_1A2B;
+    const SECTION_RVA: u32 = 0x1000;
+    const SECTION_OFFSET: usize = 0x200;
+
+    fn utf16z(text: &str) -> Vec<u8> {
+        text.encode_utf16().chain([0]).flat_map(|c| c.to_le_bytes()).collect()
+    }
+
+    fn pad4(bytes: &mut Vec<u8>) {
+        bytes.resize(align4(bytes.len()), 0);
+    }
+
+    /// One version resource block: header, key, value, then children.
+    fn block(key: &str, value: &[u8], is_text: bool, children: &[Vec<u8>]) -> Vec<u8> {
+        let mut body = utf16z(key);
+        // The key starts after the 6 byte header, so padding is relative to that.
+        body.resize(align4(body.len() + 6) - 6, 0);
+        body.extend_from_slice(value);
+        for child in children {
+            body.resize(align4(body.len() + 6) - 6, 0);
+            body.extend_from_slice(child);
+        }
+        let value_length = if is_text { value.len() / 2 } else { value.len() };
+        let mut out = Vec::new();
+        out.extend(((body.len() + 6) as u16).to_le_bytes());
+        out.extend((value_length as u16).to_le_bytes());
+        out.extend((is_text as u16).to_le_bytes());
+        out.extend(body);
+        out
+    }
+
+    fn fixed_file_info() -> Vec<u8> {
+        let mut info = Vec::new();
+        for value in [VS_FIXEDFILEINFO_SIGNATURE, 0x0001_0000, 0x0004_0005, 0x0000_0001, 0x0004_0005, 0x0000_0000] {
+            info.extend(value.to_le_bytes());
+        }
+        info.resize(52, 0);
+        info
+    }
+
+    fn version_info(strings: &[(&str, &str)], fixed: bool) -> Vec<u8> {
+        let entries: Vec<Vec<u8>> = strings
+            .iter()
+            .map(|(key, value)| block(key, &utf16z(value), true, &[]))
+            .collect();
+        let table = block("040904b0", &[], true, &entries);
+        let string_file_info = block("StringFileInfo", &[], true, &[table]);
+        let value = if fixed { fixed_file_info() } else { Vec::new() };
+        block("VS_VERSION_INFO", &value, false, &[string_file_info])
+    }
+
+    /// A PE32+ image with one section holding a resource tree that points at
+    /// `blob`, or no resource directory at all when `blob` is `None`.
+    fn pe_image(blob: Option<&[u8]>) -> Vec<u8> {
+        let mut image = vec![0u8; SECTION_OFFSET];
+        image[0..2].copy_from_slice(b"MZ");
+        image[0x3C..0x40].copy_from_slice(&64u32.to_le_bytes());
+
+        image[64..68].copy_from_slice(b"PE\0\0");
+        image[70..72].copy_from_slice(&1u16.to_le_bytes());
+        image[72..76].copy_from_slice(&TIMESTAMP.to_le_bytes());
+        image[84..86].copy_from_slice(&240u16.to_le_bytes());
+
+        let optional = 88;
+        image[optional..optional + 2].copy_from_slice(&0x20Bu16.to_le_bytes());
+        image[optional + 108..optional + 112].copy_from_slice(&16u32.to_le_bytes());
+
+        let mut resources = Vec::new();
+        if let Some(blob) = blob {
+            let directory = |resources: &mut Vec<u8>, name: u32, offset: u32| {
+                resources.extend([0u8; 14]);
+                resources.extend(1u16.to_le_bytes());
+                resources.extend(name.to_le_bytes());
+                resources.extend(offset.to_le_bytes());
+            };
+            directory(&mut resources, RT_VERSION, 0x8000_0000 | 24);
+            directory(&mut resources, 1, 0x8000_0000 | 48);
+            directory(&mut resources, 0x409, 72);
+            resources.extend((SECTION_RVA + 88).to_le_bytes());
+            resources.extend((blob.len() as u32).to_le_bytes());
+            resources.extend([0u8; 8]);
+            resources.extend_from_slice(blob);
+
+            let resource_dir = optional + 112 + RESOURCE_DIRECTORY_INDEX * 8;
+            image[resource_dir..resource_dir + 4].copy_from_slice(&SECTION_RVA.to_le_bytes());
+            image[resource_dir + 4..resource_dir + 8].copy_from_slice(&(resources.len() as u32).to_le_bytes());
+        }
+
+        let section = optional + 240;
+        image[section..section + 5].copy_from_slice(b".rsrc");
+        image[section + 8..section + 12].copy_from_slice(&(resources.len() as u32).to_le_bytes());
+        image[section + 12..section + 16].copy_from_slice(&SECTION_RVA.to_le_bytes());
+        image[section + 16..section + 20].copy_from_slice(&(resources.len() as u32).to_le_bytes());
+        image[section + 20..section + 24].copy_from_slice(&(SECTION_OFFSET as u32).to_le_bytes());
+
+        image.extend(resources);
+        image
+    }
+
+    fn read(image: &[u8]) -> io::Result<PeVersionInfo> {
+        read_pe_version(&mut Cursor::new(image))
+    }
+
+    #[test]
+    fn reads_the_string_table() {
+        let blob = version_info(
+            &[("FileVersion", "4.5.0.0"), ("ProductVersion", "++Fortnite+Release-4.5-CL-4159770")],
+            true,
+        );
+        assert_eq!(
+            read(&pe_image(Some(&blob))).unwrap(),
+            PeVersionInfo {
+                product_version: Some("++Fortnite+Release-4.5-CL-4159770".to_string()),
+                file_version: Some("4.5.0.0".to_string()),
+                link_timestamp: TIMESTAMP,
+            }
+        );
+    }
+
+    #[test]
+    fn falls_back_to_the_fixed_file_info() {
+        let blob = version_info(&[("ProductVersion", "  ")], true);
+        assert_eq!(
+            parse_version_info(&blob).unwrap(),
+            (Some("4.5.0.0".to_string()), Some("4.5.0.1".to_string()))
+        );
+    }
+
+    #[test]
+    fn image_without_resources_only_has_a_timestamp() {
+        assert_eq!(
+            read(&pe_image(None)).unwrap(),
+            PeVersionInfo {
+                link_timestamp: TIMESTAMP,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn rejects_images_that_are_not_pe() {
+        assert!(read(b"").is_err());
+        assert!(read(&[0u8; 512]).is_err());
+
+        let mut image = pe_image(None);
+        image[64] = b'X';
+        assert!(read(&image).is_err());
+
+        let mut image = pe_image(None);
+        image[88] = 0x42;
+        assert!(read(&image).is_err());
+
+        assert!(parse_version_info(&block("NOT_VERSION_INFO", &[], false, &[])).is_err());
+    }
+
+    #[test]
+    fn truncated_inputs_do_not_panic() {
+        let blob = version_info(&[("ProductVersion", "++Fortnite+Release-4.5-CL-4159770")], true);
+        let image = pe_image(Some(&blob));
+        for len in 0..image.len() {
+            let _ = read(&image[..len]);
+        }
+        for len in 0..blob.len() {
+            let _ = parse_version_info(&blob[..len]);
+        }
+    }
+
+    #[test]
+    fn corrupted_inputs_do_not_panic() {
+        let blob = version_info(&[("ProductVersion", "++Fortnite+Release-4.5-CL-4159770")], true);
+        let image = pe_image(Some(&blob));
+        for index in 0..image.len() {
+            for value in [0x00, 0x01, 0x7F, 0x80, 0xFF] {
+                let mut corrupted = image.clone();
+                corrupted[index] = value;
+                let _ = read(&corrupted);
+            }
+        }
+        for index in 0..blob.len() {
+            for value in [0x00, 0x03, 0xFF] {
+                let mut corrupted = blob.clone();
+                corrupted[index] = value;
+                let _ = parse_version_info(&corrupted);
+            }
+        }
+    }
+}
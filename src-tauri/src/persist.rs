@@ -0,0 +1,76 @@
+//! Writing the launcher's state files so a crash or power cut mid-save
+//! leaves either the old content or the new, never a torn file.
+
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Replaces `path` with `content`: it goes to a sibling temp file which is
+/// fsynced and then renamed over the old one. The parent folder is created
+/// if it doesn't exist yet.
+pub fn write_atomic(path: &Path, content: impl AsRef<[u8]>) -> io::Result<()> {
+    let parent = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, format!("{} has no parent folder", path.display())))?;
+    fs::create_dir_all(parent)?;
+
+    let temp_path = temp_path(path);
+    let written = (|| {
+        let mut file = File::create(&temp_path)?;
+        file.write_all(content.as_ref())?;
+        file.sync_all()?;
+        fs::rename(&temp_path, path)
+    })();
+    if let Err(e) = written {
+        let _ = fs::remove_file(&temp_path);
+        return Err(e);
+    }
+
+    // The rename only survives a power cut once the folder is synced too.
+    #[cfg(unix)]
+    if let Ok(dir) = File::open(parent) {
+        let _ = dir.sync_all();
+    }
+    Ok(())
+}
+
+fn temp_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".tmp");
+    path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fs::TempDir;
+
+    #[test]
+    fn replaces_the_file_and_leaves_no_temp_behind() {
+        let dir = TempDir::new("persist", "replace");
+        let path = dir.join("nested").join("state.json");
+
+        write_atomic(&path, "first").unwrap();
+        write_atomic(&path, "second").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "second");
+        assert_eq!(fs::read_dir(dir.join("nested")).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn a_failed_write_keeps_the_old_content() {
+        let dir = TempDir::new("persist", "failed");
+        let path = dir.join("state.json");
+        write_atomic(&path, "old").unwrap();
+        // A folder where the temp file should go makes the write fail.
+        fs::create_dir(temp_path(&path)).unwrap();
+
+        assert!(write_atomic(&path, "new").is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "old");
+    }
+
+    #[test]
+    fn needs_a_parent_folder() {
+        assert!(write_atomic(Path::new("state.json"), "content").is_err());
+    }
+}
@@ -0,0 +1,346 @@
+use serde::Serialize;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use sysinfo::Disks;
+use tauri::{AppHandle, State};
+
+use crate::error::LauncherError;
+use crate::game_launcher::BuildLayout;
+use crate::game_session::GameSessions;
+use crate::launch_profiles::{dll_path, resolve_launch, ProfileState};
+use crate::offline::{offline_reason, probe_backend, stored_token, token_usable_offline, unix_now, NetworkState};
+use crate::payloads::{inspect_cached, load_manifest, payloads_dir, trusted_keys, verify_local};
+
+const SAVED_DIR: &str = "FortniteGame/Saved";
+// Logs, crash dumps and config all land in Saved.
+const MIN_FREE_SPACE: u64 = 512 * 1024 * 1024;
+const LOW_FREE_SPACE: u64 = 2 * 1024 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PreflightCheck {
+    pub id: &'static str,
+    pub status: CheckStatus,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PreflightReport {
+    /// False when any check failed. Warnings don't stop a launch.
+    pub ok: bool,
+    pub offline: bool,
+    pub checks: Vec<PreflightCheck>,
+}
+
+fn check(id: &'static str, status: CheckStatus, message: impl Into<String>) -> PreflightCheck {
+    PreflightCheck {
+        id,
+        status,
+        message: message.into(),
+    }
+}
+
+fn check_executables(root: &Path) -> PreflightCheck {
+    match BuildLayout::from_root(root) {
+        Ok(layout) if layout.launcher_stub.is_none() => check(
+            "executables",
+            CheckStatus::Warn,
+            "FortniteLauncher.exe is missing; some builds close without it",
+        ),
+        Ok(_) => check("executables", CheckStatus::Pass, "Game and launcher stub found"),
+        Err(e) => check("executables", CheckStatus::Fail, e.to_string()),
+    }
+}
+
+/// Free bytes on the disk `path` is on, going by the longest mount point it
+/// sits under. `path` should be canonical.
+pub fn available_space(path: &Path) -> Option<u64> {
+    let disks = Disks::new_with_refreshed_list();
+    let disk = disks
+        .list()
+        .iter()
+        .filter(|disk| path.starts_with(disk.mount_point()))
+        .max_by_key(|disk| disk.mount_point().as_os_str().len())?;
+    Some(disk.available_space())
+}
+
+/// Free space on whichever disk holds the build's Saved folder.
+fn check_disk_space(root: &Path) -> PreflightCheck {
+    let saved = root.join(SAVED_DIR);
+    let target = fs::canonicalize(&saved)
+        .or_else(|_| fs::canonicalize(root))
+        .unwrap_or(saved);
+    let Some(free) = available_space(&target) else {
+        return check("disk_space", CheckStatus::Warn, "Couldn't tell which disk the build is on");
+    };
+    let free_mb = free / (1024 * 1024);
+    if free < MIN_FREE_SPACE {
+        check("disk_space", CheckStatus::Fail, format!("Only {} MB free for Saved", free_mb))
+    } else if free < LOW_FREE_SPACE {
+        check("disk_space", CheckStatus::Warn, format!("Only {} MB free for Saved", free_mb))
+    } else {
+        check("disk_space", CheckStatus::Pass, format!("{} MB free", free_mb))
+    }
+}
+
+/// Verifies the payloads in the cache at `dir` and the local DLLs where they
+/// are, without caching or discarding anything; the launch does that.
+fn check_payloads(dir: &Path, root: &Path, dlls: &[String], offline: bool) -> PreflightCheck {
+    if dlls.is_empty() {
+        return check("payloads", CheckStatus::Pass, "No payloads to inject");
+    }
+    let manifest = match load_manifest(dir) {
+        Ok(manifest) => manifest,
+        Err(e) => return check("payloads", CheckStatus::Fail, e.to_string()),
+    };
+
+    let keys = trusted_keys();
+    let mut to_download = Vec::new();
+    for dll in dlls {
+        let Some(entry) = manifest.get(dll) else {
+            if let Err(e) = verify_local(&manifest, &keys, &dll_path(root, dll)) {
+                return check("payloads", CheckStatus::Fail, e.to_string());
+            }
+            continue;
+        };
+        match inspect_cached(dir, &keys, entry) {
+            Ok(Some(_)) => {}
+            Ok(None) => to_download.push(entry.name.clone()),
+            Err(e) => return check("payloads", CheckStatus::Fail, e.to_string()),
+        }
+    }
+
+    match (to_download.is_empty(), offline) {
+        (true, _) => check("payloads", CheckStatus::Pass, "Payload hashes verified"),
+        (false, true) => check(
+            "payloads",
+            CheckStatus::Fail,
+            format!("Not cached and can't be downloaded offline: {}", to_download.join(", ")),
+        ),
+        (false, false) => check(
+            "payloads",
+            CheckStatus::Warn,
+            format!("Will be downloaded at launch: {}", to_download.join(", ")),
+        ),
+    }
+}
+
+fn check_token(app_handle: &AppHandle, offline: bool) -> PreflightCheck {
+    match stored_token(app_handle) {
+        None if offline => check("token", CheckStatus::Fail, "No stored login to play offline with"),
+        None => check("token", CheckStatus::Warn, "No stored login"),
+        Some(stored) if token_usable_offline(&stored, unix_now()) => {
+            check("token", CheckStatus::Pass, "Stored login is still valid")
+        }
+        Some(_) if offline => check("token", CheckStatus::Fail, "Stored login has expired"),
+        Some(_) => check("token", CheckStatus::Warn, "Stored login has expired; log in again soon"),
+    }
+}
+
+/// Whether files could be created in the folder at `path`, asked of the OS
+/// rather than tried.
+#[cfg(unix)]
+fn can_create_files(path: &Path) -> io::Result<()> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = std::ffi::CString::new(path.as_os_str().as_bytes())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    if unsafe { libc::access(path.as_ptr(), libc::W_OK | libc::X_OK) } == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+/// Whether files could be created in the folder at `path`. Opening the folder
+/// for writing is checked against its ACL without changing anything in it.
+#[cfg(windows)]
+fn can_create_files(path: &Path) -> io::Result<()> {
+    use std::os::windows::fs::OpenOptionsExt;
+    use winapi::um::winbase::FILE_FLAG_BACKUP_SEMANTICS;
+
+    fs::OpenOptions::new()
+        .write(true)
+        .custom_flags(FILE_FLAG_BACKUP_SEMANTICS)
+        .open(path)
+        .map(|_| ())
+}
+
+/// Whether the game could write to the build folder. Nothing is written.
+fn check_writable(root: &Path) -> PreflightCheck {
+    match can_create_files(root) {
+        Ok(()) => check("writable", CheckStatus::Pass, "Build folder is writable"),
+        Err(e) => check("writable", CheckStatus::Fail, format!("Can't write to the build folder: {}", e)),
+    }
+}
+
+/// Runs every check a launch of `path` with `profile` depends on.
+#[tauri::command]
+pub async fn preflight(
+    path: String,
+    profile: Option<String>,
+    profiles: State<'_, ProfileState>,
+    network: State<'_, NetworkState>,
+    sessions: State<'_, GameSessions>,
+    app_handle: AppHandle,
+) -> Result<PreflightReport, LauncherError> {
+    let root = PathBuf::from(&path);
+    let launch = resolve_launch(&profiles.0.lock().unwrap(), profile.as_deref(), &path)?;
+    let mode = *network.0.lock().unwrap();
+    let status = probe_backend().await;
+    let offline = offline_reason(mode, status.is_some());
+
+    let backend = match (&status, &offline) {
+        (_, Some(reason)) => check("backend", CheckStatus::Warn, format!("Playing offline: {}", reason)),
+        (Some(status), None) if status.is_server_ready => check("backend", CheckStatus::Pass, "Servers are online"),
+        (Some(_), None) => check("backend", CheckStatus::Fail, "Servers are offline"),
+        (None, None) => check("backend", CheckStatus::Fail, "The backend can't be reached"),
+    };
+    let session = if sessions.is_running(&path) {
+        check("session", CheckStatus::Fail, "This build is already running")
+    } else {
+        check("session", CheckStatus::Pass, "No session running for this build")
+    };
+
+    // Hashing payloads and listing disks block, so they stay off the async
+    // runtime.
+    let payloads = payloads_dir(&app_handle);
+    let is_offline = offline.is_some();
+    let [executables, disk_space, payloads, writable] = tokio::task::spawn_blocking(move || {
+        [
+            check_executables(&root),
+            check_disk_space(&root),
+            check_payloads(&payloads, &root, &launch.dlls, is_offline),
+            check_writable(&root),
+        ]
+    })
+    .await?;
+
+    let checks = vec![
+        executables,
+        disk_space,
+        payloads,
+        backend,
+        check_token(&app_handle, is_offline),
+        session,
+        writable,
+    ];
+    Ok(PreflightReport {
+        ok: checks.iter().all(|check| check.status != CheckStatus::Fail),
+        offline: offline.is_some(),
+        checks,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::payloads::{cached_path, PayloadEntry, PayloadManifest};
+    use crate::test_fs::{fake_build, TempDir};
+    use sha2::{Digest, Sha256};
+
+    /// Every file under `root`, so a check can be shown to leave it alone.
+    fn snapshot(root: &Path) -> Vec<PathBuf> {
+        let mut files = Vec::new();
+        let mut stack = vec![root.to_path_buf()];
+        while let Some(dir) = stack.pop() {
+            for entry in fs::read_dir(dir).unwrap().flatten() {
+                if entry.file_type().unwrap().is_dir() {
+                    stack.push(entry.path());
+                }
+                files.push(entry.path());
+            }
+        }
+        files.sort();
+        files
+    }
+
+    #[test]
+    fn executables_need_the_client_and_want_the_stub() {
+        let dir = TempDir::new("preflight", "executables");
+        let with_stubs = fake_build(&dir, "with-stubs", true);
+        let without_stubs = fake_build(&dir, "without-stubs", false);
+
+        assert_eq!(check_executables(&with_stubs).status, CheckStatus::Pass);
+        assert_eq!(check_executables(&without_stubs).status, CheckStatus::Warn);
+        assert_eq!(check_executables(&dir.join("missing")).status, CheckStatus::Fail);
+    }
+
+    #[test]
+    fn finds_the_disk_a_build_is_on() {
+        let dir = TempDir::new("preflight", "disk");
+        let root = fake_build(&dir, "build", false);
+
+        // Without a Saved folder yet, the build's own disk is used.
+        let space = check_disk_space(&root);
+        assert_eq!(space.id, "disk_space");
+        assert!(space.message.contains("MB"), "{}", space.message);
+        assert!(available_space(&root).is_some());
+        assert!(!root.join(SAVED_DIR).exists());
+    }
+
+    #[test]
+    fn writable_only_asks() {
+        let dir = TempDir::new("preflight", "writable");
+        let root = fake_build(&dir, "build", false);
+        let before = snapshot(&root);
+
+        assert_eq!(check_writable(&root).status, CheckStatus::Pass);
+        assert_eq!(check_writable(&dir.join("missing")).status, CheckStatus::Fail);
+        assert_eq!(snapshot(&root), before);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn a_read_only_build_is_not_writable() {
+        use std::os::unix::fs::PermissionsExt;
+
+        // Root may write anywhere, so the check would rightly pass.
+        if unsafe { libc::geteuid() } == 0 {
+            return;
+        }
+        let dir = TempDir::new("preflight", "read-only");
+        let root = fake_build(&dir, "build", false);
+        fs::set_permissions(&root, fs::Permissions::from_mode(0o555)).unwrap();
+        let status = check_writable(&root).status;
+        fs::set_permissions(&root, fs::Permissions::from_mode(0o755)).unwrap();
+        assert_eq!(status, CheckStatus::Fail);
+    }
+
+    #[test]
+    fn payload_check_changes_nothing() {
+        let dir = TempDir::new("preflight", "payloads");
+        let root = fake_build(&dir, "build", false);
+        let cache = dir.join("payloads");
+        fs::create_dir_all(&cache).unwrap();
+        let dll = b"MZ local";
+        fs::write(dll_path(&root, "local.dll"), dll).unwrap();
+        let entry = |name: &str, data: &[u8]| PayloadEntry {
+            name: name.to_string(),
+            url: format!("http://127.0.0.1/{}.dll", name),
+            sha256: hex::encode(Sha256::digest(data)),
+            signature: None,
+        };
+        let manifest = PayloadManifest {
+            payloads: vec![entry("client", b"MZ client"), entry("local", dll)],
+        };
+        fs::write(cache.join("manifest.json"), serde_json::to_string(&manifest).unwrap()).unwrap();
+        // A cached copy that no longer matches its hash.
+        fs::write(cached_path(&cache, &manifest.payloads[0]), "MZ tampered").unwrap();
+        let before = (snapshot(&root), snapshot(&cache));
+
+        let local = check_payloads(&cache, &root, &["local.dll".to_string()], false);
+        assert_eq!(local.status, CheckStatus::Pass, "{}", local.message);
+        let cached = check_payloads(&cache, &root, &["client".to_string()], false);
+        assert_eq!(cached.status, CheckStatus::Fail);
+        assert_eq!((snapshot(&root), snapshot(&cache)), before);
+    }
+}
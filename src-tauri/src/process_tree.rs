@@ -0,0 +1,92 @@
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use sysinfo::{Pid, System};
+
+use crate::error::LauncherError;
+
+/// Enough about a process to tell it apart from a later one that got the
+/// same PID.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ProcessIdentity {
+    pub pid: u32,
+    pub name: String,
+    /// Seconds since the epoch.
+    pub start_time: u64,
+}
+
+impl ProcessIdentity {
+    pub fn of(system: &System, pid: u32) -> Option<Self> {
+        system.process(Pid::from_u32(pid)).map(|process| ProcessIdentity {
+            pid,
+            name: process.name().to_string(),
+            start_time: process.start_time(),
+        })
+    }
+
+    /// Whether `pid` still belongs to this process, as far as `system` knows.
+    pub fn is_current(&self, system: &System) -> bool {
+        ProcessIdentity::of(system, self.pid).as_ref() == Some(self)
+    }
+}
+
+pub fn snapshot() -> System {
+    let mut system = System::new();
+    system.refresh_processes();
+    system
+}
+
+/// Every process below `root`, children before their parents, so killing in
+/// order never orphans a grandchild.
+pub fn descendants(system: &System, root: u32) -> Vec<ProcessIdentity> {
+    let mut children: HashMap<u32, Vec<u32>> = HashMap::new();
+    for (pid, process) in system.processes() {
+        if let Some(parent) = process.parent() {
+            children.entry(parent.as_u32()).or_default().push(pid.as_u32());
+        }
+    }
+
+    let mut order = Vec::new();
+    collect_post_order(&children, root, &mut HashSet::new(), &mut order);
+    order.pop(); // `root` itself comes last.
+
+    // A parent that started after its child means the parent's PID was
+    // reused and the "child" isn't ours.
+    order
+        .into_iter()
+        .filter_map(|pid| ProcessIdentity::of(system, pid))
+        .filter(|identity| {
+            system
+                .process(Pid::from_u32(identity.pid))
+                .and_then(|process| process.parent())
+                .and_then(|parent| system.process(parent))
+                .is_none_or(|parent| parent.start_time() <= identity.start_time)
+        })
+        .collect()
+}
+
+fn collect_post_order(children: &HashMap<u32, Vec<u32>>, pid: u32, seen: &mut HashSet<u32>, order: &mut Vec<u32>) {
+    // PID reuse can make the parent links loop.
+    if !seen.insert(pid) {
+        return;
+    }
+    for &child in children.get(&pid).into_iter().flatten() {
+        collect_post_order(children, child, seen, order);
+    }
+    order.push(pid);
+}
+
+/// Kills `identity` if its PID still belongs to it. `Ok(false)` means it was
+/// already gone, or the PID has been handed to something else.
+pub fn terminate(identity: &ProcessIdentity) -> Result<bool, LauncherError> {
+    let mut system = System::new();
+    let pid = Pid::from_u32(identity.pid);
+    system.refresh_process(pid);
+    if !identity.is_current(&system) {
+        return Ok(false);
+    }
+    match system.process(pid) {
+        Some(process) if process.kill() => Ok(true),
+        Some(_) => Err(LauncherError::Launch(format!("Failed to kill {} ({})", identity.name, identity.pid))),
+        None => Ok(false),
+    }
+}
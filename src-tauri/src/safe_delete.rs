@@ -0,0 +1,418 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::error::LauncherError;
+use crate::persist::write_atomic;
+use crate::thumbnail_cache::backfill_thumbnails;
+use crate::{save_versions, StoredVersion, VersionState};
+
+const UNDO_WINDOW: Duration = Duration::from_secs(30);
+const PENDING_FILE: &str = "pending_deletions.json";
+// Top-level folders a build root may contain. Anything else means the folder
+// holds more than one build and is not ours to delete.
+const BUILD_ROOT_DIRS: &[&str] = &["FortniteGame", "Engine", "EasyAntiCheat", "BattlEye"];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PlanEntry {
+    pub name: String,
+    pub is_dir: bool,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DeletionPlan {
+    pub path: String,
+    pub file_count: u64,
+    pub total_bytes: u64,
+    pub entries: Vec<PlanEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingDeletion {
+    pub id: String,
+    pub original_path: String,
+    pub staged_path: String,
+    pub version: Option<StoredVersion>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct DeletionEvent {
+    id: String,
+    path: String,
+    undo_seconds: u64,
+}
+
+#[derive(Default)]
+pub struct DeletionState(Mutex<HashMap<String, PendingDeletion>>);
+
+/// Folders that must never be deleted, nor any folder containing them.
+pub fn protected_dirs(app_handle: &AppHandle) -> Vec<PathBuf> {
+    let resolver = app_handle.path();
+    let candidates = [
+        resolver.home_dir(),
+        resolver.desktop_dir(),
+        resolver.document_dir(),
+        resolver.download_dir(),
+        resolver.picture_dir(),
+        resolver.video_dir(),
+        resolver.audio_dir(),
+        resolver.public_dir(),
+        resolver.data_dir(),
+        resolver.local_data_dir(),
+        resolver.config_dir(),
+        resolver.cache_dir(),
+        resolver.app_data_dir(),
+    ];
+
+    candidates
+        .into_iter()
+        .flatten()
+        .chain(std::iter::once(std::env::temp_dir()))
+        .filter_map(|dir| fs::canonicalize(dir).ok())
+        .collect()
+}
+
+/// Confirms `path` is a build root that is safe to delete and returns its
+/// canonical form. Refuses filesystem roots, protected folders and their
+/// ancestors, folders without the `FortniteGame` layout, and folders holding
+/// anything besides a build.
+pub fn check_deletable(path: &Path, protected: &[PathBuf]) -> Result<PathBuf, LauncherError> {
+    let canonical =
+        fs::canonicalize(path).map_err(|e| LauncherError::io(format!("Cannot resolve {}", path.display()), e))?;
+
+    if !canonical.is_dir() {
+        return Err(LauncherError::InvalidInput(format!("{} is not a directory", canonical.display())));
+    }
+    if canonical.parent().is_none() {
+        return Err(LauncherError::InvalidInput(format!(
+            "Refusing to delete filesystem root {}",
+            canonical.display()
+        )));
+    }
+    if let Some(dir) = protected.iter().find(|dir| dir.starts_with(&canonical)) {
+        return Err(LauncherError::InvalidInput(format!(
+            "Refusing to delete {} because it contains {}",
+            canonical.display(),
+            dir.display()
+        )));
+    }
+
+    let game_dir = canonical.join("FortniteGame");
+    let has_layout = game_dir.is_dir() && (game_dir.join("Binaries").is_dir() || game_dir.join("Content").is_dir());
+    if !has_layout {
+        return Err(LauncherError::InvalidInput(format!(
+            "{} does not look like a Fortnite build",
+            canonical.display()
+        )));
+    }
+
+    let entries =
+        fs::read_dir(&canonical).map_err(|e| LauncherError::io(format!("Failed to read {}", canonical.display()), e))?;
+    let mut foreign = Vec::new();
+    for entry in entries.flatten() {
+        let is_dir = entry.file_type().map(|t| t.is_dir()).unwrap_or(false);
+        let name = entry.file_name().to_string_lossy().to_string();
+        if is_dir && !BUILD_ROOT_DIRS.iter().any(|known| known.eq_ignore_ascii_case(&name)) {
+            foreign.push(name);
+        }
+    }
+    if !foreign.is_empty() {
+        foreign.sort();
+        return Err(LauncherError::InvalidInput(format!(
+            "Refusing to delete {} because it also contains: {}",
+            canonical.display(),
+            foreign.join(", ")
+        )));
+    }
+
+    Ok(canonical)
+}
+
+fn measure(path: &Path) -> (u64, u64) {
+    let metadata = match fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(_) => return (0, 0),
+    };
+    if !metadata.is_dir() {
+        return (1, metadata.len());
+    }
+
+    let mut files = 0;
+    let mut bytes = 0;
+    if let Ok(entries) = fs::read_dir(path) {
+        for entry in entries.flatten() {
+            let (entry_files, entry_bytes) = measure(&entry.path());
+            files += entry_files;
+            bytes += entry_bytes;
+        }
+    }
+    (files, bytes)
+}
+
+/// Lists what deleting `root` would remove, without touching anything.
+pub fn plan_deletion(root: &Path) -> Result<DeletionPlan, LauncherError> {
+    let mut entries = Vec::new();
+    let mut file_count = 0;
+    let mut total_bytes = 0;
+
+    let listing = fs::read_dir(root).map_err(|e| LauncherError::io(format!("Failed to read {}", root.display()), e))?;
+    for entry in listing.flatten() {
+        let (files, bytes) = measure(&entry.path());
+        file_count += files;
+        total_bytes += bytes;
+        entries.push(PlanEntry {
+            name: entry.file_name().to_string_lossy().to_string(),
+            is_dir: entry.file_type().map(|t| t.is_dir()).unwrap_or(false),
+            bytes,
+        });
+    }
+    entries.sort_by_key(|entry| std::cmp::Reverse(entry.bytes));
+
+    Ok(DeletionPlan {
+        path: root.to_string_lossy().to_string(),
+        file_count,
+        total_bytes,
+        entries,
+    })
+}
+
+/// Hides `root` by renaming it to a dot-folder next to it, so the delete can
+/// be undone until it is finalized.
+pub fn stage_deletion(root: &Path, id: &str) -> Result<PathBuf, LauncherError> {
+    let name = root
+        .file_name()
+        .ok_or_else(|| LauncherError::InvalidInput("Build folder has no name".to_string()))?
+        .to_string_lossy()
+        .to_string();
+    let staged = root.with_file_name(format!(".{}.rewind-deleted-{}", name, id));
+    fs::rename(root, &staged)
+        .map_err(|e| LauncherError::io(format!("Failed to stage {} for deletion", root.display()), e))?;
+    Ok(staged)
+}
+
+/// Sends a staged folder to the OS trash, deleting it outright when the
+/// platform has no trash.
+pub fn finalize_deletion(staged: &Path) -> Result<(), LauncherError> {
+    if !staged.exists() {
+        return Ok(());
+    }
+    if let Err(e) = trash::delete(staged) {
+        println!("Could not move {} to trash, deleting instead: {}", staged.display(), e);
+        fs::remove_dir_all(staged).map_err(|e| LauncherError::io(format!("Failed to delete {}", staged.display()), e))?;
+    }
+    Ok(())
+}
+
+fn pending_file(app_handle: &AppHandle) -> PathBuf {
+    app_handle.path().app_data_dir().unwrap().join(PENDING_FILE)
+}
+
+fn persist_pending(app_handle: &AppHandle, pending: &HashMap<String, PendingDeletion>) {
+    let list: Vec<&PendingDeletion> = pending.values().collect();
+    match serde_json::to_string_pretty(&list) {
+        Ok(content) => {
+            if let Err(e) = write_atomic(&pending_file(app_handle), content) {
+                println!("Failed to save pending deletions: {}", e);
+            }
+        }
+        Err(e) => println!("Failed to serialize pending deletions: {}", e),
+    }
+}
+
+fn take_pending(app_handle: &AppHandle, id: &str) -> Option<PendingDeletion> {
+    let state = app_handle.state::<DeletionState>();
+    let mut pending = state.0.lock().unwrap();
+    let taken = pending.remove(id);
+    if taken.is_some() {
+        persist_pending(app_handle, &pending);
+    }
+    taken
+}
+
+async fn finalize_pending(app_handle: AppHandle, deletion: PendingDeletion) {
+    let staged = PathBuf::from(&deletion.staged_path);
+    let result = tokio::task::spawn_blocking(move || finalize_deletion(&staged))
+        .await
+        .map_err(LauncherError::from)
+        .and_then(|r| r);
+    if let Err(e) = &result {
+        println!("Failed to finalize deletion of {}: {}", deletion.original_path, e);
+    }
+    let _ = app_handle.emit("deletion-finalized", DeletionEvent {
+        id: deletion.id,
+        path: deletion.original_path,
+        undo_seconds: 0,
+    });
+}
+
+/// Stages `root` for deletion and finalizes it once the undo window passes.
+/// Returns the id `undo_delete_version` takes.
+pub fn schedule_deletion(
+    app_handle: &AppHandle,
+    root: &Path,
+    version: Option<StoredVersion>,
+) -> Result<String, LauncherError> {
+    let id = format!(
+        "{:x}",
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or(0)
+    );
+    let staged = stage_deletion(root, &id)?;
+
+    let deletion = PendingDeletion {
+        id: id.clone(),
+        original_path: root.to_string_lossy().to_string(),
+        staged_path: staged.to_string_lossy().to_string(),
+        version,
+    };
+    {
+        let state = app_handle.state::<DeletionState>();
+        let mut pending = state.0.lock().unwrap();
+        pending.insert(id.clone(), deletion.clone());
+        persist_pending(app_handle, &pending);
+    }
+
+    let _ = app_handle.emit("deletion-pending", DeletionEvent {
+        id: id.clone(),
+        path: deletion.original_path,
+        undo_seconds: UNDO_WINDOW.as_secs(),
+    });
+
+    let task_handle = app_handle.clone();
+    let task_id = id.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(UNDO_WINDOW).await;
+        if let Some(deletion) = take_pending(&task_handle, &task_id) {
+            finalize_pending(task_handle, deletion).await;
+        }
+    });
+
+    Ok(id)
+}
+
+/// Finishes deletions the launcher was closed in the middle of.
+pub fn resume_pending_deletions(app_handle: &AppHandle) {
+    let leftovers: Vec<PendingDeletion> = fs::read_to_string(pending_file(app_handle))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default();
+
+    for deletion in leftovers {
+        tauri::async_runtime::spawn(finalize_pending(app_handle.clone(), deletion));
+    }
+    let _ = fs::remove_file(pending_file(app_handle));
+}
+
+#[tauri::command]
+pub async fn plan_remove_version(path: String, app_handle: AppHandle) -> Result<DeletionPlan, LauncherError> {
+    let protected = protected_dirs(&app_handle);
+    tokio::task::spawn_blocking(move || {
+        let root = check_deletable(Path::new(&path), &protected)?;
+        plan_deletion(&root)
+    })
+    .await?
+}
+
+#[tauri::command]
+pub async fn undo_delete_version(id: String, app_handle: AppHandle) -> Result<(), LauncherError> {
+    let deletion = take_pending(&app_handle, &id)
+        .ok_or_else(|| LauncherError::NotFound("The undo window has passed".to_string()))?;
+
+    let original = PathBuf::from(&deletion.original_path);
+    let restored = if original.exists() {
+        Err(LauncherError::InvalidInput(format!("{} already exists", original.display())))
+    } else {
+        fs::rename(&deletion.staged_path, &original).map_err(|e| LauncherError::io("Failed to restore build", e))
+    };
+    if let Err(e) = restored {
+        // Keep it on the books so it is still finalized later.
+        let state = app_handle.state::<DeletionState>();
+        let mut pending = state.0.lock().unwrap();
+        pending.insert(deletion.id.clone(), deletion);
+        persist_pending(&app_handle, &pending);
+        return Err(e);
+    }
+
+    if let Some(mut version) = deletion.version {
+        // The thumbnail was collected with the version; have it rebuilt.
+        version.splash_key = None;
+        {
+            let state = app_handle.state::<VersionState>();
+            let mut versions = state.0.lock().unwrap();
+            versions.insert(version.path.clone(), version);
+            save_versions(&versions, &app_handle)?;
+        }
+        tauri::async_runtime::spawn(backfill_thumbnails(app_handle.clone()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fs::{fake_build, TempDir};
+
+    #[test]
+    fn accepts_a_build_root() {
+        let dir = TempDir::new("delete", "accept");
+        let root = fake_build(&dir, "Season 4", false);
+        fs::write(root.join("stray.log"), "files at the top are fine").unwrap();
+
+        assert_eq!(check_deletable(&root.join("FortniteGame/.."), &[]).unwrap(), root);
+    }
+
+    #[test]
+    fn refuses_what_is_not_a_lone_build() {
+        let dir = TempDir::new("delete", "refuse");
+        let root = fake_build(&dir, "build", false);
+
+        assert!(check_deletable(&dir.join("missing"), &[]).is_err());
+        assert!(check_deletable(&root.join("Engine/Build.version"), &[]).is_err());
+        assert!(check_deletable(Path::new("/"), &[]).is_err());
+        // The parent of a build holds the build, not the layout.
+        let refusal = |path: &Path, protected: &[PathBuf]| check_deletable(path, protected).unwrap_err().to_string();
+        assert!(refusal(&dir, &[]).contains("does not look like"));
+
+        let protected = vec![root.join("FortniteGame")];
+        assert!(refusal(&root, &protected).contains("because it contains"));
+
+        fs::create_dir(root.join("Saves")).unwrap();
+        assert!(refusal(&root, &[]).contains("also contains: Saves"));
+    }
+
+    #[test]
+    fn plan_lists_sizes_biggest_first() {
+        let dir = TempDir::new("delete", "plan");
+        let root = fake_build(&dir, "build", false);
+
+        let plan = plan_deletion(&root).unwrap();
+        assert_eq!(plan.file_count, 3);
+        assert_eq!(plan.total_bytes, 1302);
+        let names: Vec<&str> = plan.entries.iter().map(|entry| entry.name.as_str()).collect();
+        assert_eq!(names, ["FortniteGame", "Engine"]);
+        assert!(plan.entries.iter().all(|entry| entry.is_dir));
+        assert!(root.join("FortniteGame").exists());
+    }
+
+    #[test]
+    fn staging_hides_the_build_next_to_itself() {
+        let dir = TempDir::new("delete", "stage");
+        let root = fake_build(&dir, "build", false);
+
+        let staged = stage_deletion(&root, "abc").unwrap();
+        assert_eq!(staged, dir.join(".build.rewind-deleted-abc"));
+        assert!(!root.exists());
+        assert!(staged.join("FortniteGame/Content/Paks/pakchunk0.pak").exists());
+
+        // Nothing left to stage: the folder stays where it is.
+        assert!(stage_deletion(&root, "abc").is_err());
+        assert!(staged.exists());
+    }
+}
@@ -0,0 +1,601 @@
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+use tokio::sync::Notify;
+
+use crate::error::LauncherError;
+
+const LOG_FILE: &str = "session.log";
+const MAX_LOG_FILE_SIZE: u64 = 8 * 1024 * 1024;
+// session.log plus session.1.log .. session.4.log
+const MAX_LOG_FILES: usize = 5;
+// Older session folders are pruned once there are more than this many.
+const MAX_SESSION_LOGS: usize = 20;
+const UE_LOG: &str = "FortniteGame/Saved/Logs/FortniteGame.log";
+const TAIL_INTERVAL: Duration = Duration::from_millis(250);
+// How long `finish_and_wait` gives the tailer to do its last read.
+const TAIL_FINISH_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogSource {
+    /// Notes from the launcher itself about the session.
+    Launcher,
+    Stdout,
+    Stderr,
+    Game,
+}
+
+impl LogSource {
+    fn tag(self) -> &'static str {
+        match self {
+            LogSource::Launcher => "launcher",
+            LogSource::Stdout => "stdout",
+            LogSource::Stderr => "stderr",
+            LogSource::Game => "game",
+        }
+    }
+
+    fn from_tag(tag: &str) -> Option<Self> {
+        match tag {
+            "launcher" => Some(LogSource::Launcher),
+            "stdout" => Some(LogSource::Stdout),
+            "stderr" => Some(LogSource::Stderr),
+            "game" => Some(LogSource::Game),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LogLine {
+    pub timestamp_ms: u64,
+    pub source: LogSource,
+    pub line: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct LogFilter {
+    /// Case-insensitive substring the line must contain.
+    pub text: Option<String>,
+    /// Only these sources; all of them when empty.
+    pub sources: Vec<LogSource>,
+    /// Keep only the last this many matches.
+    pub limit: Option<usize>,
+}
+
+/// One line on disk: `<unix ms> <source> <text>`.
+pub fn format_line(line: &LogLine) -> String {
+    format!("{} {} {}\n", line.timestamp_ms, line.source.tag(), line.line)
+}
+
+pub fn parse_line(text: &str) -> Option<LogLine> {
+    let mut parts = text.splitn(3, ' ');
+    let timestamp_ms = parts.next()?.parse().ok()?;
+    let source = LogSource::from_tag(parts.next()?)?;
+    Some(LogLine {
+        timestamp_ms,
+        source,
+        line: parts.next().unwrap_or("").to_string(),
+    })
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn rotated_path(dir: &Path, index: usize) -> PathBuf {
+    if index == 0 {
+        dir.join(LOG_FILE)
+    } else {
+        dir.join(format!("session.{}.log", index))
+    }
+}
+
+/// session.log, rolled over to session.1.log and so on once it gets big.
+struct RotatingFile {
+    dir: PathBuf,
+    file: File,
+    size: u64,
+}
+
+impl RotatingFile {
+    fn open(dir: &Path) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let file = OpenOptions::new().create(true).append(true).open(rotated_path(dir, 0))?;
+        let size = file.metadata()?.len();
+        Ok(RotatingFile {
+            dir: dir.to_path_buf(),
+            file,
+            size,
+        })
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        let _ = fs::remove_file(rotated_path(&self.dir, MAX_LOG_FILES - 1));
+        for index in (0..MAX_LOG_FILES - 1).rev() {
+            let from = rotated_path(&self.dir, index);
+            if from.exists() {
+                fs::rename(&from, rotated_path(&self.dir, index + 1))?;
+            }
+        }
+        *self = RotatingFile::open(&self.dir)?;
+        Ok(())
+    }
+
+    fn write(&mut self, text: &str) -> io::Result<()> {
+        if self.size > 0 && self.size + text.len() as u64 > MAX_LOG_FILE_SIZE {
+            self.rotate()?;
+        }
+        self.file.write_all(text.as_bytes())?;
+        self.size += text.len() as u64;
+        Ok(())
+    }
+}
+
+/// Everything one game session printed, written to disk and handed to
+/// `on_line` as it arrives.
+pub struct SessionLog {
+    dir: PathBuf,
+    file: Mutex<RotatingFile>,
+    on_line: Box<dyn Fn(&LogLine) + Send + Sync>,
+    finished: AtomicBool,
+    tailing: AtomicBool,
+    tail_done: Notify,
+}
+
+impl SessionLog {
+    pub fn create(dir: &Path, on_line: impl Fn(&LogLine) + Send + Sync + 'static) -> io::Result<Arc<Self>> {
+        Ok(Arc::new(SessionLog {
+            dir: dir.to_path_buf(),
+            file: Mutex::new(RotatingFile::open(dir)?),
+            on_line: Box::new(on_line),
+            finished: AtomicBool::new(false),
+            tailing: AtomicBool::new(false),
+            tail_done: Notify::new(),
+        }))
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn write(&self, source: LogSource, text: &str) {
+        let line = LogLine {
+            timestamp_ms: now_ms(),
+            source,
+            line: text.trim_end_matches(['\r', '\n']).to_string(),
+        };
+        if let Err(e) = self.file.lock().unwrap().write(&format_line(&line)) {
+            println!("Failed to write session log: {}", e);
+        }
+        (self.on_line)(&line);
+    }
+
+    /// Copies `reader` into the log line by line on its own thread until the
+    /// pipe closes.
+    pub fn capture(self: &Arc<Self>, source: LogSource, reader: impl Read + Send + 'static) {
+        let log = self.clone();
+        std::thread::spawn(move || {
+            let mut reader = BufReader::new(reader);
+            let mut buffer = Vec::new();
+            loop {
+                buffer.clear();
+                match reader.read_until(b'\n', &mut buffer) {
+                    Ok(0) => break,
+                    Ok(_) => log.write(source, &String::from_utf8_lossy(&buffer)),
+                    Err(e) => {
+                        println!("Stopped capturing {}: {}", source.tag(), e);
+                        break;
+                    }
+                }
+            }
+        });
+    }
+
+    /// Follows the build's UE log until `finish` is called. The game moves the
+    /// previous log aside on startup, so a file that shrinks or is replaced is
+    /// read again from the start. Shrinking is the check that matters on
+    /// NTFS, which hands a recreated file its predecessor's creation time.
+    pub fn tail_game_log(self: &Arc<Self>, build_root: &Path) {
+        let log = self.clone();
+        let path = build_root.join(UE_LOG);
+        self.tailing.store(true, Ordering::SeqCst);
+        tauri::async_runtime::spawn(async move {
+            let mut tail = LogTail::new(&path);
+            loop {
+                let finished = log.finished.load(Ordering::SeqCst);
+                for line in tail.read_new_lines() {
+                    log.write(LogSource::Game, &line);
+                }
+                if finished {
+                    break;
+                }
+                tokio::time::sleep(TAIL_INTERVAL).await;
+            }
+            log.tail_done.notify_one();
+        });
+    }
+
+    /// Lets the tailer do its last read and stop.
+    pub fn finish(&self) {
+        self.finished.store(true, Ordering::SeqCst);
+    }
+
+    /// `finish`, then waits for the tailer's last read so everything the game
+    /// wrote before exiting is on disk.
+    pub async fn finish_and_wait(&self) {
+        self.finish();
+        if self.tailing.load(Ordering::SeqCst) {
+            let _ = tokio::time::timeout(TAIL_FINISH_TIMEOUT, self.tail_done.notified()).await;
+        }
+    }
+}
+
+pub struct LogTail {
+    path: PathBuf,
+    offset: u64,
+    created: Option<SystemTime>,
+    partial: Vec<u8>,
+}
+
+impl LogTail {
+    /// Skips whatever the file already holds; only lines written from now on
+    /// are returned.
+    pub fn new(path: &Path) -> Self {
+        let metadata = fs::metadata(path).ok();
+        LogTail {
+            path: path.to_path_buf(),
+            offset: metadata.as_ref().map(|m| m.len()).unwrap_or(0),
+            created: metadata.and_then(|m| m.created().ok()),
+            partial: Vec::new(),
+        }
+    }
+
+    pub fn read_new_lines(&mut self) -> Vec<String> {
+        let Ok(metadata) = fs::metadata(&self.path) else {
+            return Vec::new();
+        };
+        let created = metadata.created().ok();
+        if metadata.len() < self.offset || created != self.created {
+            self.offset = 0;
+            self.partial.clear();
+            self.created = created;
+        }
+        if metadata.len() == self.offset {
+            return Vec::new();
+        }
+
+        let mut chunk = Vec::new();
+        let read = File::open(&self.path).and_then(|mut file| {
+            file.seek(SeekFrom::Start(self.offset))?;
+            file.read_to_end(&mut chunk)
+        });
+        if read.is_err() {
+            return Vec::new();
+        }
+        if self.offset == 0 && chunk.starts_with(b"\xEF\xBB\xBF") {
+            chunk.drain(..3);
+            self.offset += 3;
+        }
+        self.offset += chunk.len() as u64;
+        self.partial.extend_from_slice(&chunk);
+
+        let mut lines = Vec::new();
+        while let Some(end) = self.partial.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = self.partial.drain(..=end).collect();
+            lines.push(
+                String::from_utf8_lossy(&line)
+                    .trim_end_matches(['\r', '\n'])
+                    .to_string(),
+            );
+        }
+        lines
+    }
+}
+
+pub fn logs_dir(app_handle: &AppHandle) -> PathBuf {
+    app_handle.path().app_data_dir().unwrap().join("logs")
+}
+
+/// Removes the oldest session folders beyond `MAX_SESSION_LOGS`.
+pub fn prune_session_logs(root: &Path) {
+    let Ok(entries) = fs::read_dir(root) else {
+        return;
+    };
+    let mut sessions: Vec<(SystemTime, PathBuf)> = entries
+        .flatten()
+        .filter(|entry| entry.file_type().map(|t| t.is_dir()).unwrap_or(false))
+        .map(|entry| {
+            let modified = entry
+                .metadata()
+                .and_then(|m| m.modified())
+                .unwrap_or(UNIX_EPOCH);
+            (modified, entry.path())
+        })
+        .collect();
+    if sessions.len() <= MAX_SESSION_LOGS {
+        return;
+    }
+
+    sessions.sort();
+    for (_, path) in &sessions[..sessions.len() - MAX_SESSION_LOGS] {
+        if let Err(e) = fs::remove_dir_all(path) {
+            println!("Failed to prune {}: {}", path.display(), e);
+        }
+    }
+}
+
+/// Reads a session's log back, oldest line first, applying `filter`.
+pub fn read_log(dir: &Path, filter: &LogFilter) -> io::Result<Vec<LogLine>> {
+    let needle = filter
+        .text
+        .as_deref()
+        .map(str::trim)
+        .filter(|text| !text.is_empty())
+        .map(str::to_lowercase);
+
+    let mut lines = Vec::new();
+    for index in (0..MAX_LOG_FILES).rev() {
+        let path = rotated_path(dir, index);
+        if !path.exists() {
+            continue;
+        }
+        for text in BufReader::new(File::open(&path)?).lines() {
+            let Some(line) = parse_line(&text?) else {
+                continue;
+            };
+            if !filter.sources.is_empty() && !filter.sources.contains(&line.source) {
+                continue;
+            }
+            if needle
+                .as_ref()
+                .is_some_and(|needle| !line.line.to_lowercase().contains(needle))
+            {
+                continue;
+            }
+            lines.push(line);
+        }
+    }
+
+    if let Some(limit) = filter.limit {
+        let skip = lines.len().saturating_sub(limit);
+        lines.drain(..skip);
+    }
+    Ok(lines)
+}
+
+/// Session ids come from the frontend, so only accept what we generate.
+fn session_dir(app_handle: &AppHandle, session_id: &str) -> Result<PathBuf, LauncherError> {
+    if session_id.is_empty() || !session_id.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(LauncherError::InvalidInput(format!("Invalid session id: {}", session_id)));
+    }
+    Ok(logs_dir(app_handle).join(session_id))
+}
+
+#[tauri::command]
+pub async fn get_session_log(
+    session_id: String,
+    filter: Option<LogFilter>,
+    app_handle: AppHandle,
+) -> Result<Vec<LogLine>, LauncherError> {
+    let dir = session_dir(&app_handle, &session_id)?;
+    if !dir.is_dir() {
+        return Err(LauncherError::NotFound(format!("No log for session {}", session_id)));
+    }
+    let filter = filter.unwrap_or_default();
+    tokio::task::spawn_blocking(move || read_log(&dir, &filter))
+        .await?
+        .map_err(|e| LauncherError::io("Failed to read session log", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fs::TempDir;
+
+    fn line(timestamp_ms: u64, source: LogSource, text: &str) -> LogLine {
+        LogLine {
+            timestamp_ms,
+            source,
+            line: text.to_string(),
+        }
+    }
+
+    fn append(path: &Path, text: &str) {
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .unwrap()
+            .write_all(text.as_bytes())
+            .unwrap();
+    }
+
+    #[test]
+    fn lines_survive_a_round_trip() {
+        for original in [
+            line(1700000000123, LogSource::Game, "LogInit: Build: ++Fortnite+Release-8.51"),
+            line(0, LogSource::Stderr, "  leading and  inner spaces"),
+            line(42, LogSource::Launcher, ""),
+        ] {
+            let formatted = format_line(&original);
+            assert!(formatted.ends_with('\n'));
+            assert_eq!(parse_line(formatted.trim_end_matches('\n')), Some(original));
+        }
+        assert_eq!(parse_line("not a log line"), None);
+        assert_eq!(parse_line("12 unknown text"), None);
+        assert_eq!(parse_line(""), None);
+    }
+
+    #[test]
+    fn rotates_once_the_file_is_full() {
+        let dir = TempDir::new("session-log", "rotate");
+        let mut file = RotatingFile::open(&dir).unwrap();
+        let block = "x".repeat(MAX_LOG_FILE_SIZE as usize / 2 - 1) + "\n";
+
+        file.write(&block).unwrap();
+        file.write(&block).unwrap();
+        assert!(!rotated_path(&dir, 1).exists());
+        assert_eq!(fs::metadata(rotated_path(&dir, 0)).unwrap().len(), MAX_LOG_FILE_SIZE);
+
+        file.write("next\n").unwrap();
+        assert_eq!(fs::read_to_string(rotated_path(&dir, 0)).unwrap(), "next\n");
+        assert_eq!(fs::metadata(rotated_path(&dir, 1)).unwrap().len(), MAX_LOG_FILE_SIZE);
+
+        // Only the newest MAX_LOG_FILES files are kept.
+        for _ in 0..MAX_LOG_FILES + 1 {
+            file.write(&block).unwrap();
+            file.write(&block).unwrap();
+        }
+        assert!(rotated_path(&dir, MAX_LOG_FILES - 1).exists());
+        assert!(!rotated_path(&dir, MAX_LOG_FILES).exists());
+    }
+
+    #[test]
+    fn reopening_keeps_counting_from_the_existing_size() {
+        let dir = TempDir::new("session-log", "reopen");
+        let block = "x".repeat(MAX_LOG_FILE_SIZE as usize - 1) + "\n";
+        RotatingFile::open(&dir).unwrap().write(&block).unwrap();
+
+        RotatingFile::open(&dir).unwrap().write("after\n").unwrap();
+        assert_eq!(fs::read_to_string(rotated_path(&dir, 0)).unwrap(), "after\n");
+    }
+
+    #[test]
+    fn tail_returns_only_whole_new_lines() {
+        let dir = TempDir::new("session-log", "tail");
+        let path = dir.join("FortniteGame.log");
+        assert!(LogTail::new(&path).read_new_lines().is_empty());
+
+        fs::write(&path, "from the last run\n").unwrap();
+        let mut tail = LogTail::new(&path);
+        assert!(tail.read_new_lines().is_empty());
+
+        append(&path, "first\r\nsecond half");
+        assert_eq!(tail.read_new_lines(), ["first"]);
+        append(&path, "-finished\n");
+        assert_eq!(tail.read_new_lines(), ["second half-finished"]);
+        assert!(tail.read_new_lines().is_empty());
+    }
+
+    #[test]
+    fn tail_starts_over_when_the_log_shrinks() {
+        let dir = TempDir::new("session-log", "tail-shrink");
+        let path = dir.join("FortniteGame.log");
+        fs::write(&path, "a long line from the previous run\nand another one\n").unwrap();
+        let mut tail = LogTail::new(&path);
+
+        // The game moves the old log aside and starts a new one, BOM first.
+        fs::remove_file(&path).unwrap();
+        assert!(tail.read_new_lines().is_empty());
+        fs::write(&path, "\u{feff}LogInit: new run\n").unwrap();
+        assert_eq!(tail.read_new_lines(), ["LogInit: new run"]);
+        append(&path, "LogInit: more\n");
+        assert_eq!(tail.read_new_lines(), ["LogInit: more"]);
+
+        // Truncated in place, with a partial line pending.
+        append(&path, "half a li");
+        assert!(tail.read_new_lines().is_empty());
+        fs::write(&path, "short\n").unwrap();
+        assert_eq!(tail.read_new_lines(), ["short"]);
+    }
+
+    #[test]
+    fn tail_starts_over_when_the_log_is_replaced_by_a_longer_one() {
+        let dir = TempDir::new("session-log", "tail-replace");
+        let path = dir.join("FortniteGame.log");
+        fs::write(&path, "old\n").unwrap();
+        if fs::metadata(&path).unwrap().created().is_err() {
+            // Without creation times only shrinking can be noticed.
+            return;
+        }
+        let mut tail = LogTail::new(&path);
+
+        std::thread::sleep(Duration::from_millis(20));
+        fs::remove_file(&path).unwrap();
+        fs::write(&path, "a longer first line\n").unwrap();
+        assert_eq!(tail.read_new_lines(), ["a longer first line"]);
+    }
+
+    #[test]
+    fn reads_rotated_logs_oldest_first_with_filters() {
+        let dir = TempDir::new("session-log", "read");
+        let older = [
+            line(1, LogSource::Launcher, "Starting 8.51"),
+            line(2, LogSource::Game, "LogInit: Build"),
+        ];
+        let newer = [
+            line(3, LogSource::Stderr, "warning: shader cache"),
+            line(4, LogSource::Game, "LogNet: Browse: 127.0.0.1"),
+            line(5, LogSource::Game, "LogInit: Exiting"),
+        ];
+        fs::write(rotated_path(&dir, 1), older.iter().map(format_line).collect::<String>()).unwrap();
+        let mut current: String = newer.iter().map(format_line).collect();
+        current.push_str("garbage that isn't a log line\n");
+        fs::write(rotated_path(&dir, 0), current).unwrap();
+
+        let timestamps = |filter: LogFilter| -> Vec<u64> {
+            read_log(&dir, &filter).unwrap().iter().map(|line| line.timestamp_ms).collect()
+        };
+        assert_eq!(timestamps(LogFilter::default()), [1, 2, 3, 4, 5]);
+        assert_eq!(
+            timestamps(LogFilter {
+                sources: vec![LogSource::Game],
+                ..Default::default()
+            }),
+            [2, 4, 5]
+        );
+        assert_eq!(
+            timestamps(LogFilter {
+                text: Some(" loginit ".to_string()),
+                ..Default::default()
+            }),
+            [2, 5]
+        );
+        assert_eq!(
+            timestamps(LogFilter {
+                sources: vec![LogSource::Game, LogSource::Launcher],
+                limit: Some(2),
+                ..Default::default()
+            }),
+            [4, 5]
+        );
+        assert_eq!(
+            timestamps(LogFilter {
+                limit: Some(0),
+                ..Default::default()
+            }),
+            Vec::<u64>::new()
+        );
+    }
+
+    #[test]
+    fn prunes_the_oldest_sessions() {
+        let dir = TempDir::new("session-log", "prune");
+        let sessions: Vec<PathBuf> = (0..MAX_SESSION_LOGS + 2).map(|i| dir.join(format!("{:04x}", i))).collect();
+        for session in &sessions {
+            fs::create_dir(session).unwrap();
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        fs::write(dir.join("stray.txt"), "not a session").unwrap();
+
+        prune_session_logs(&dir);
+        assert!(!sessions[0].exists());
+        assert!(!sessions[1].exists());
+        assert!(sessions[2..].iter().all(|session| session.exists()));
+        assert!(dir.join("stray.txt").exists());
+
+        prune_session_logs(&dir);
+        assert!(sessions[2..].iter().all(|session| session.exists()));
+    }
+}
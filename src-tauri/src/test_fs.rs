@@ -0,0 +1,56 @@
+//! Scratch folders for tests that touch the filesystem. A `TempDir` is
+//! removed when it drops, so a failing assertion doesn't leave it behind.
+
+use std::fs;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+
+pub struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    /// An empty folder for the test `name` in `area`, unique to this run.
+    /// The path is canonical, so it compares equal to resolved paths.
+    pub fn new(area: &str, name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("rewind-{}-{}-{}", area, name, std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        TempDir {
+            path: fs::canonicalize(&path).unwrap(),
+        }
+    }
+}
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+/// A build at `parent/name` laid out like a real one: a 300-byte shipping
+/// client, a 1000-byte pak and `Engine/Build.version`. `stubs` adds the
+/// launcher and anti-cheat stubs the client is started behind.
+pub fn fake_build(parent: &Path, name: &str, stubs: bool) -> PathBuf {
+    let root = parent.join(name);
+    let binaries = root.join("FortniteGame/Binaries/Win64");
+    fs::create_dir_all(&binaries).unwrap();
+    fs::create_dir_all(root.join("FortniteGame/Content/Paks")).unwrap();
+    fs::create_dir_all(root.join("Engine")).unwrap();
+    fs::write(binaries.join("FortniteClient-Win64-Shipping.exe"), vec![0u8; 300]).unwrap();
+    if stubs {
+        fs::write(binaries.join("FortniteLauncher.exe"), "").unwrap();
+        fs::write(binaries.join("FortniteClient-Win64-Shipping_EAC.exe"), "").unwrap();
+    }
+    fs::write(root.join("FortniteGame/Content/Paks/pakchunk0.pak"), vec![0u8; 1000]).unwrap();
+    fs::write(root.join("Engine/Build.version"), "{}").unwrap();
+    root
+}
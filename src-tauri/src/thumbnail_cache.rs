@@ -0,0 +1,476 @@
+use image::{imageops::FilterType, ImageFormat};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tauri::http::{Response, StatusCode};
+use tauri::{AppHandle, Manager, State};
+
+use crate::error::LauncherError;
+use crate::persist::write_atomic;
+use crate::{save_versions, VersionState};
+
+pub const THUMBNAIL_SCHEME: &str = "thumb";
+const SPLASH_RELATIVE: &str = "FortniteGame/Content/Splash/Splash.bmp";
+const THUMBNAILS_DIR: &str = "thumbnails";
+const OPTIONS_FILE: &str = "thumbnails.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ThumbnailFormat {
+    Png,
+    Webp,
+}
+
+impl ThumbnailFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            ThumbnailFormat::Png => "png",
+            ThumbnailFormat::Webp => "webp",
+        }
+    }
+
+    fn image_format(self) -> ImageFormat {
+        match self {
+            ThumbnailFormat::Png => ImageFormat::Png,
+            ThumbnailFormat::Webp => ImageFormat::WebP,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ThumbnailOptions {
+    pub max_width: u32,
+    pub max_height: u32,
+    pub format: ThumbnailFormat,
+}
+
+impl Default for ThumbnailOptions {
+    fn default() -> Self {
+        Self {
+            max_width: 640,
+            max_height: 360,
+            format: ThumbnailFormat::Png,
+        }
+    }
+}
+
+impl ThumbnailOptions {
+    fn validate(&self) -> Result<(), LauncherError> {
+        if self.max_width == 0 || self.max_height == 0 {
+            return Err(LauncherError::InvalidInput("Thumbnail size must be greater than zero".to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// The saved options, or the defaults when there are none or they don't
+/// make sense.
+fn load_options(path: &Path) -> ThumbnailOptions {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str::<ThumbnailOptions>(&content).ok())
+        .filter(|options| options.validate().is_ok())
+        .unwrap_or_default()
+}
+
+fn save_options(path: &Path, options: &ThumbnailOptions) -> Result<(), LauncherError> {
+    let content = serde_json::to_string_pretty(options)?;
+    write_atomic(path, content).map_err(|e| LauncherError::io("Failed to save thumbnail options", e))
+}
+
+/// Content-addressed store of downscaled splash images under
+/// `app_data_dir/thumbnails`. Keys are `<sha256>.<ext>` where the hash covers
+/// the source bytes and the options used, so a key never changes meaning.
+pub struct ThumbnailCache {
+    dir: PathBuf,
+    options: Mutex<ThumbnailOptions>,
+    /// Keys stored for builds that aren't in the library yet, with how many
+    /// pins each has. Garbage collection leaves them alone.
+    pinned: Mutex<HashMap<String, usize>>,
+}
+
+/// Keeps a freshly stored thumbnail out of garbage collection until the
+/// version that uses it has been saved.
+pub struct ThumbnailPin {
+    cache: Arc<ThumbnailCache>,
+    key: String,
+}
+
+impl ThumbnailPin {
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+}
+
+impl Drop for ThumbnailPin {
+    fn drop(&mut self) {
+        let mut pinned = self.cache.pinned.lock().unwrap();
+        if let Some(count) = pinned.get_mut(&self.key) {
+            *count -= 1;
+            if *count == 0 {
+                pinned.remove(&self.key);
+            }
+        }
+    }
+}
+
+impl ThumbnailCache {
+    pub fn new(dir: PathBuf, options: ThumbnailOptions) -> Self {
+        Self {
+            dir,
+            options: Mutex::new(options),
+            pinned: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The cache under `app_data_dir/thumbnails`, with the options last set.
+    pub fn load(app_handle: &AppHandle) -> Self {
+        let data_dir = app_handle.path().app_data_dir().unwrap();
+        Self::new(data_dir.join(THUMBNAILS_DIR), load_options(&data_dir.join(OPTIONS_FILE)))
+    }
+
+    pub fn options(&self) -> ThumbnailOptions {
+        *self.options.lock().unwrap()
+    }
+
+    /// Caches a thumbnail of `source`, pinned until the returned guard is
+    /// dropped.
+    pub fn store(self: &Arc<Self>, source: &[u8]) -> Result<ThumbnailPin, LauncherError> {
+        let options = self.options();
+
+        let mut hasher = Sha256::new();
+        hasher.update(source);
+        hasher.update(format!("{}x{}", options.max_width, options.max_height).as_bytes());
+        hasher.update(options.format.extension().as_bytes());
+        let key = format!("{}.{}", hex::encode(hasher.finalize()), options.format.extension());
+
+        // Pinned before the file is looked at, so a collection running now
+        // either finishes first or leaves it be.
+        *self.pinned.lock().unwrap().entry(key.clone()).or_insert(0) += 1;
+        let pin = ThumbnailPin {
+            cache: self.clone(),
+            key,
+        };
+
+        let path = self.dir.join(pin.key());
+        if path.exists() {
+            return Ok(pin);
+        }
+
+        let image = image::load_from_memory(source)
+            .map_err(|e| LauncherError::CorruptBuild(format!("Failed to decode splash image: {}", e)))?;
+        let thumbnail = image.resize(options.max_width, options.max_height, FilterType::Triangle);
+
+        let mut encoded = Vec::new();
+        thumbnail
+            .write_to(&mut Cursor::new(&mut encoded), options.format.image_format())
+            .map_err(|e| LauncherError::Internal(format!("Failed to encode thumbnail: {}", e)))?;
+
+        let failed = |e| LauncherError::io("Failed to cache thumbnail", e);
+        fs::create_dir_all(&self.dir).map_err(failed)?;
+        let temp_path = self.dir.join(format!("{}.tmp", pin.key()));
+        fs::write(&temp_path, encoded).map_err(failed)?;
+        fs::rename(&temp_path, &path).map_err(failed)?;
+
+        Ok(pin)
+    }
+
+    fn path_for(&self, key: &str) -> Option<PathBuf> {
+        let (hash, extension) = key.split_once('.')?;
+        let valid_hash = hash.len() == 64 && hash.chars().all(|c| c.is_ascii_hexdigit());
+        let valid_extension = extension == "png" || extension == "webp";
+        if valid_hash && valid_extension {
+            Some(self.dir.join(key))
+        } else {
+            None
+        }
+    }
+
+    /// Deletes every cached image whose key is neither in `live_keys` nor
+    /// pinned and returns how many were removed.
+    pub fn collect_garbage(&self, live_keys: &HashSet<String>) -> usize {
+        // Held throughout, so nothing gets pinned halfway through.
+        let pinned = self.pinned.lock().unwrap();
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(_) => return 0,
+        };
+
+        let mut removed = 0;
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if live_keys.contains(&name) || pinned.contains_key(&name) || name.ends_with(".tmp") {
+                continue;
+            }
+            if fs::remove_file(entry.path()).is_ok() {
+                removed += 1;
+            }
+        }
+        removed
+    }
+}
+
+/// URL the webview loads a cached thumbnail from. Windows and Android serve
+/// custom schemes over `http://<scheme>.localhost`.
+pub fn thumbnail_url(key: &str) -> String {
+    if cfg!(any(windows, target_os = "android")) {
+        format!("http://{}.localhost/{}", THUMBNAIL_SCHEME, key)
+    } else {
+        format!("{}://localhost/{}", THUMBNAIL_SCHEME, key)
+    }
+}
+
+pub fn handle_thumbnail_request(app_handle: &AppHandle, uri_path: &str) -> Response<Vec<u8>> {
+    let cache = app_handle.state::<Arc<ThumbnailCache>>();
+    let key = uri_path.trim_start_matches('/');
+
+    let found = cache
+        .path_for(key)
+        .and_then(|path| fs::read(path).ok());
+
+    match found {
+        Some(bytes) => {
+            let content_type = if key.ends_with(".webp") { "image/webp" } else { "image/png" };
+            Response::builder()
+                .header("Content-Type", content_type)
+                .header("Cache-Control", "max-age=31536000, immutable")
+                .body(bytes)
+                .unwrap()
+        }
+        None => Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Vec::new())
+            .unwrap(),
+    }
+}
+
+pub fn splash_path(build_path: &str) -> PathBuf {
+    Path::new(build_path).join(SPLASH_RELATIVE)
+}
+
+/// Drops cache entries no stored version points at. The versions stay
+/// locked until it is done, so a version saved meanwhile can't lose its
+/// thumbnail.
+pub fn collect_thumbnail_garbage(app_handle: &AppHandle) -> usize {
+    let state = app_handle.state::<VersionState>();
+    let versions = state.0.lock().unwrap();
+    let live_keys: HashSet<String> = versions.values().filter_map(|v| v.splash_key.clone()).collect();
+    app_handle.state::<Arc<ThumbnailCache>>().collect_garbage(&live_keys)
+}
+
+/// Regenerates thumbnails for stored versions that have no cache key yet,
+/// e.g. right after migrating a versions.json that held inline BMPs.
+pub async fn backfill_thumbnails(app_handle: AppHandle) {
+    let missing: Vec<String> = {
+        let state = app_handle.state::<VersionState>();
+        let versions = state.0.lock().unwrap();
+        versions
+            .values()
+            .filter(|v| v.splash_key.is_none())
+            .map(|v| v.path.clone())
+            .collect()
+    };
+
+    let cache = app_handle.state::<Arc<ThumbnailCache>>().inner().clone();
+    let mut generated = Vec::new();
+    for path in missing {
+        let source = match tokio::fs::read(splash_path(&path)).await {
+            Ok(source) => source,
+            Err(e) => {
+                println!("No splash for {}: {}", path, e);
+                continue;
+            }
+        };
+        let cache = cache.clone();
+        match tokio::task::spawn_blocking(move || cache.store(&source)).await {
+            Ok(Ok(pin)) => generated.push((path, pin)),
+            Ok(Err(e)) => println!("Failed to create thumbnail for {}: {}", path, e),
+            Err(e) => println!("Thumbnail task for {} failed: {}", path, e),
+        }
+    }
+
+    if !generated.is_empty() {
+        let state = app_handle.state::<VersionState>();
+        let mut versions = state.0.lock().unwrap();
+        for (path, pin) in &generated {
+            if let Some(version) = versions.get_mut(path) {
+                version.splash_key = Some(pin.key().to_string());
+            }
+        }
+        if let Err(e) = save_versions(&versions, &app_handle) {
+            println!("Failed to save backfilled thumbnails: {}", e);
+        }
+    }
+
+    let removed = collect_thumbnail_garbage(&app_handle);
+    if removed > 0 {
+        println!("Removed {} unused thumbnails", removed);
+    }
+}
+
+#[tauri::command]
+pub async fn set_thumbnail_options(
+    options: ThumbnailOptions,
+    cache: State<'_, Arc<ThumbnailCache>>,
+    app_handle: AppHandle,
+) -> Result<(), LauncherError> {
+    options.validate()?;
+    save_options(&app_handle.path().app_data_dir().unwrap().join(OPTIONS_FILE), &options)?;
+    *cache.options.lock().unwrap() = options;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fs::TempDir;
+
+    /// A small BMP, like the splash images builds ship.
+    fn splash(shade: u8) -> Vec<u8> {
+        let image = image::RgbImage::from_pixel(32, 18, image::Rgb([shade, shade, shade]));
+        let mut bytes = Vec::new();
+        image.write_to(&mut Cursor::new(&mut bytes), ImageFormat::Bmp).unwrap();
+        bytes
+    }
+
+    fn cache_in(dir: &Path, max_width: u32, max_height: u32, format: ThumbnailFormat) -> Arc<ThumbnailCache> {
+        let options = ThumbnailOptions {
+            max_width,
+            max_height,
+            format,
+        };
+        Arc::new(ThumbnailCache::new(dir.join(THUMBNAILS_DIR), options))
+    }
+
+    #[test]
+    fn stores_a_downscaled_copy_under_its_hash() {
+        let dir = TempDir::new("thumbnails", "store");
+        let cache = cache_in(&dir, 16, 16, ThumbnailFormat::Png);
+
+        let pin = cache.store(&splash(10)).unwrap();
+        let (hash, extension) = pin.key().split_once('.').unwrap();
+        assert_eq!((hash.len(), extension), (64, "png"));
+        let stored = image::open(cache.path_for(pin.key()).unwrap()).unwrap();
+        assert_eq!((stored.width(), stored.height()), (16, 9));
+
+        assert_eq!(cache.store(&splash(10)).unwrap().key(), pin.key());
+        assert_ne!(cache.store(&splash(200)).unwrap().key(), pin.key());
+    }
+
+    #[test]
+    fn different_options_get_different_keys() {
+        let dir = TempDir::new("thumbnails", "options");
+        let small = cache_in(&dir, 16, 16, ThumbnailFormat::Png).store(&splash(10)).unwrap();
+        let large = cache_in(&dir, 24, 24, ThumbnailFormat::Png).store(&splash(10)).unwrap();
+        let webp = cache_in(&dir, 16, 16, ThumbnailFormat::Webp).store(&splash(10)).unwrap();
+
+        assert_ne!(small.key(), large.key());
+        assert!(webp.key().ends_with(".webp"));
+        assert_eq!(fs::read_dir(dir.join(THUMBNAILS_DIR)).unwrap().count(), 3);
+    }
+
+    #[test]
+    fn an_undecodable_splash_caches_nothing() {
+        let dir = TempDir::new("thumbnails", "corrupt");
+        let cache = cache_in(&dir, 16, 16, ThumbnailFormat::Png);
+
+        assert!(matches!(cache.store(b"not an image"), Err(LauncherError::CorruptBuild(_))));
+        assert!(!dir.join(THUMBNAILS_DIR).exists());
+        assert!(cache.pinned.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn only_well_formed_keys_map_to_paths() {
+        let dir = TempDir::new("thumbnails", "keys");
+        let cache = cache_in(&dir, 16, 16, ThumbnailFormat::Png);
+        let hash = "ab".repeat(32);
+
+        for key in [format!("{}.png", hash), format!("{}.webp", hash.to_uppercase())] {
+            assert_eq!(cache.path_for(&key), Some(dir.join(THUMBNAILS_DIR).join(&key)), "{}", key);
+        }
+        for key in [
+            hash.clone(),
+            format!("{}.bmp", hash),
+            format!("{}.png", &hash[1..]),
+            format!("{}g.png", &hash[1..]),
+            format!("{}.png.png", hash),
+            "../versions.json".to_string(),
+            format!("../{}.png", &hash[3..]),
+            String::new(),
+        ] {
+            assert_eq!(cache.path_for(&key), None, "{}", key);
+        }
+    }
+
+    #[test]
+    fn collection_keeps_live_keys_and_partial_writes() {
+        let dir = TempDir::new("thumbnails", "collect");
+        let cache = cache_in(&dir, 16, 16, ThumbnailFormat::Png);
+        let live = cache.store(&splash(10)).unwrap().key().to_string();
+        let dead = cache.store(&splash(200)).unwrap().key().to_string();
+        let partial = format!("{}.tmp", dead);
+        fs::write(dir.join(THUMBNAILS_DIR).join(&partial), "").unwrap();
+
+        assert_eq!(cache.collect_garbage(&HashSet::from([live.clone()])), 1);
+        let thumbnails = dir.join(THUMBNAILS_DIR);
+        assert!(thumbnails.join(&live).exists());
+        assert!(!thumbnails.join(&dead).exists());
+        assert!(thumbnails.join(&partial).exists());
+    }
+
+    #[test]
+    fn collecting_without_a_cache_folder_removes_nothing() {
+        let dir = TempDir::new("thumbnails", "empty");
+        assert_eq!(cache_in(&dir, 16, 16, ThumbnailFormat::Png).collect_garbage(&HashSet::new()), 0);
+    }
+
+    #[test]
+    fn pinned_thumbnails_survive_collection_until_released() {
+        let dir = TempDir::new("thumbnails", "pinned");
+        let cache = cache_in(&dir, 16, 16, ThumbnailFormat::Png);
+        let first = cache.store(&splash(10)).unwrap();
+        let again = cache.store(&splash(10)).unwrap();
+        assert_eq!(first.key(), again.key());
+        let key = first.key().to_string();
+
+        assert_eq!(cache.collect_garbage(&HashSet::new()), 0);
+        drop(first);
+        assert_eq!(cache.collect_garbage(&HashSet::new()), 0);
+        drop(again);
+        assert_eq!(cache.collect_garbage(&HashSet::new()), 1);
+        assert!(!dir.join(THUMBNAILS_DIR).join(&key).exists());
+    }
+
+    #[test]
+    fn options_survive_a_restart() {
+        let dir = TempDir::new("thumbnails", "saved-options");
+        let path = dir.join(OPTIONS_FILE);
+        assert_eq!(load_options(&path).max_width, ThumbnailOptions::default().max_width);
+
+        let options = ThumbnailOptions {
+            max_width: 320,
+            max_height: 180,
+            format: ThumbnailFormat::Webp,
+        };
+        save_options(&path, &options).unwrap();
+        let loaded = load_options(&path);
+        assert_eq!((loaded.max_width, loaded.max_height, loaded.format), (320, 180, ThumbnailFormat::Webp));
+    }
+
+    #[test]
+    fn unusable_saved_options_fall_back_to_the_defaults() {
+        let dir = TempDir::new("thumbnails", "bad-options");
+        let path = dir.join(OPTIONS_FILE);
+        let defaults = ThumbnailOptions::default();
+
+        for content in ["not json", r#"{"max_width":0,"max_height":180,"format":"png"}"#, r#"{"format":"gif"}"#] {
+            fs::write(&path, content).unwrap();
+            let loaded = load_options(&path);
+            assert_eq!((loaded.max_width, loaded.max_height), (defaults.max_width, defaults.max_height), "{}", content);
+        }
+    }
+}
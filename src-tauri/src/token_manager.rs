@@ -0,0 +1,498 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine as _;
+use reqwest::{Client, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::accounts::active_account;
+use crate::error::LauncherError;
+use crate::offline::unix_now;
+use crate::token_store::TokenState;
+use crate::StoredToken;
+
+pub const REFRESH_URL: &str = "your_refresh_url";
+pub const AUTH_STATE_EVENT: &str = "auth-state-changed";
+// Tokens are refreshed this long before they expire.
+const REFRESH_MARGIN: u64 = 5 * 60;
+// How often the background task looks at a token it can't refresh yet, so a
+// login or a changed token is picked up without waking on every call.
+const CHECK_INTERVAL: Duration = Duration::from_secs(5 * 60);
+const REFRESH_ATTEMPTS: u32 = 4;
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(2);
+const REFRESH_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthState {
+    LoggedIn,
+    Refreshing,
+    /// There is a token, but it has expired. It stays expired until a refresh
+    /// succeeds, if it can be refreshed at all.
+    Expired,
+    LoggedOut,
+}
+
+/// Sent with every `auth-state-changed` event.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AuthStatus {
+    pub state: AuthState,
+    pub account_id: Option<String>,
+    /// Seconds since the epoch, when it is known.
+    pub expires_at: Option<u64>,
+}
+
+impl AuthStatus {
+    fn of(account: Option<&str>, stored: Option<&StoredToken>, now: u64) -> Self {
+        let (Some(account), Some(stored)) = (account, stored) else {
+            return AuthStatus {
+                state: AuthState::LoggedOut,
+                account_id: None,
+                expires_at: None,
+            };
+        };
+        let expires_at = token_expiry(stored);
+        let state = if is_expired(stored, now) {
+            AuthState::Expired
+        } else {
+            AuthState::LoggedIn
+        };
+        AuthStatus {
+            state,
+            account_id: Some(account.to_string()),
+            expires_at,
+        }
+    }
+}
+
+/// When a JWT's `exp` claim says it expires, if it is one.
+fn jwt_expiry(token: &str) -> Option<u64> {
+    let payload = token.split('.').nth(1)?;
+    let claims: serde_json::Value = serde_json::from_slice(&URL_SAFE_NO_PAD.decode(payload).ok()?).ok()?;
+    claims.get("exp")?.as_u64()
+}
+
+/// When `stored` expires, going by what the backend said or else the token
+/// itself. `None` for tokens that don't say.
+pub fn token_expiry(stored: &StoredToken) -> Option<u64> {
+    stored.expires_at.or_else(|| jwt_expiry(&stored.token))
+}
+
+fn is_expired(stored: &StoredToken, now: u64) -> bool {
+    token_expiry(stored).is_some_and(|expires_at| expires_at <= now)
+}
+
+/// Whether `stored` should be refreshed at `now`. Tokens without an expiry
+/// or a refresh token are left alone.
+pub fn refresh_due(stored: &StoredToken, now: u64) -> bool {
+    stored.refresh_token.is_some() && token_expiry(stored).is_some_and(|expires_at| now + REFRESH_MARGIN >= expires_at)
+}
+
+/// What the backend answers with whenever it hands out a token.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenResponse {
+    pub launcher_token: String,
+    /// Present on login, and on refresh when the backend rotates them.
+    #[serde(default)]
+    pub refresh_token: Option<String>,
+    /// Seconds from now.
+    #[serde(default)]
+    pub expires_in: Option<u64>,
+}
+
+impl TokenResponse {
+    pub fn into_stored(self, now: u64) -> StoredToken {
+        StoredToken {
+            token: self.launcher_token,
+            stored_at: now,
+            refresh_token: self.refresh_token,
+            expires_at: self.expires_in.map(|seconds| now + seconds),
+        }
+    }
+}
+
+/// Trades `refresh_token` for a new token at `url`.
+pub async fn request_refresh(client: &Client, url: &str, refresh_token: &str) -> Result<TokenResponse, LauncherError> {
+    let response = client
+        .post(url)
+        .json(&serde_json::json!({ "refreshToken": refresh_token }))
+        .timeout(REFRESH_TIMEOUT)
+        .send()
+        .await?;
+    match response.status() {
+        StatusCode::BAD_REQUEST | StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+            return Err(LauncherError::AuthExpired(
+                "Your login has expired. Log in again to continue.".to_string(),
+            ))
+        }
+        status if !status.is_success() => return Err(LauncherError::api(status)),
+        _ => {}
+    }
+    Ok(response.json().await?)
+}
+
+/// Whether trying again later could make `error` go away.
+fn retryable(error: &LauncherError) -> bool {
+    match error {
+        LauncherError::NetworkDown(_) | LauncherError::Http(_) => true,
+        LauncherError::Api { status, .. } => *status >= 500 || *status == 429,
+        _ => false,
+    }
+}
+
+/// Runs `attempt` up to `attempts` times, doubling the wait after each
+/// failure that might go away on its own.
+pub async fn with_backoff<T, F, Fut>(attempts: u32, base_delay: Duration, mut attempt: F) -> Result<T, LauncherError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, LauncherError>>,
+{
+    let mut delay = base_delay;
+    let mut tries = 1;
+    loop {
+        match attempt().await {
+            Err(e) if tries < attempts && retryable(&e) => {
+                println!("Attempt {} failed, retrying in {:?}: {}", tries, delay, e);
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+                tries += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Owns the active account's token lifecycle: knows when it expires,
+/// refreshes it ahead of time and tells the webview whenever the login state
+/// changes. Anything that calls the backend as the user gets its token from
+/// here.
+pub struct TokenManager {
+    status: Mutex<AuthStatus>,
+    // Held for the whole refresh so concurrent callers wait for one refresh
+    // instead of each spending the refresh token.
+    refreshing: tokio::sync::Mutex<()>,
+}
+
+/// The active account and its token, if anyone is logged in.
+pub fn active_token(app_handle: &AppHandle) -> Option<(String, StoredToken)> {
+    let account = active_account(app_handle)?;
+    let stored = app_handle.state::<TokenState>().get(&account)?;
+    Some((account, stored))
+}
+
+fn current_status(app_handle: &AppHandle) -> AuthStatus {
+    match active_token(app_handle) {
+        Some((account, stored)) => AuthStatus::of(Some(&account), Some(&stored), unix_now()),
+        None => AuthStatus::of(None, None, unix_now()),
+    }
+}
+
+impl TokenManager {
+    pub fn load(app_handle: &AppHandle) -> Self {
+        TokenManager {
+            status: Mutex::new(current_status(app_handle)),
+            refreshing: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    pub fn status(&self) -> AuthStatus {
+        self.status.lock().unwrap().clone()
+    }
+
+    fn set_status(&self, app_handle: &AppHandle, status: AuthStatus) {
+        let mut current = self.status.lock().unwrap();
+        if *current == status {
+            return;
+        }
+        *current = status.clone();
+        drop(current);
+        println!("Auth state changed to {:?}", status.state);
+        if let Err(e) = app_handle.emit(AUTH_STATE_EVENT, &status) {
+            println!("Failed to emit {}: {}", AUTH_STATE_EVENT, e);
+        }
+    }
+
+    fn set_state(&self, app_handle: &AppHandle, account: &str, state: AuthState, expires_at: Option<u64>) {
+        self.set_status(
+            app_handle,
+            AuthStatus {
+                state,
+                account_id: Some(account.to_string()),
+                expires_at,
+            },
+        );
+    }
+
+    /// Catches up with a login, logout or account switch.
+    pub fn sync(&self, app_handle: &AppHandle) {
+        self.set_status(app_handle, current_status(app_handle));
+    }
+
+    /// A token to call the backend with, refreshed first if it is about to
+    /// expire. A token that is still valid is used even if refreshing it
+    /// failed.
+    pub async fn bearer(&self, app_handle: &AppHandle) -> Result<String, LauncherError> {
+        let Some((account, stored)) = active_token(app_handle) else {
+            self.sync(app_handle);
+            return Err(LauncherError::AuthExpired("Log in to continue.".to_string()));
+        };
+        if refresh_due(&stored, unix_now()) {
+            match self.refresh(app_handle).await {
+                Ok(refreshed) => return Ok(refreshed.token),
+                Err(e) if !is_expired(&stored, unix_now()) => {
+                    println!("Using the current token, refreshing it failed: {}", e)
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        if is_expired(&stored, unix_now()) {
+            self.set_state(app_handle, &account, AuthState::Expired, token_expiry(&stored));
+            return Err(LauncherError::AuthExpired(
+                "Your login has expired. Log in again to continue.".to_string(),
+            ));
+        }
+        Ok(stored.token)
+    }
+
+    /// Refreshes the active account's token if it is due, retrying with
+    /// backoff while the backend is unreachable.
+    pub async fn refresh(&self, app_handle: &AppHandle) -> Result<StoredToken, LauncherError> {
+        let _refreshing = self.refreshing.lock().await;
+        let tokens = app_handle.state::<TokenState>();
+        // Whoever held the lock before may have refreshed it already.
+        let Some((account, stored)) = active_token(app_handle) else {
+            self.sync(app_handle);
+            return Err(LauncherError::AuthExpired("Log in to continue.".to_string()));
+        };
+        let Some(refresh_token) = stored
+            .refresh_token
+            .clone()
+            .filter(|_| refresh_due(&stored, unix_now()))
+        else {
+            return Ok(stored);
+        };
+
+        let expires_at = token_expiry(&stored);
+        self.set_state(app_handle, &account, AuthState::Refreshing, expires_at);
+        let client = Client::new();
+        let result = with_backoff(REFRESH_ATTEMPTS, RETRY_BASE_DELAY, || {
+            request_refresh(&client, REFRESH_URL, &refresh_token)
+        })
+        .await;
+
+        let refreshed = match result {
+            Ok(refreshed) => refreshed,
+            Err(e) => {
+                // A rejected refresh token won't work later either; the
+                // current token is all there is until it runs out.
+                if let LauncherError::AuthExpired(_) = e {
+                    let kept = StoredToken {
+                        refresh_token: None,
+                        ..stored.clone()
+                    };
+                    if let Err(e) = tokens.save(&account, &kept) {
+                        println!("Failed to drop the rejected refresh token: {}", e);
+                    }
+                }
+                if is_expired(&stored, unix_now()) {
+                    self.set_state(app_handle, &account, AuthState::Expired, expires_at);
+                } else {
+                    self.sync(app_handle);
+                }
+                return Err(e);
+            }
+        };
+
+        let mut renewed = refreshed.into_stored(unix_now());
+        renewed.refresh_token.get_or_insert(refresh_token);
+        tokens.save(&account, &renewed)?;
+        self.sync(app_handle);
+        Ok(renewed)
+    }
+
+    /// How long the background task should sleep before looking again.
+    fn next_check(&self, app_handle: &AppHandle) -> Duration {
+        let Some((_, stored)) = active_token(app_handle) else {
+            return CHECK_INTERVAL;
+        };
+        match (&stored.refresh_token, token_expiry(&stored)) {
+            (Some(_), Some(expires_at)) => {
+                let due_in = expires_at.saturating_sub(REFRESH_MARGIN).saturating_sub(unix_now());
+                Duration::from_secs(due_in).min(CHECK_INTERVAL)
+            }
+            _ => CHECK_INTERVAL,
+        }
+    }
+}
+
+/// Keeps the active account's token fresh for as long as the launcher runs,
+/// so it doesn't expire while nothing is calling the backend.
+pub async fn refresh_loop(app_handle: AppHandle) {
+    loop {
+        let manager = app_handle.state::<TokenManager>();
+        tokio::time::sleep(manager.next_check(&app_handle)).await;
+        if manager.status().state == AuthState::LoggedOut {
+            continue;
+        }
+        if let Err(e) = manager.refresh(&app_handle).await {
+            println!("Background token refresh failed: {}", e);
+            // Don't spin on a refresh that keeps failing.
+            tokio::time::sleep(CHECK_INTERVAL).await;
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn get_auth_state(manager: State<'_, TokenManager>) -> Result<AuthStatus, LauncherError> {
+    Ok(manager.status())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_http::{Response, TestServer};
+    use serde_json::json;
+    use std::cell::Cell;
+
+    const NOW: u64 = 1_700_000_000;
+
+    fn jwt(claims: serde_json::Value) -> String {
+        format!("e30.{}.signature", URL_SAFE_NO_PAD.encode(claims.to_string()))
+    }
+
+    fn stored(expires_at: Option<u64>, refresh_token: Option<&str>) -> StoredToken {
+        StoredToken {
+            token: "opaque".to_string(),
+            stored_at: NOW - 60,
+            refresh_token: refresh_token.map(str::to_string),
+            expires_at,
+        }
+    }
+
+    #[test]
+    fn reads_the_expiry_from_a_jwt() {
+        assert_eq!(jwt_expiry(&jwt(json!({ "exp": NOW, "sub": "account" }))), Some(NOW));
+        assert_eq!(jwt_expiry(&jwt(json!({ "sub": "account" }))), None);
+        assert_eq!(jwt_expiry(&jwt(json!({ "exp": "soon" }))), None);
+        assert_eq!(jwt_expiry("opaque"), None);
+        assert_eq!(jwt_expiry("a.not base64!.c"), None);
+
+        let token = StoredToken {
+            token: jwt(json!({ "exp": NOW })),
+            ..stored(None, None)
+        };
+        assert_eq!(token_expiry(&token), Some(NOW));
+        // What the backend said wins over the token.
+        let token = StoredToken {
+            expires_at: Some(NOW + 10),
+            ..token
+        };
+        assert_eq!(token_expiry(&token), Some(NOW + 10));
+    }
+
+    #[test]
+    fn refreshes_within_the_margin_only() {
+        assert!(!refresh_due(&stored(Some(NOW + REFRESH_MARGIN + 1), Some("refresh")), NOW));
+        assert!(refresh_due(&stored(Some(NOW + REFRESH_MARGIN), Some("refresh")), NOW));
+        assert!(refresh_due(&stored(Some(NOW - 1), Some("refresh")), NOW));
+        assert!(!refresh_due(&stored(Some(NOW - 1), None), NOW));
+        assert!(!refresh_due(&stored(None, Some("refresh")), NOW));
+    }
+
+    #[test]
+    fn status_follows_the_token() {
+        let status = |stored: Option<&StoredToken>| AuthStatus::of(Some("account"), stored, NOW);
+
+        assert_eq!(AuthStatus::of(None, None, NOW).state, AuthState::LoggedOut);
+        assert_eq!(AuthStatus::of(Some("account"), None, NOW).state, AuthState::LoggedOut);
+        assert_eq!(status(Some(&stored(None, None))).state, AuthState::LoggedIn);
+        assert_eq!(status(Some(&stored(Some(NOW + 60), None))).state, AuthState::LoggedIn);
+        assert_eq!(status(Some(&stored(Some(NOW), None))).state, AuthState::Expired);
+
+        // A refresh token doesn't make an expired token good again.
+        let expired = status(Some(&stored(Some(NOW - 1), Some("refresh"))));
+        assert_eq!(expired.state, AuthState::Expired);
+        assert_eq!(expired.account_id.as_deref(), Some("account"));
+        assert_eq!(expired.expires_at, Some(NOW - 1));
+    }
+
+    #[tokio::test]
+    async fn retries_only_what_might_go_away() {
+        let delay = Duration::from_millis(1);
+        let calls = Cell::new(0);
+        let result: Result<(), LauncherError> = with_backoff(3, delay, || {
+            calls.set(calls.get() + 1);
+            async { Err(LauncherError::NetworkDown("offline".to_string())) }
+        })
+        .await;
+        assert!(matches!(result, Err(LauncherError::NetworkDown(_))));
+        assert_eq!(calls.get(), 3);
+
+        calls.set(0);
+        let result = with_backoff(3, delay, || {
+            calls.set(calls.get() + 1);
+            let attempt = calls.get();
+            async move {
+                match attempt {
+                    1 => Err(LauncherError::api(StatusCode::SERVICE_UNAVAILABLE)),
+                    2 => Err(LauncherError::api(StatusCode::TOO_MANY_REQUESTS)),
+                    _ => Ok(attempt),
+                }
+            }
+        })
+        .await;
+        assert_eq!(result.unwrap(), 3);
+
+        for error in [
+            LauncherError::AuthExpired("rejected".to_string()),
+            LauncherError::api(StatusCode::NOT_FOUND),
+        ] {
+            calls.set(0);
+            let mut error = Some(error);
+            let result: Result<(), LauncherError> = with_backoff(3, delay, || {
+                calls.set(calls.get() + 1);
+                let error = error.take().unwrap();
+                async move { Err(error) }
+            })
+            .await;
+            assert!(result.is_err());
+            assert_eq!(calls.get(), 1);
+        }
+    }
+
+    async fn refresh_at(server: &TestServer, path: &str) -> Result<TokenResponse, LauncherError> {
+        request_refresh(&Client::new(), &format!("{}{}", server.url, path), "refresh-1").await
+    }
+
+    #[tokio::test]
+    async fn trades_the_refresh_token_for_a_new_one() {
+        let server = TestServer::start(|request| match request.path.as_str() {
+            "/refresh" => Response::json(200, json!({ "launcherToken": "new", "expiresIn": 3600 })),
+            "/rejected" => Response::new(401, ""),
+            "/invalid" => Response::new(400, ""),
+            _ => Response::new(502, ""),
+        })
+        .await;
+        let refreshed = refresh_at(&server, "/refresh").await.unwrap();
+        assert_eq!(refreshed.launcher_token, "new");
+        assert_eq!(refreshed.refresh_token, None);
+        let renewed = refreshed.into_stored(NOW);
+        assert_eq!(renewed.expires_at, Some(NOW + 3600));
+        let requests = server.requests();
+        assert_eq!(requests[0].method, "POST");
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(&requests[0].body).unwrap(),
+            json!({ "refreshToken": "refresh-1" })
+        );
+
+        assert!(matches!(refresh_at(&server, "/rejected").await, Err(LauncherError::AuthExpired(_))));
+        assert!(matches!(refresh_at(&server, "/invalid").await, Err(LauncherError::AuthExpired(_))));
+        assert!(matches!(
+            refresh_at(&server, "/down").await,
+            Err(LauncherError::Api { status: 502, .. })
+        ));
+    }
+}
@@ -0,0 +1,487 @@
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use tauri::{AppHandle, Manager};
+
+use crate::error::LauncherError;
+use crate::offline::unix_now;
+use crate::persist::write_atomic;
+use crate::{get_token_path, StoredToken};
+
+const KEYRING_SERVICE: &str = "Rewind";
+const TOKEN_ENTRY: &str = "launcherToken";
+const ENCRYPTED_TOKEN_FILE: &str = "token.enc";
+const ENCRYPTED_TOKENS_DIR: &str = "tokens";
+const ENCRYPTED_FILE_VERSION: u32 = 1;
+// Keeps keys derived here from matching a hash of the machine id made for
+// anything else.
+const KEY_CONTEXT: &[u8] = b"rewind-launcher token key v1";
+
+/// Named secrets somewhere outside our own files.
+pub trait SecretStore: Send + Sync {
+    fn get(&self, name: &str) -> Result<Option<String>, LauncherError>;
+    fn set(&self, name: &str, value: &str) -> Result<(), LauncherError>;
+    fn delete(&self, name: &str) -> Result<(), LauncherError>;
+}
+
+/// Secret Service on Linux, Credential Manager on Windows, Keychain on macOS.
+pub struct OsKeyring;
+
+impl OsKeyring {
+    fn entry(name: &str) -> Result<keyring::Entry, LauncherError> {
+        keyring::Entry::new(KEYRING_SERVICE, name).map_err(|e| LauncherError::Keyring(format!("Keyring unavailable: {}", e)))
+    }
+}
+
+impl SecretStore for OsKeyring {
+    fn get(&self, name: &str) -> Result<Option<String>, LauncherError> {
+        match OsKeyring::entry(name)?.get_password() {
+            Ok(value) => Ok(Some(value)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(LauncherError::Keyring(format!("Failed to read {} from the keyring: {}", name, e))),
+        }
+    }
+
+    fn set(&self, name: &str, value: &str) -> Result<(), LauncherError> {
+        OsKeyring::entry(name)?
+            .set_password(value)
+            .map_err(|e| LauncherError::Keyring(format!("Failed to write {} to the keyring: {}", name, e)))
+    }
+
+    fn delete(&self, name: &str) -> Result<(), LauncherError> {
+        match OsKeyring::entry(name)?.delete_password() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(LauncherError::Keyring(format!("Failed to remove {} from the keyring: {}", name, e))),
+        }
+    }
+}
+
+/// Stands in for the OS keyring in tests. An unavailable one fails every
+/// call, the way a Linux box without a Secret Service does.
+#[cfg(test)]
+#[derive(Default)]
+pub struct MemoryKeyring {
+    secrets: std::sync::Mutex<std::collections::HashMap<String, String>>,
+    unavailable: bool,
+}
+
+#[cfg(test)]
+impl MemoryKeyring {
+    pub fn unavailable() -> Self {
+        MemoryKeyring {
+            unavailable: true,
+            ..Default::default()
+        }
+    }
+
+    fn check(&self) -> Result<(), LauncherError> {
+        if self.unavailable {
+            return Err(LauncherError::Keyring("Keyring unavailable".to_string()));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+impl SecretStore for MemoryKeyring {
+    fn get(&self, name: &str) -> Result<Option<String>, LauncherError> {
+        self.check()?;
+        Ok(self.secrets.lock().unwrap().get(name).cloned())
+    }
+
+    fn set(&self, name: &str, value: &str) -> Result<(), LauncherError> {
+        self.check()?;
+        self.secrets.lock().unwrap().insert(name.to_string(), value.to_string());
+        Ok(())
+    }
+
+    fn delete(&self, name: &str) -> Result<(), LauncherError> {
+        self.check()?;
+        self.secrets.lock().unwrap().remove(name);
+        Ok(())
+    }
+}
+
+/// Where versions from before accounts kept their only token. Its account
+/// isn't known until the token has been decoded.
+pub const UNASSIGNED: &str = "";
+
+/// Somewhere to keep each account's launcher token between runs.
+pub trait TokenStore: Send + Sync {
+    fn load(&self, account: &str) -> Result<Option<StoredToken>, LauncherError>;
+    fn save(&self, account: &str, token: &StoredToken) -> Result<(), LauncherError>;
+    fn clear(&self, account: &str) -> Result<(), LauncherError>;
+}
+
+pub struct KeyringTokenStore<K: SecretStore> {
+    keyring: K,
+}
+
+impl<K: SecretStore> KeyringTokenStore<K> {
+    pub fn new(keyring: K) -> Self {
+        KeyringTokenStore { keyring }
+    }
+
+    fn entry_name(account: &str) -> String {
+        if account == UNASSIGNED {
+            return TOKEN_ENTRY.to_string();
+        }
+        format!("{}:{}", TOKEN_ENTRY, account)
+    }
+}
+
+impl<K: SecretStore> TokenStore for KeyringTokenStore<K> {
+    fn load(&self, account: &str) -> Result<Option<StoredToken>, LauncherError> {
+        match self.keyring.get(&Self::entry_name(account))? {
+            Some(content) => serde_json::from_str(&content)
+                .map(Some)
+                .map_err(|e| LauncherError::AuthExpired(format!("Failed to parse stored token: {}", e))),
+            None => Ok(None),
+        }
+    }
+
+    fn save(&self, account: &str, token: &StoredToken) -> Result<(), LauncherError> {
+        let content = serde_json::to_string(token)?;
+        self.keyring.set(&Self::entry_name(account), &content)
+    }
+
+    fn clear(&self, account: &str) -> Result<(), LauncherError> {
+        self.keyring.delete(&Self::entry_name(account))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct EncryptedToken {
+    version: u32,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+/// The token sealed with AES-256-GCM under a key derived from something that
+/// only this machine has. It keeps a copied file from working elsewhere; it
+/// is no match for the keyring against someone already on the machine.
+pub struct EncryptedFileTokenStore {
+    dir: PathBuf,
+    machine_secret: Vec<u8>,
+}
+
+impl EncryptedFileTokenStore {
+    pub fn new(dir: PathBuf, machine_secret: impl Into<Vec<u8>>) -> Self {
+        EncryptedFileTokenStore {
+            dir,
+            machine_secret: machine_secret.into(),
+        }
+    }
+
+    /// Account ids are hex-encoded so any of them makes a safe file name.
+    fn path(&self, account: &str) -> PathBuf {
+        if account == UNASSIGNED {
+            return self.dir.join(ENCRYPTED_TOKEN_FILE);
+        }
+        self.dir
+            .join(ENCRYPTED_TOKENS_DIR)
+            .join(format!("{}.enc", hex::encode(account)))
+    }
+
+    fn key(&self, salt: &[u8]) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(KEY_CONTEXT);
+        hasher.update(salt);
+        hasher.update(&self.machine_secret);
+        hasher.finalize().into()
+    }
+}
+
+impl TokenStore for EncryptedFileTokenStore {
+    fn load(&self, account: &str) -> Result<Option<StoredToken>, LauncherError> {
+        let content = match fs::read_to_string(self.path(account)) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(LauncherError::io("Failed to read stored token", e)),
+        };
+        // A token that can't be read back is as good as no login at all.
+        let sealed: EncryptedToken = serde_json::from_str(&content)
+            .map_err(|e| LauncherError::AuthExpired(format!("Failed to parse stored token: {}", e)))?;
+        if sealed.version != ENCRYPTED_FILE_VERSION {
+            return Err(LauncherError::AuthExpired(format!(
+                "Unknown token file version {}",
+                sealed.version
+            )));
+        }
+
+        let corrupt = || LauncherError::AuthExpired("Stored token is corrupt".to_string());
+        let decode = |field: &str| hex::decode(field).map_err(|_| corrupt());
+        let salt = decode(&sealed.salt)?;
+        let nonce = decode(&sealed.nonce)?;
+        if nonce.len() != 12 {
+            return Err(corrupt());
+        }
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&self.key(&salt)));
+        let plaintext = cipher
+            .decrypt(Nonce::from_slice(&nonce), decode(&sealed.ciphertext)?.as_slice())
+            .map_err(|_| LauncherError::AuthExpired("Stored token can't be decrypted on this machine".to_string()))?;
+        serde_json::from_slice(&plaintext)
+            .map(Some)
+            .map_err(|e| LauncherError::AuthExpired(format!("Failed to parse stored token: {}", e)))
+    }
+
+    fn save(&self, account: &str, token: &StoredToken) -> Result<(), LauncherError> {
+        let plaintext = serde_json::to_vec(token)?;
+        let salt: [u8; 16] = rand_bytes();
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&self.key(&salt)));
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = cipher
+            .encrypt(&nonce, plaintext.as_slice())
+            .map_err(|_| LauncherError::Internal("Failed to encrypt the token".to_string()))?;
+
+        let sealed = EncryptedToken {
+            version: ENCRYPTED_FILE_VERSION,
+            salt: hex::encode(salt),
+            nonce: hex::encode(nonce),
+            ciphertext: hex::encode(ciphertext),
+        };
+        let content = serde_json::to_string(&sealed)?;
+        write_atomic(&self.path(account), content).map_err(|e| LauncherError::io("Failed to store token", e))
+    }
+
+    fn clear(&self, account: &str) -> Result<(), LauncherError> {
+        match fs::remove_file(self.path(account)) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(LauncherError::io("Failed to remove stored token", e)),
+        }
+    }
+}
+
+/// `N` bytes from the OS's secure random source.
+pub fn rand_bytes<const N: usize>() -> [u8; N] {
+    let mut bytes = [0u8; N];
+    aes_gcm::aead::rand_core::RngCore::fill_bytes(&mut OsRng, &mut bytes);
+    bytes
+}
+
+/// The machine's install id. Only used to derive the file key, never
+/// stored. The host name is no substitute: anyone holding the file can guess
+/// it, so without an install id there is no secret rather than a weak one.
+pub fn machine_secret() -> Result<String, LauncherError> {
+    machine_uid::get()
+        .ok()
+        .filter(|secret| !secret.trim().is_empty())
+        .ok_or_else(|| LauncherError::NotFound("No machine id to derive the token key from".to_string()))
+}
+
+/// Uses `primary` while it works and `fallback` when it doesn't. A token
+/// saved to the primary store is removed from the fallback, so the two never
+/// disagree for long.
+pub struct FallbackTokenStore {
+    primary: Box<dyn TokenStore>,
+    fallback: Box<dyn TokenStore>,
+}
+
+impl FallbackTokenStore {
+    pub fn new(primary: Box<dyn TokenStore>, fallback: Box<dyn TokenStore>) -> Self {
+        FallbackTokenStore { primary, fallback }
+    }
+}
+
+impl TokenStore for FallbackTokenStore {
+    fn load(&self, account: &str) -> Result<Option<StoredToken>, LauncherError> {
+        match self.primary.load(account) {
+            Ok(Some(token)) => Ok(Some(token)),
+            Ok(None) => self.fallback.load(account),
+            Err(e) => {
+                println!("{}, trying the token file", e);
+                self.fallback.load(account)
+            }
+        }
+    }
+
+    fn save(&self, account: &str, token: &StoredToken) -> Result<(), LauncherError> {
+        match self.primary.save(account, token) {
+            Ok(()) => {
+                if let Err(e) = self.fallback.clear(account) {
+                    println!("Failed to remove the old token file: {}", e);
+                }
+                Ok(())
+            }
+            Err(e) => {
+                println!("{}, storing the token in the encrypted file", e);
+                self.fallback.save(account, token)
+            }
+        }
+    }
+
+    fn clear(&self, account: &str) -> Result<(), LauncherError> {
+        let primary = self.primary.clear(account);
+        let fallback = self.fallback.clear(account);
+        primary.and(fallback)
+    }
+}
+
+/// Moves a token left in plaintext by older versions into `store`'s
+/// unassigned slot and deletes the plaintext file. The file is kept if the token can't be stored.
+pub fn migrate_plaintext(store: &dyn TokenStore, legacy_path: &Path) -> Result<bool, LauncherError> {
+    let content = match fs::read_to_string(legacy_path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(LauncherError::io("Failed to read the old token file", e)),
+    };
+
+    // The oldest versions wrote the bare token.
+    let token = serde_json::from_str::<StoredToken>(&content).unwrap_or_else(|_| StoredToken {
+        token: content.trim().to_string(),
+        stored_at: fs::metadata(legacy_path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+            .map(|age| age.as_secs())
+            .unwrap_or_else(unix_now),
+        refresh_token: None,
+        expires_at: None,
+    });
+    if !token.token.is_empty() {
+        store.save(UNASSIGNED, &token)?;
+    }
+    fs::remove_file(legacy_path).map_err(|e| LauncherError::io("Failed to remove the old token file", e))?;
+    Ok(true)
+}
+
+pub struct TokenState(Box<dyn TokenStore>);
+
+impl TokenState {
+    /// The keyring with the encrypted file behind it, with any plaintext
+    /// token from an older version moved in. Without a machine secret the
+    /// file would be readable anywhere, so only the keyring is used.
+    pub fn load(app_handle: &AppHandle) -> Self {
+        let dir = app_handle.path().app_data_dir().unwrap();
+        let keyring = Box::new(KeyringTokenStore::new(OsKeyring));
+        let store: Box<dyn TokenStore> = match machine_secret() {
+            Ok(secret) => Box::new(FallbackTokenStore::new(
+                keyring,
+                Box::new(EncryptedFileTokenStore::new(dir, secret)),
+            )),
+            Err(e) => {
+                println!("{}, keeping tokens in the keyring only", e);
+                keyring
+            }
+        };
+        match migrate_plaintext(store.as_ref(), &get_token_path(app_handle)) {
+            Ok(true) => println!("Moved the stored token out of the plaintext file"),
+            Ok(false) => {}
+            Err(e) => println!("Failed to migrate the stored token: {}", e),
+        }
+        TokenState(store)
+    }
+
+    pub fn get(&self, account: &str) -> Option<StoredToken> {
+        self.0
+            .load(account)
+            .map_err(|e| println!("Failed to load the stored token: {}", e))
+            .ok()
+            .flatten()
+    }
+
+    pub fn save(&self, account: &str, token: &StoredToken) -> Result<(), LauncherError> {
+        self.0.save(account, token)
+    }
+
+    pub fn clear(&self, account: &str) -> Result<(), LauncherError> {
+        self.0.clear(account)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fs::TempDir;
+
+    fn stored(token: &str) -> StoredToken {
+        StoredToken {
+            token: token.to_string(),
+            stored_at: 1_700_000_000,
+            refresh_token: Some(format!("{}-refresh", token)),
+            expires_at: None,
+        }
+    }
+
+    fn loaded(store: &dyn TokenStore, account: &str) -> Option<String> {
+        store.load(account).unwrap().map(|stored| stored.token)
+    }
+
+    #[test]
+    fn encrypted_file_round_trips_each_account() {
+        let dir = TempDir::new("tokens", "round-trip");
+        let store = EncryptedFileTokenStore::new(dir.to_path_buf(), "machine-a");
+
+        store.save(UNASSIGNED, &stored("legacy-token")).unwrap();
+        store.save("0123abcd", &stored("account-token")).unwrap();
+
+        assert_eq!(loaded(&store, UNASSIGNED).as_deref(), Some("legacy-token"));
+        assert_eq!(loaded(&store, "0123abcd").as_deref(), Some("account-token"));
+        assert_eq!(loaded(&store, "someone-else"), None);
+        let on_disk = fs::read_to_string(dir.join(ENCRYPTED_TOKEN_FILE)).unwrap();
+        assert!(!on_disk.contains("legacy-token"));
+
+        store.clear("0123abcd").unwrap();
+        assert_eq!(loaded(&store, "0123abcd"), None);
+        store.clear("0123abcd").unwrap();
+    }
+
+    #[test]
+    fn another_machine_cannot_decrypt_the_file() {
+        let dir = TempDir::new("tokens", "other-machine");
+        EncryptedFileTokenStore::new(dir.to_path_buf(), "machine-a")
+            .save(UNASSIGNED, &stored("token"))
+            .unwrap();
+
+        let elsewhere = EncryptedFileTokenStore::new(dir.to_path_buf(), "machine-b");
+        assert!(matches!(elsewhere.load(UNASSIGNED), Err(LauncherError::AuthExpired(_))));
+    }
+
+    #[test]
+    fn falls_back_to_the_file_without_a_keyring() {
+        let dir = TempDir::new("tokens", "fallback");
+        let file = || Box::new(EncryptedFileTokenStore::new(dir.to_path_buf(), "machine-a"));
+
+        let without_keyring =
+            FallbackTokenStore::new(Box::new(KeyringTokenStore::new(MemoryKeyring::unavailable())), file());
+        without_keyring.save("account", &stored("token")).unwrap();
+        assert_eq!(loaded(&without_keyring, "account").as_deref(), Some("token"));
+        assert_eq!(loaded(file().as_ref(), "account").as_deref(), Some("token"));
+
+        // Once the keyring works again the token moves there and the file goes.
+        let with_keyring = FallbackTokenStore::new(Box::new(KeyringTokenStore::new(MemoryKeyring::default())), file());
+        with_keyring.save("account", &stored("renewed")).unwrap();
+        assert_eq!(loaded(&with_keyring, "account").as_deref(), Some("renewed"));
+        assert_eq!(loaded(file().as_ref(), "account"), None);
+    }
+
+    #[test]
+    fn migrates_both_plaintext_formats() {
+        let dir = TempDir::new("tokens", "migrate");
+        let legacy = dir.join("token.json");
+        let store = KeyringTokenStore::new(MemoryKeyring::default());
+
+        assert!(!migrate_plaintext(&store, &legacy).unwrap());
+
+        fs::write(&legacy, serde_json::to_string(&stored("json-token")).unwrap()).unwrap();
+        assert!(migrate_plaintext(&store, &legacy).unwrap());
+        let migrated = store.load(UNASSIGNED).unwrap().unwrap();
+        assert_eq!(migrated.token, "json-token");
+        assert_eq!(migrated.stored_at, 1_700_000_000);
+        assert!(!legacy.exists());
+
+        fs::write(&legacy, "  bare-token\n").unwrap();
+        assert!(migrate_plaintext(&store, &legacy).unwrap());
+        let migrated = store.load(UNASSIGNED).unwrap().unwrap();
+        assert_eq!(migrated.token, "bare-token");
+        assert_eq!(migrated.refresh_token, None);
+        assert!(migrated.stored_at > 1_700_000_000);
+        assert!(!legacy.exists());
+    }
+}
@@ -0,0 +1,188 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::io::{self, Read, Seek, SeekFrom};
+
+const MARKER: &str = "++Fortnite+Release-";
+const CHUNK_SIZE: usize = 1024 * 1024;
+// Bytes decoded after each marker hit. Also the overlap carried between
+// chunks, so a build string split across two reads is still seen whole.
+const WINDOW_SIZE: usize = 200;
+
+lazy_static! {
+    static ref MARKER_BYTES: Vec<u8> = MARKER
+        .encode_utf16()
+        .flat_map(|c| c.to_le_bytes())
+        .collect();
+    static ref RELEASE_RE: Regex =
+        Regex::new(r"\+\+Fortnite\+Release-(\d{1,2}\.\d{1,2}|Live|Next|Cert)-CL-(\d+)").unwrap();
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReleaseInfo {
+    pub release: String,
+    pub cl: String,
+}
+
+/// Scans `reader` from the start for the UTF-16 `++Fortnite+Release-` build
+/// string and returns the first one that parses. Memory use is bounded by
+/// `CHUNK_SIZE + WINDOW_SIZE` regardless of the input size.
+pub fn scan_release_string<R: Read + Seek>(reader: &mut R) -> io::Result<Option<ReleaseInfo>> {
+    reader.seek(SeekFrom::Start(0))?;
+
+    let mut buffer: Vec<u8> = Vec::with_capacity(CHUNK_SIZE + WINDOW_SIZE);
+    let mut chunk = vec![0u8; CHUNK_SIZE];
+
+    loop {
+        let read = read_full(reader, &mut chunk)?;
+        let eof = read < CHUNK_SIZE;
+        buffer.extend_from_slice(&chunk[..read]);
+
+        // Hits inside the trailing overlap are left for the next pass, where
+        // their whole window will be available.
+        let limit = if eof {
+            buffer.len()
+        } else {
+            buffer.len().saturating_sub(WINDOW_SIZE)
+        };
+
+        let mut start = 0;
+        while let Some(offset) = find_marker(&buffer[start..]) {
+            let index = start + offset;
+            if index >= limit {
+                break;
+            }
+            let end = (index + WINDOW_SIZE).min(buffer.len());
+            if let Some(info) = parse_window(&buffer[index..end]) {
+                return Ok(Some(info));
+            }
+            start = index + 1;
+        }
+
+        if eof {
+            return Ok(None);
+        }
+
+        let keep_from = buffer.len() - WINDOW_SIZE.min(buffer.len());
+        buffer.drain(..keep_from);
+    }
+}
+
+fn read_full<R: Read>(reader: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+fn find_marker(haystack: &[u8]) -> Option<usize> {
+    let marker = MARKER_BYTES.as_slice();
+    if haystack.len() < marker.len() {
+        return None;
+    }
+    haystack.windows(marker.len()).position(|window| window == marker)
+}
+
+fn parse_window(window: &[u8]) -> Option<ReleaseInfo> {
+    let text = String::from_utf16_lossy(
+        &window
+            .chunks_exact(2)
+            .map(|chunk| u16::from_le_bytes([chunk[0], chunk[1]]))
+            .collect::<Vec<u16>>(),
+    );
+
+    parse_release(&text)
+}
+
+/// Parses a `++Fortnite+Release-<release>-CL-<cl>` string, wherever it sits
+/// in `text`.
+pub fn parse_release(text: &str) -> Option<ReleaseInfo> {
+    RELEASE_RE.captures(text).map(|captures| ReleaseInfo {
+        release: captures[1].to_string(),
+        cl: captures[2].to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn utf16(text: &str) -> Vec<u8> {
+        text.encode_utf16().flat_map(|c| c.to_le_bytes()).collect()
+    }
+
+    fn scan(bytes: Vec<u8>) -> Option<ReleaseInfo> {
+        scan_release_string(&mut Cursor::new(bytes)).unwrap()
+    }
+
+    fn release(release: &str, cl: &str) -> Option<ReleaseInfo> {
+        Some(ReleaseInfo {
+            release: release.to_string(),
+            cl: cl.to_string(),
+        })
+    }
+
+    #[test]
+    fn parses_release_strings() {
+        assert_eq!(parse_release("++Fortnite+Release-12.41-CL-12905909"), release("12.41", "12905909"));
+        assert_eq!(parse_release("x ++Fortnite+Release-Live-CL-3700114 y"), release("Live", "3700114"));
+        assert_eq!(parse_release("++Fortnite+Release-Cert-CL-3541083"), release("Cert", "3541083"));
+        assert_eq!(parse_release("++Fortnite+Release-CL-3541083"), None);
+        assert_eq!(parse_release("++Fortnite+Release-123.4-CL-1"), None);
+        assert_eq!(parse_release(""), None);
+    }
+
+    #[test]
+    fn finds_the_build_string_in_a_buffer() {
+        let mut bytes = vec![0u8; 4096];
+        bytes.extend(utf16("++Fortnite+Release-4.5-CL-4159770"));
+        bytes.extend(vec![0u8; 64]);
+        assert_eq!(scan(bytes), release("4.5", "4159770"));
+    }
+
+    #[test]
+    fn finds_a_build_string_split_across_chunks() {
+        let text = utf16("++Fortnite+Release-8.51-CL-6165369");
+        for split in [1, 2, 17, text.len() - 1] {
+            let mut bytes = vec![0u8; CHUNK_SIZE - split];
+            bytes.extend(&text);
+            bytes.extend(vec![0u8; 32]);
+            assert_eq!(scan(bytes), release("8.51", "6165369"), "split at {}", split);
+        }
+    }
+
+    #[test]
+    fn finds_a_build_string_at_the_very_end() {
+        let mut bytes = vec![0u8; CHUNK_SIZE * 2 + 3];
+        bytes.extend(utf16("++Fortnite+Release-Next-CL-1"));
+        assert_eq!(scan(bytes), release("Next", "1"));
+    }
+
+    #[test]
+    fn skips_markers_that_do_not_parse() {
+        let mut bytes = utf16("++Fortnite+Release-garbage");
+        bytes.extend(vec![0u8; 100]);
+        bytes.extend(utf16("++Fortnite+Release-2.4-CL-3870737"));
+        assert_eq!(scan(bytes), release("2.4", "3870737"));
+    }
+
+    #[test]
+    fn ignores_utf8_and_missing_strings() {
+        assert_eq!(scan(b"++Fortnite+Release-4.5-CL-4159770".to_vec()), None);
+        assert_eq!(scan(vec![0xAB; 3 * CHUNK_SIZE / 2]), None);
+        assert_eq!(scan(Vec::new()), None);
+    }
+
+    #[test]
+    fn scans_from_the_start_whatever_the_position() {
+        let mut cursor = Cursor::new(utf16("++Fortnite+Release-1.8-CL-3724489"));
+        cursor.set_position(20);
+        assert_eq!(scan_release_string(&mut cursor).unwrap(), release("1.8", "3724489"));
+    }
+}
@@ -0,0 +1,262 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::error::LauncherError;
+use crate::persist::write_atomic;
+use crate::StoredVersion;
+
+pub const CURRENT_SCHEMA: u64 = 3;
+
+type Migration = fn(Value) -> Result<Value, String>;
+
+// MIGRATIONS[n] turns the `versions` payload of schema n into schema n + 1.
+const MIGRATIONS: &[Migration] = &[migrate_v0_to_v1, migrate_v1_to_v2, migrate_v2_to_v3];
+
+/// Schema 0 is the bare `{ path: StoredVersion }` map written before the
+/// envelope existed; its entries are already in the schema 1 shape.
+fn migrate_v0_to_v1(versions: Value) -> Result<Value, String> {
+    if !versions.is_object() {
+        return Err("Expected a map of versions".to_string());
+    }
+    Ok(versions)
+}
+
+/// Schema 2 drops the inline `data:image/bmp;base64` splash in favour of a
+/// thumbnail cache key. Keys start out empty and are regenerated from each
+/// build's Splash.bmp after load.
+fn migrate_v1_to_v2(mut versions: Value) -> Result<Value, String> {
+    let entries = versions.as_object_mut().ok_or("Expected a map of versions")?;
+    for entry in entries.values_mut() {
+        let entry = entry.as_object_mut().ok_or("Expected a version object")?;
+        entry.remove("splash_image");
+        entry.insert("splash_key".to_string(), Value::Null);
+    }
+    Ok(versions)
+}
+
+/// Schema 3 adds per-build user metadata. Existing builds start with none;
+/// their added date is unknown, so it stays empty.
+fn migrate_v2_to_v3(mut versions: Value) -> Result<Value, String> {
+    let entries = versions.as_object_mut().ok_or("Expected a map of versions")?;
+    for entry in entries.values_mut() {
+        let entry = entry.as_object_mut().ok_or("Expected a version object")?;
+        entry
+            .entry("metadata")
+            .or_insert_with(|| Value::Object(Default::default()));
+    }
+    Ok(versions)
+}
+
+#[derive(Serialize)]
+struct Envelope<'a> {
+    schema: u64,
+    versions: &'a HashMap<String, StoredVersion>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RecoveryNotice {
+    pub backup_path: String,
+    pub error: String,
+}
+
+pub struct LoadedVersions {
+    pub versions: HashMap<String, StoredVersion>,
+    pub recovery: Option<RecoveryNotice>,
+}
+
+/// Parses any known layout of versions.json and returns the versions along
+/// with the schema the file was written in.
+pub fn decode(content: &str) -> Result<(HashMap<String, StoredVersion>, u64), String> {
+    let value: Value = serde_json::from_str(content).map_err(|e| e.to_string())?;
+
+    let (schema, mut payload) = match value {
+        Value::Object(mut object) if object.contains_key("schema") => {
+            let schema = object
+                .get("schema")
+                .and_then(Value::as_u64)
+                .ok_or("schema is not a number")?;
+            let payload = object.remove("versions").ok_or("Missing versions")?;
+            (schema, payload)
+        }
+        Value::Object(object) => (0, Value::Object(object)),
+        _ => return Err("Expected a JSON object".to_string()),
+    };
+
+    if schema > CURRENT_SCHEMA {
+        return Err(format!(
+            "versions.json has schema {} but this launcher only knows up to {}",
+            schema, CURRENT_SCHEMA
+        ));
+    }
+
+    for migration in &MIGRATIONS[schema as usize..] {
+        payload = migration(payload)?;
+    }
+
+    let versions = serde_json::from_value(payload).map_err(|e| e.to_string())?;
+    Ok((versions, schema))
+}
+
+pub fn encode(versions: &HashMap<String, StoredVersion>) -> Result<String, LauncherError> {
+    Ok(serde_json::to_string_pretty(&Envelope {
+        schema: CURRENT_SCHEMA,
+        versions,
+    })?)
+}
+
+/// Loads versions.json. A file that can't be read or migrated is moved aside
+/// to a `.bak` and reported instead of being silently dropped; a file in an
+/// older schema is copied aside before the next save overwrites it.
+pub fn load(path: &Path) -> LoadedVersions {
+    let empty = |recovery| LoadedVersions {
+        versions: HashMap::new(),
+        recovery,
+    };
+
+    if !path.exists() {
+        return empty(None);
+    }
+
+    let result = fs::read_to_string(path)
+        .map_err(|e| e.to_string())
+        .and_then(|content| decode(&content));
+
+    match result {
+        Ok((versions, schema)) => {
+            if schema < CURRENT_SCHEMA {
+                let backup = backup_path(path, &format!("v{}", schema));
+                if let Err(e) = fs::copy(path, &backup) {
+                    println!("Failed to back up {} before migrating: {}", path.display(), e);
+                }
+            }
+            LoadedVersions {
+                versions,
+                recovery: None,
+            }
+        }
+        Err(error) => {
+            let backup = backup_path(path, &unix_now().to_string());
+            println!("Could not load {}: {}. Moving it to {}", path.display(), error, backup.display());
+            if let Err(e) = fs::rename(path, &backup) {
+                println!("Failed to move {} aside: {}", path.display(), e);
+            }
+            empty(Some(RecoveryNotice {
+                backup_path: backup.to_string_lossy().to_string(),
+                error,
+            }))
+        }
+    }
+}
+
+/// Writes versions.json atomically.
+pub fn save(path: &Path, versions: &HashMap<String, StoredVersion>) -> Result<(), LauncherError> {
+    let content = encode(versions)?;
+    write_atomic(path, content).map_err(|e| LauncherError::io("Failed to save versions.json", e))
+}
+
+fn backup_path(path: &Path, tag: &str) -> PathBuf {
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| "versions.json".to_string());
+    path.with_file_name(format!("{}.{}.bak", file_name, tag))
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fs::TempDir;
+
+    macro_rules! fixture {
+        ($name:literal) => {
+            include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/versions/", $name))
+        };
+    }
+
+    const MIGRATED: &str = fixture!("migrated.json");
+
+    fn json(content: &str) -> Value {
+        serde_json::from_str(content).unwrap()
+    }
+
+    #[test]
+    fn migrates_every_old_schema_to_the_golden_file() {
+        for (content, schema) in [(fixture!("v0.json"), 0), (fixture!("v1.json"), 1), (fixture!("v2.json"), 2)] {
+            let (versions, found) = decode(content).unwrap();
+            assert_eq!(found, schema);
+            assert_eq!(json(&encode(&versions).unwrap()), json(MIGRATED), "schema {}", schema);
+        }
+    }
+
+    #[test]
+    fn current_schema_round_trips() {
+        let content = fixture!("v3.json");
+        let (versions, schema) = decode(content).unwrap();
+        assert_eq!(schema, CURRENT_SCHEMA);
+        let encoded = encode(&versions).unwrap();
+        assert_eq!(json(&encoded), json(content));
+
+        let (again, _) = decode(&encoded).unwrap();
+        assert_eq!(json(&encode(&again).unwrap()), json(&encoded));
+    }
+
+    #[test]
+    fn rejects_unknown_layouts() {
+        assert!(decode(r#"{"schema": 4, "versions": {}}"#).unwrap_err().contains("schema 4"));
+        assert!(decode(r#"{"schema": "3", "versions": {}}"#).is_err());
+        assert!(decode(r#"{"schema": 3}"#).is_err());
+        assert!(decode("[]").is_err());
+        assert!(decode(r#"{"C:\\Builds": 5}"#).is_err());
+        assert!(decode("{").is_err());
+    }
+
+    #[test]
+    fn load_backs_up_old_schemas_before_migrating() {
+        let dir = TempDir::new("versions", "backup");
+        let path = dir.join("versions.json");
+        fs::write(&path, fixture!("v0.json")).unwrap();
+
+        let loaded = load(&path);
+        assert_eq!(loaded.versions.len(), 2);
+        assert!(loaded.recovery.is_none());
+        assert_eq!(fs::read_to_string(dir.join("versions.json.v0.bak")).unwrap(), fixture!("v0.json"));
+    }
+
+    #[test]
+    fn load_moves_unreadable_files_aside() {
+        let dir = TempDir::new("versions", "corrupt");
+        let path = dir.join("versions.json");
+        fs::write(&path, "{ not json").unwrap();
+
+        let loaded = load(&path);
+        assert!(loaded.versions.is_empty());
+        let recovery = loaded.recovery.unwrap();
+        assert!(!path.exists());
+        assert_eq!(fs::read_to_string(&recovery.backup_path).unwrap(), "{ not json");
+    }
+
+    #[test]
+    fn save_then_load_round_trips() {
+        let dir = TempDir::new("versions", "save");
+        let path = dir.join("nested").join("versions.json");
+        let (versions, _) = decode(fixture!("v0.json")).unwrap();
+
+        save(&path, &versions).unwrap();
+        assert!(!path.with_extension("json.tmp").exists());
+        let loaded = load(&path);
+        assert!(loaded.recovery.is_none());
+        assert_eq!(json(&encode(&loaded.versions).unwrap()), json(&encode(&versions).unwrap()));
+        assert_eq!(json(&fs::read_to_string(&path).unwrap()), json(MIGRATED));
+    }
+}
@@ -16,9 +16,17 @@ import { ThemeProvider, useTheme } from './contexts/ThemeContext';
 import MusicPlayer from './components/MusicPlayer';
 import { initializeSecurity } from './utils/security';
 import { discordRPC } from './utils/discordRPC';
+import { AccountRegistry } from './utils/accounts';
+import { errorMessage as launcherErrorMessage } from './utils/launcherError';
 
 
 
+interface AuthStatus {
+  state: 'logged_in' | 'refreshing' | 'expired' | 'logged_out';
+  account_id: string | null;
+  expires_at: number | null;
+}
+
 interface VersionCheckResponse {
   needs_update: boolean;
   download_url?: string;
@@ -40,7 +48,6 @@ interface LoginSuccessPayload {
   username: string;
   accountId: string;
   email: string;
-  password: string;
   avatar_url: string;
   favoriteSkin: string;
   role: {
@@ -78,7 +85,6 @@ interface idkprops {
     avatar_url: string;
     accountId: string;
     email: string;
-    password: string;
     favoriteSkin: string;
   };
 }
@@ -90,7 +96,6 @@ const mockUser: LoginSuccessPayload = {
   username: "mars",
   accountId: "mars",
   email: "mars@rewind.co",
-  password: "test",
   avatar_url: "https://cdn.discordapp.com/avatars/803615288549441557/a_0cdd64fd3e86a45719b22404644ec24c?size=1024",
   favoriteSkin: "cid_028_athena_commando_f",
   role: {
@@ -370,6 +375,20 @@ export default function App({userData}: idkprops) {
     }
   };
 
+  const handleBrowserLogin = async () => {
+    setIsFromLogout(false);
+    setLoginStage('loading');
+    try {
+      const url = await invoke<string>('start_loopback_login');
+      await open(url);
+      setLoginStage('waiting');
+    } catch (error) {
+      console.error("could not start browser login:", error);
+      setErrorMessage(launcherErrorMessage(error));
+      setLoginStage('error');
+    }
+  };
+
   const handleCancel = () => {
     setLoginStage('initial');
   };
@@ -389,9 +408,29 @@ export default function App({userData}: idkprops) {
         setLoginStage('error');
       });
 
+      const unlistenAuth = listen<AuthStatus>('auth-state-changed', (event) => {
+        if (event.payload.state === 'expired' || event.payload.state === 'logged_out') {
+          setUser(null);
+          setIsPreparing(false);
+          setLoginStage('initial');
+          navigate('/login');
+        } else if (event.payload.state === 'logged_in' && event.payload.account_id) {
+          const accountId = event.payload.account_id;
+          invoke<AccountRegistry>('list_accounts')
+            .then((registry) => {
+              const account = registry.accounts.find((a) => a.accountId === accountId);
+              if (account) {
+                setUser((current) => (current?.accountId === accountId ? current : account));
+              }
+            })
+            .catch((error) => console.error('could not load accounts:', error));
+        }
+      });
+
       return () => {
         unlisten.then(fn => fn());
         unlistenError.then(fn => fn());
+        unlistenAuth.then(fn => fn());
       };
     }
   }, [navigate, location.pathname]);
@@ -457,6 +496,7 @@ export default function App({userData}: idkprops) {
         isEventsLoading={isEventsLoading}
         handleLogout={handleLogout}
         handleDiscordLogin={handleDiscordLogin}
+        handleBrowserLogin={handleBrowserLogin}
         handleCancel={handleCancel}
         loginStage={loginStage}
         isFromLogout={isFromLogout}
@@ -481,6 +521,7 @@ interface AppContentProps {
   isEventsLoading: boolean;
   handleLogout: () => void;
   handleDiscordLogin: () => void;
+  handleBrowserLogin: () => void;
   handleCancel: () => void;
   loginStage: 'initial' | 'loading' | 'waiting' | 'error';
   isFromLogout: boolean;
@@ -501,6 +542,7 @@ const AppContent: React.FC<AppContentProps> = ({
   isEventsLoading,
   handleLogout,
   handleDiscordLogin,
+  handleBrowserLogin,
   handleCancel,
   loginStage,
   isFromLogout,
@@ -592,6 +634,7 @@ const AppContent: React.FC<AppContentProps> = ({
                       <LoginContainer
                         stage={loginStage}
                         onLogin={handleDiscordLogin}
+                        onBrowserLogin={handleBrowserLogin}
                         onCancel={handleCancel}
                         isFromLogout={isFromLogout}
                         errorMessage={errorMessage}
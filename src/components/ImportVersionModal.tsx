@@ -1,6 +1,7 @@
 import { useState, useEffect } from 'react';
 import { open } from '@tauri-apps/plugin-dialog';
 import { invoke } from '@tauri-apps/api/core';
+import { errorCode, errorMessage } from '../utils/launcherError';
 
 interface ImportVersionModalProps {
   isOpen: boolean;
@@ -11,7 +12,13 @@ interface ImportVersionModalProps {
 interface VersionInfo {
   version: string;
   technical_version: string;
+  splash_key: string;
   splash_image: string;
+  source: 'build_string' | 'version_resource';
+  confidence: 'high' | 'medium' | 'low';
+  product_version: string | null;
+  file_version: string | null;
+  link_timestamp: number | null;
 }
 
 interface Version {
@@ -169,7 +176,11 @@ const ImportVersionModal = ({ isOpen, onClose, onAddVersion }: ImportVersionModa
 
       setVersionInfo(null);
       setStep('select');
-      setError("Version not found. Please try another folder.");
+      setError(
+        errorCode(err) === 'not_found'
+          ? `${errorMessage(err)} Please pick the folder that contains FortniteGame.`
+          : "Version not found. Please try another folder."
+      );
     } finally {
       setIsLoading(false);
     }
@@ -201,7 +212,7 @@ const ImportVersionModal = ({ isOpen, onClose, onAddVersion }: ImportVersionModa
         setError("Failed to add version.");
       }
     } catch (error) {
-      setError(error as string);
+      setError(errorMessage(error));
     } finally {
       setIsLoading(false);
     }
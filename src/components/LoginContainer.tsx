@@ -16,6 +16,7 @@ interface ServerStatus {
 interface LoginContainerProps {
   stage: 'initial' | 'loading' | 'waiting' | 'error';
   onLogin: () => void;
+  onBrowserLogin?: () => void;
   onCancel: () => void;
   isFromLogout?: boolean;
   errorMessage?: string;
@@ -103,7 +104,7 @@ const DownloadContentStage = ({ progress }: { progress: number }) => (
   </div>
 );
 
-export default function LoginContainer({ stage, onLogin, onCancel, isFromLogout = false, errorMessage }: LoginContainerProps) {
+export default function LoginContainer({ stage, onLogin, onBrowserLogin, onCancel, isFromLogout = false, errorMessage }: LoginContainerProps) {
   const [isCheckingUpdates, setIsCheckingUpdates] = useState(true);
   const [showContent, setShowContent] = useState(false);
   const [downloadProgress, setDownloadProgress] = useState(0);
@@ -253,6 +254,14 @@ export default function LoginContainer({ stage, onLogin, onCancel, isFromLogout
                   <span className="mx-auto">Authenticate</span>
                 )}
               </button>
+              {stage === 'waiting' && onBrowserLogin && (
+                <button
+                  className="mt-3 text-white/50 hover:text-white/80 text-sm transition-colors duration-200 font-['Bricolage_Grotesque']"
+                  onClick={onBrowserLogin}
+                >
+                  Browser didn't send you back? Log in another way
+                </button>
+              )}
             </div>
           )}
           {stage === 'error' && <ErrorStage onCancel={onCancel} errorMessage={errorMessage} />}
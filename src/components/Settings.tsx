@@ -4,7 +4,10 @@ import { IoIosLogOut } from "react-icons/io";
 import { IoPersonCircle } from "react-icons/io5";
 import { FaHeart } from "react-icons/fa";
 import { FaPalette } from "react-icons/fa";
+import { invoke } from '@tauri-apps/api/core';
 import { useTheme } from '../contexts/ThemeContext';
+import { AccountRegistry } from '../utils/accounts';
+import { errorMessage } from '../utils/launcherError';
 
 
 const hexToRgba = (hex: string, alpha: number): string => {
@@ -76,6 +79,7 @@ const Settings: React.FC<SettingsProps> = ({ isOpen, onClose, onLogout, initialS
   const [isAnimating, setIsAnimating] = useState(false);
   const [activeSection, setActiveSection] = useState<'profile' | 'themes'>(initialSection);
   const { currentTheme, setTheme, availableThemes } = useTheme();
+  const [registry, setRegistry] = useState<AccountRegistry | null>(null);
 
   useEffect(() => {
     if (isOpen) {
@@ -102,6 +106,38 @@ const Settings: React.FC<SettingsProps> = ({ isOpen, onClose, onLogout, initialS
     setTimeout(onClose, 150);
   };
 
+  const loadAccounts = async () => {
+    try {
+      setRegistry(await invoke<AccountRegistry>('list_accounts'));
+    } catch (error) {
+      console.error('could not load accounts:', errorMessage(error));
+    }
+  };
+
+  useEffect(() => {
+    if (isOpen) {
+      loadAccounts();
+    }
+  }, [isOpen, user.accountId]);
+
+  const handleSwitchAccount = async (accountId: string) => {
+    try {
+      await invoke('switch_account', { accountId });
+    } catch (error) {
+      console.error('could not switch account:', errorMessage(error));
+    }
+    loadAccounts();
+  };
+
+  const handleRemoveAccount = async (accountId: string) => {
+    try {
+      await invoke('remove_account', { accountId });
+    } catch (error) {
+      console.error('could not remove account:', errorMessage(error));
+    }
+    loadAccounts();
+  };
+
   const handleLogout = () => {
     handleClose();
     setTimeout(() => {
@@ -246,6 +282,52 @@ const Settings: React.FC<SettingsProps> = ({ isOpen, onClose, onLogout, initialS
                       </div>
                     </div>
                   </div>
+
+                  {registry && registry.accounts.length > 1 && (
+                    <div className="mt-8">
+                      <h3 className="text-sm font-medium text-white/60 font-['Bricolage_Grotesque'] mb-3">Accounts</h3>
+                      <div className="space-y-2">
+                        {registry.accounts.map((account) => {
+                          const isActive = account.accountId === registry.active;
+                          return (
+                            <div
+                              key={account.accountId}
+                              className={`flex items-center gap-3 rounded-lg px-3 py-2 transition-all duration-200
+                                ${isActive ? 'bg-white/10' : 'hover:bg-white/5'}`}
+                            >
+                              <img
+                                src={account.avatar_url}
+                                alt={`${account.username}'s avatar`}
+                                className="w-8 h-8 rounded-full object-cover"
+                              />
+                              <div className="flex-1 min-w-0">
+                                <p className="text-white text-sm font-['Bricolage_Grotesque'] truncate">{account.username}</p>
+                                <p className="text-xs" style={{ color: account.role.color }}>{account.role.name}</p>
+                              </div>
+                              {isActive ? (
+                                <span className="text-white/40 text-xs font-['Bricolage_Grotesque']">Current</span>
+                              ) : (
+                                <>
+                                  <button
+                                    onClick={() => handleSwitchAccount(account.accountId)}
+                                    className="text-white/60 hover:text-white text-xs font-['Bricolage_Grotesque'] px-2 py-1 rounded hover:bg-white/10"
+                                  >
+                                    Switch
+                                  </button>
+                                  <button
+                                    onClick={() => handleRemoveAccount(account.accountId)}
+                                    className="text-white/60 hover:text-red-500 text-xs font-['Bricolage_Grotesque'] px-2 py-1 rounded hover:bg-red-500/10"
+                                  >
+                                    Remove
+                                  </button>
+                                </>
+                              )}
+                            </div>
+                          );
+                        })}
+                      </div>
+                    </div>
+                  )}
                 </div>
               )}
 
@@ -1,6 +1,6 @@
 import { useState, useEffect, useRef, useContext } from 'react';
 import { open } from "@tauri-apps/plugin-shell";
-import { invoke } from '@tauri-apps/api/core';
+import { invoke, convertFileSrc } from '@tauri-apps/api/core';
 import { useNavigate } from 'react-router-dom';
 import { motion, AnimatePresence } from "framer-motion";
 import * as FaIcons from 'react-icons/fa';
@@ -75,7 +75,6 @@ interface HomeProps {
     accountId: string;
     email: string;
     avatar_url: string;
-    password: string;
     favoriteSkin: string;
     mtxCurrency?: string;
     hype?: string;
@@ -351,7 +350,7 @@ export default function Home({ user, isPreparing, events, isEventsLoading }: Hom
             path: string;
             version: string;
             technical_version: string;
-            splash_image: string;
+            splash_key: string | null;
           }>>('get_versions');
           
           console.log('Available versions:', versions);
@@ -373,14 +372,13 @@ export default function Home({ user, isPreparing, events, isEventsLoading }: Hom
                 version: {
                   version: matchingVersion.version,
                   technical_version: matchingVersion.technical_version,
-                  splash_image: matchingVersion.splash_image,
+                  splash_image: matchingVersion.splash_key ? convertFileSrc(matchingVersion.splash_key, 'thumb') : '',
                   path: matchingVersion.path,
                   access_type: 'public',
                   build_name: matchingVersion.version
                 },
                 user: {
-                  email: user.email,
-                  password: user.password
+                  email: user.email
                 },
                 autoLaunch: true
               }
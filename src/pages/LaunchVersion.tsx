@@ -1,8 +1,53 @@
 import { useLocation, useNavigate } from 'react-router-dom';
 import { useEffect, useState } from 'react';
 import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
 import { FaPlay, FaArrowLeft, FaTimes } from 'react-icons/fa';
 import { discordRPC } from '../utils/discordRPC';
+import { errorCode, errorMessage } from '../utils/launcherError';
+
+interface GameSessionInfo {
+  build: string;
+  pid: number | null;
+  state: 'launching' | 'injecting' | 'running' | 'exited' | 'crashed';
+  code?: number | null;
+}
+
+const isActive = (session: GameSessionInfo) =>
+  ['launching', 'injecting', 'running'].includes(session.state);
+
+interface ProcessStopResult {
+  pid: number;
+  name: string;
+  role: 'client' | 'launcher' | 'anti_cheat' | null;
+  outcome: 'closed' | 'terminated' | 'already_exited' | 'failed';
+  steps: { step: 'close_request' | 'wait' | 'terminate'; ok: boolean; message: string }[];
+}
+
+interface SessionStopResult {
+  session_id: string;
+  build: string;
+  processes: ProcessStopResult[];
+}
+
+interface PreflightCheck {
+  id: string;
+  status: 'pass' | 'warn' | 'fail';
+  message: string;
+}
+
+interface PreflightReport {
+  ok: boolean;
+  offline: boolean;
+  checks: PreflightCheck[];
+}
+
+interface LaunchReport {
+  session_id: string;
+  account_id: string | null;
+  offline: boolean;
+  skipped: { step: string; reason: string }[];
+}
 
 export default function LaunchVersion() {
   const navigate = useNavigate();
@@ -19,15 +64,29 @@ export default function LaunchVersion() {
   const [launchProgress, setLaunchProgress] = useState(0);
   const [isGameRunning, setIsGameRunning] = useState(false);
   const [hasError, setHasError] = useState(false);
-  const [gameMonitorInterval, setGameMonitorInterval] = useState<number | null>(null);
   const [isModalAnimating, setIsModalAnimating] = useState(false);
   const [isModalClosing, setIsModalClosing] = useState(false);
+  const [preflightChecks, setPreflightChecks] = useState<PreflightCheck[]>([]);
 
   useEffect(() => {
     requestAnimationFrame(() => setIsAnimating(true));
     checkGameStatus();
   }, []);
 
+  useEffect(() => {
+    const unlisten = listen<GameSessionInfo>('game-state-changed', (event) => {
+      if (event.payload.build !== version?.path) return;
+      const active = isActive(event.payload);
+      setIsGameRunning(active);
+      if (!active) {
+        discordRPC.setLauncherActivity(user?.avatar_url, user?.username);
+      }
+    });
+    return () => {
+      unlisten.then((stop) => stop());
+    };
+  }, [version?.path]);
+
   useEffect(() => {
     if (showLaunchModal) {
       setIsModalAnimating(true);
@@ -47,10 +106,9 @@ export default function LaunchVersion() {
 
   const checkGameStatus = async () => {
     try {
-      const isRunning = await invoke('is_game_running');
-      if (isRunning) {
+      const sessions = await invoke<GameSessionInfo[]>('get_game_sessions');
+      if (sessions.some((session) => session.build === version?.path && isActive(session))) {
         setIsGameRunning(true);
-        startGameMonitoring();
       }
     } catch (err) {
     }
@@ -65,13 +123,14 @@ export default function LaunchVersion() {
   };
 
   const handleLaunch = async () => {
-    let errorMessages: string[] = [];
-
     if (isGameRunning) {
       try {
-        await invoke('stop_game_process');
+        const results = await invoke<SessionStopResult[]>('stop_game_process', { build: version.path });
+        const failed = results.flatMap((r) => r.processes).filter((p) => p.outcome === 'failed');
+        if (failed.length > 0) {
+          console.error('Some game processes could not be stopped:', failed);
+        }
         setIsGameRunning(false);
-        stopGameMonitoring();
         discordRPC.setLauncherActivity(user?.avatar_url, user?.username);
       } catch (err) {
         console.error('Failed to stop game:', err);
@@ -84,6 +143,7 @@ export default function LaunchVersion() {
     setIsModalClosing(false);
     setHasError(false);
     setLaunchProgress(0);
+    setPreflightChecks([]);
     
     discordRPC.setPlayingActivity(version.version, user?.avatar_url);
 
@@ -100,49 +160,52 @@ export default function LaunchVersion() {
       setLaunchProgress(40);
       await new Promise(resolve => setTimeout(resolve, 1500));
 
-      setLaunchStatus('Verifying files...');
+      setLaunchStatus('Running pre-flight checks...');
       setLaunchProgress(60);
-      await new Promise(resolve => setTimeout(resolve, 1000));
+      const preflight = await invoke<PreflightReport>('preflight', { path: version.path });
+      setPreflightChecks(preflight.checks.filter((check) => check.status !== 'pass'));
+      if (!preflight.ok) {
+        setHasError(true);
+        setLaunchStatus('Pre-flight checks failed');
+        setLaunchProgress(0);
+        discordRPC.setLauncherActivity(user?.avatar_url, user?.username);
+        return;
+      }
 
       setLaunchStatus('Launching game...');
       setLaunchProgress(80);
       await new Promise(resolve => setTimeout(resolve, 1000));
 
-      const { email, password, username, avatar_url } = user;
-      await invoke('version_card_clicked', {
+      const report = await invoke<LaunchReport>('version_card_clicked', {
         path: version.path,
-        email,
-        password,
-        username,
-        avatar_url,
         version: version.version,
       });
     
-      setLaunchStatus('Successfully launched game!');
+      setLaunchStatus(
+        report.offline
+          ? `Launched offline (skipped ${report.skipped.map((s) => s.step.replace(/_/g, ' ')).join(', ')})`
+          : 'Successfully launched game!'
+      );
       setLaunchProgress(100);
       await new Promise(resolve => setTimeout(resolve, 700));
-      try {
-        const isRunning = await invoke('is_game_running');
-        if (isRunning) {
-          setIsGameRunning(true);
-          closeModalWithAnimation();
-          startGameMonitoring();
-
-          discordRPC.setPlayingActivity(version.version, user?.avatar_url);
-        } else {
-          throw new Error('process not detected');
-        }
-      } catch (verifyErr) {
-        setHasError(true);
-        setLaunchStatus(`Error: ${errorMessages.join('; ')}`);
-        setTimeout(() => {
-          closeModalWithAnimation();
-          setHasError(false);
-        }, 3000);
-      }
+      // Whether it is still running from here on arrives as game-state-changed.
+      closeModalWithAnimation();
     } catch (err) {
       setHasError(true);
-      setLaunchStatus(`Error: ${errorMessages.join('; ')}`);
+      switch (errorCode(err)) {
+        case 'auth_expired':
+          setLaunchStatus('Your login has expired. Log in again to play.');
+          break;
+        case 'network_down':
+          setLaunchStatus(`Can't reach Rewind: ${errorMessage(err)}`);
+          break;
+        case 'corrupt_build':
+        case 'not_found':
+          setLaunchStatus(`This build looks broken: ${errorMessage(err)}`);
+          break;
+        default:
+          setLaunchStatus(`Error: ${errorMessage(err)}`);
+      }
       setLaunchProgress(0);
 
       setTimeout(() => {
@@ -154,39 +217,6 @@ export default function LaunchVersion() {
     }
   };
 
-  const startGameMonitoring = () => {
-    if (gameMonitorInterval) {
-      clearInterval(gameMonitorInterval);
-    }
-
-   const interval = window.setInterval(async () => {
-    try {
-    const isRunning = await invoke('is_game_running');
-    if (!isRunning && isGameRunning) {
-      setIsGameRunning(false);
-      stopGameMonitoring();
-    }
-     } catch (err) {
-    }
-    }, 3000);
-
-setGameMonitorInterval(interval);
-
-  };
-
-  const stopGameMonitoring = () => {
-    if (gameMonitorInterval) {
-      clearInterval(gameMonitorInterval);
-      setGameMonitorInterval(null);
-    }
-  };
-
-  useEffect(() => {
-    return () => {
-      stopGameMonitoring();
-    };
-  }, [gameMonitorInterval]);
-
   const closeModalWithAnimation = () => {
     setIsModalClosing(true);
     setTimeout(() => {
@@ -347,6 +377,28 @@ setGameMonitorInterval(interval);
                 </span>
               </div>
 
+              {preflightChecks.length > 0 && (
+                <ul className="space-y-1">
+                  {preflightChecks.map((check) => (
+                    <li key={check.id} className="flex items-start gap-2 text-xs">
+                      <span className={check.status === 'fail' ? 'text-red-400' : 'text-yellow-400'}>
+                        {check.status === 'fail' ? 'FAIL' : 'WARN'}
+                      </span>
+                      <span className="text-gray-300">{check.message}</span>
+                    </li>
+                  ))}
+                </ul>
+              )}
+
+              {hasError && preflightChecks.some((check) => check.status === 'fail') && (
+                <button
+                  onClick={closeModalWithAnimation}
+                  className="text-xs text-gray-400 hover:text-white transition-colors"
+                >
+                  Close
+                </button>
+              )}
+
               {!hasError && (
                 <div className="w-full bg-gray-700 h-0.5">
                   <div
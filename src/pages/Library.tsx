@@ -244,7 +244,6 @@ interface HomeProps {
     avatar_url: string;
     accountId: string;
     email: string;
-    password: string;
     favoriteSkin: string;
   };
 }
@@ -344,7 +343,7 @@ export default function Library({ user }: HomeProps) {
   }, [cardId, isGrid, isListEntering, hasAnimatedGrid, hasAnimatedList]);
 
 const handleCardClick = () => {
-  const { email, password, username, avatar_url } = user;
+  const { email, username, avatar_url } = user;
 
   discordRPC.setPlayingActivity(version, user?.avatar_url, user?.username);
 
@@ -360,7 +359,6 @@ const handleCardClick = () => {
       },
       user: {
         email,
-        password,
         username,
         avatar_url
       }
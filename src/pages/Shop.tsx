@@ -19,7 +19,6 @@ interface ShopProps {
     avatar_url: string;
     accountId: string;
     email: string;
-    password: string;
     favoriteSkin: string;
     mtxCurrency?: string;
   };
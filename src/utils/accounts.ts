@@ -0,0 +1,20 @@
+// An account that has logged in on this machine, as `list_accounts` returns
+// it. Same shape as the `login-success` payload, plus bookkeeping.
+export interface Account {
+  accountId: string;
+  username: string;
+  email: string;
+  avatar_url: string;
+  favoriteSkin: string;
+  role: {
+    name: string;
+    color: string;
+  };
+  added_at: number;
+  last_used_at: number | null;
+}
+
+export interface AccountRegistry {
+  active: string | null;
+  accounts: Account[];
+}
@@ -0,0 +1,38 @@
+// Shape of every error a Tauri command rejects with. `code` is stable and
+// safe to branch on; `message` is for people and may change.
+export type LauncherErrorCode =
+  | 'auth_expired'
+  | 'network_down'
+  | 'api_error'
+  | 'not_found'
+  | 'permission_denied'
+  | 'corrupt_build'
+  | 'already_running'
+  | 'invalid_input'
+  | 'launch_failed'
+  | 'io'
+  | 'http'
+  | 'parse'
+  | 'regex'
+  | 'cancelled'
+  | 'timeout'
+  | 'keyring'
+  | 'internal';
+
+export interface LauncherError {
+  code: LauncherErrorCode;
+  message: string;
+  details: string | null;
+}
+
+export const isLauncherError = (err: unknown): err is LauncherError =>
+  typeof err === 'object' && err !== null && 'code' in err && 'message' in err;
+
+export const errorMessage = (err: unknown): string => {
+  if (isLauncherError(err)) return err.message;
+  if (err instanceof Error) return err.message;
+  return String(err);
+};
+
+export const errorCode = (err: unknown): LauncherErrorCode | null =>
+  isLauncherError(err) ? err.code : null;